@@ -11,13 +11,22 @@ macro_rules! verification_error {
     }
 }
 
+/// The reasons why the data isn't a valid molecule encoding.
+///
+/// More rules could be verified by the generated code in the future, so it's non-exhaustive.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum VerificationError {
     TotalSizeNotMatch(String, usize, usize),
     HeaderIsBroken(String, usize, usize),
     UnknownItem(String, usize, Number),
     OffsetsNotMatch(String),
     FieldCountNotMatch(String, usize, usize),
+    /// The items of a set or a map aren't in ascending order, or the item at the index is a
+    /// duplicate of the previous one.
+    ItemsNotSorted(String, usize),
+    /// The bytes of a string aren't valid UTF-8, only the bytes before the index are valid.
+    InvalidUtf8(String, usize),
 }
 
 pub type VerificationResult<T> = result::Result<T, VerificationError>;
@@ -56,6 +65,20 @@ impl fmt::Display for VerificationError {
                     st, expected, actual
                 )?;
             }
+            VerificationError::ItemsNotSorted(st, index) => {
+                write!(
+                    f,
+                    "{} items are not sorted or not unique, at index {}",
+                    st, index
+                )?;
+            }
+            VerificationError::InvalidUtf8(st, valid_up_to) => {
+                write!(
+                    f,
+                    "{} is not valid utf-8, valid up to {} bytes",
+                    st, valid_up_to
+                )?;
+            }
         }
         Ok(())
     }
@@ -1,3 +1,5 @@
+use std::{env, fs, path::Path};
+
 use codegen::{Compiler, Language};
 
 fn compile_schema(schema: &str) {
@@ -8,10 +10,13 @@ fn compile_schema(schema: &str) {
         .output_dir_set_default()
         .run()
         .unwrap();
+    // The C headers are in a sub-directory, so `strings.h` doesn't shadow the system header.
+    let c_dir = Path::new(&env::var("OUT_DIR").unwrap()).join("c");
+    fs::create_dir_all(&c_dir).unwrap();
     compiler
         .input_schema_file(schema)
         .generate_code(Language::C)
-        .output_dir_set_default()
+        .output_dir(c_dir)
         .run()
         .unwrap();
     println!("cargo:rerun-if-changed={}", schema);
@@ -20,10 +25,22 @@ fn compile_schema(schema: &str) {
 fn main() {
     println!("cargo:rerun-if-changed=../../test/vectors");
     println!("cargo:rerun-if-changed=src/capi.c");
+    println!("cargo:rerun-if-changed=src/capi_collections.c");
+    println!("cargo:rerun-if-changed=src/capi_strings.c");
+    println!("cargo:rerun-if-changed=src/capi_reserved.c");
+    println!("cargo:rerun-if-changed=src/capi_union_variants.c");
     compile_schema("../../test/schemas/types.mol");
+    compile_schema("../../tests/collections.mol");
+    compile_schema("../../tests/strings.mol");
+    compile_schema("../../tests/reserved.mol");
+    compile_schema("../../tests/union_variants.mol");
     let out_dir = ::std::env::var("OUT_DIR").unwrap();
     cc::Build::new()
         .file("src/capi.c")
+        .file("src/capi_collections.c")
+        .file("src/capi_strings.c")
+        .file("src/capi_reserved.c")
+        .file("src/capi_union_variants.c")
         .include(&out_dir)
         .include("../../bindings/c/include")
         .warnings(false)
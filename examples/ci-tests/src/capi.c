@@ -1,4 +1,4 @@
-#include "types.h"
+#include "c/types.h"
 
 uint32_t tablea_verify(uint8_t *data, uint32_t data_len) {
     mol_seg_t input;
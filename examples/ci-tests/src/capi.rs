@@ -1,9 +1,59 @@
 mod ffi {
     extern "C" {
         pub(super) fn tablea_verify(data: *const u8, data_len: u32) -> u32;
+        pub(super) fn uint32set_verify(data: *const u8, data_len: u32) -> u32;
+        pub(super) fn bytesset_verify(data: *const u8, data_len: u32) -> u32;
+        pub(super) fn uint32map_verify(data: *const u8, data_len: u32) -> u32;
+        pub(super) fn bytesmap_verify(data: *const u8, data_len: u32) -> u32;
+        pub(super) fn name_verify(data: *const u8, data_len: u32) -> u32;
+        pub(super) fn person_verify(data: *const u8, data_len: u32) -> u32;
+        pub(super) fn book_verify(data: *const u8, data_len: u32) -> u32;
+        pub(super) fn reserved_message_verify(data: *const u8, data_len: u32) -> u32;
+        pub(super) fn config_verify(data: *const u8, data_len: u32) -> u32;
+        pub(super) fn union_variants_message_verify(data: *const u8, data_len: u32) -> u32;
     }
 }
 
 pub fn tablea_verify(input: &[u8]) -> bool {
     unsafe { ffi::tablea_verify(input.as_ptr(), input.len() as u32) == 0 }
 }
+
+pub fn uint32set_verify(input: &[u8]) -> bool {
+    unsafe { ffi::uint32set_verify(input.as_ptr(), input.len() as u32) == 0 }
+}
+
+pub fn bytesset_verify(input: &[u8]) -> bool {
+    unsafe { ffi::bytesset_verify(input.as_ptr(), input.len() as u32) == 0 }
+}
+
+pub fn uint32map_verify(input: &[u8]) -> bool {
+    unsafe { ffi::uint32map_verify(input.as_ptr(), input.len() as u32) == 0 }
+}
+
+pub fn bytesmap_verify(input: &[u8]) -> bool {
+    unsafe { ffi::bytesmap_verify(input.as_ptr(), input.len() as u32) == 0 }
+}
+
+pub fn name_verify(input: &[u8]) -> bool {
+    unsafe { ffi::name_verify(input.as_ptr(), input.len() as u32) == 0 }
+}
+
+pub fn person_verify(input: &[u8]) -> bool {
+    unsafe { ffi::person_verify(input.as_ptr(), input.len() as u32) == 0 }
+}
+
+pub fn book_verify(input: &[u8]) -> bool {
+    unsafe { ffi::book_verify(input.as_ptr(), input.len() as u32) == 0 }
+}
+
+pub fn reserved_message_verify(input: &[u8]) -> bool {
+    unsafe { ffi::reserved_message_verify(input.as_ptr(), input.len() as u32) == 0 }
+}
+
+pub fn config_verify(input: &[u8]) -> bool {
+    unsafe { ffi::config_verify(input.as_ptr(), input.len() as u32) == 0 }
+}
+
+pub fn union_variants_message_verify(input: &[u8]) -> bool {
+    unsafe { ffi::union_variants_message_verify(input.as_ptr(), input.len() as u32) == 0 }
+}
@@ -0,0 +1,35 @@
+// The API of each schema file is static, since the schema files declare the same names.
+#define MOLECULE_API_DECORATOR static
+#include "c/collections.h"
+
+uint32_t uint32set_verify(uint8_t *data, uint32_t data_len) {
+    mol_seg_t input;
+    input.ptr = data;
+    input.size = data_len;
+    mol_errno result = MolReader_Uint32Set_verify(&input, false);
+    return result == MOL_OK ? 0 : 1;
+}
+
+uint32_t bytesset_verify(uint8_t *data, uint32_t data_len) {
+    mol_seg_t input;
+    input.ptr = data;
+    input.size = data_len;
+    mol_errno result = MolReader_BytesSet_verify(&input, false);
+    return result == MOL_OK ? 0 : 1;
+}
+
+uint32_t uint32map_verify(uint8_t *data, uint32_t data_len) {
+    mol_seg_t input;
+    input.ptr = data;
+    input.size = data_len;
+    mol_errno result = MolReader_Uint32Map_verify(&input, false);
+    return result == MOL_OK ? 0 : 1;
+}
+
+uint32_t bytesmap_verify(uint8_t *data, uint32_t data_len) {
+    mol_seg_t input;
+    input.ptr = data;
+    input.size = data_len;
+    mol_errno result = MolReader_BytesMap_verify(&input, false);
+    return result == MOL_OK ? 0 : 1;
+}
@@ -0,0 +1,18 @@
+#define MOLECULE_API_DECORATOR static
+#include "c/reserved.h"
+
+uint32_t reserved_message_verify(uint8_t *data, uint32_t data_len) {
+    mol_seg_t input;
+    input.ptr = data;
+    input.size = data_len;
+    mol_errno result = MolReader_Message_verify(&input, false);
+    return result == MOL_OK ? 0 : 1;
+}
+
+uint32_t config_verify(uint8_t *data, uint32_t data_len) {
+    mol_seg_t input;
+    input.ptr = data;
+    input.size = data_len;
+    mol_errno result = MolReader_Config_verify(&input, false);
+    return result == MOL_OK ? 0 : 1;
+}
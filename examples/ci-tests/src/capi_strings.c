@@ -0,0 +1,26 @@
+#define MOLECULE_API_DECORATOR static
+#include "c/strings.h"
+
+uint32_t name_verify(uint8_t *data, uint32_t data_len) {
+    mol_seg_t input;
+    input.ptr = data;
+    input.size = data_len;
+    mol_errno result = MolReader_Name_verify(&input, false);
+    return result == MOL_OK ? 0 : 1;
+}
+
+uint32_t person_verify(uint8_t *data, uint32_t data_len) {
+    mol_seg_t input;
+    input.ptr = data;
+    input.size = data_len;
+    mol_errno result = MolReader_Person_verify(&input, false);
+    return result == MOL_OK ? 0 : 1;
+}
+
+uint32_t book_verify(uint8_t *data, uint32_t data_len) {
+    mol_seg_t input;
+    input.ptr = data;
+    input.size = data_len;
+    mol_errno result = MolReader_Book_verify(&input, false);
+    return result == MOL_OK ? 0 : 1;
+}
@@ -0,0 +1,10 @@
+#define MOLECULE_API_DECORATOR static
+#include "c/union_variants.h"
+
+uint32_t union_variants_message_verify(uint8_t *data, uint32_t data_len) {
+    mol_seg_t input;
+    input.ptr = data;
+    input.size = data_len;
+    mol_errno result = MolReader_Message_verify(&input, false);
+    return result == MOL_OK ? 0 : 1;
+}
@@ -8,6 +8,26 @@ pub mod types {
     include!(concat!(env!("OUT_DIR"), "/", "types", ".rs"));
 }
 
+pub mod collections {
+    #![allow(clippy::all)]
+    include!(concat!(env!("OUT_DIR"), "/", "collections", ".rs"));
+}
+
+pub mod strings {
+    #![allow(clippy::all)]
+    include!(concat!(env!("OUT_DIR"), "/", "strings", ".rs"));
+}
+
+pub mod reserved {
+    #![allow(clippy::all)]
+    include!(concat!(env!("OUT_DIR"), "/", "reserved", ".rs"));
+}
+
+pub mod union_variants {
+    #![allow(clippy::all)]
+    include!(concat!(env!("OUT_DIR"), "/", "union_variants", ".rs"));
+}
+
 #[macro_export]
 macro_rules! testset {
     (array, $callback:ident) => {
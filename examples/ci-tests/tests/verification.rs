@@ -0,0 +1,187 @@
+use molecule::prelude::*;
+
+use molecule_ci_tests::{capi, collections, reserved, strings, union_variants};
+
+fn fixvec(items: &[&[u8]]) -> Vec<u8> {
+    let mut data = molecule::pack_number(items.len() as molecule::Number).to_vec();
+    for item in items {
+        data.extend_from_slice(item);
+    }
+    data
+}
+
+fn bytes(data: &[u8]) -> Vec<u8> {
+    let mut bytes = molecule::pack_number(data.len() as molecule::Number).to_vec();
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+fn dynvec(items: &[&[u8]]) -> Vec<u8> {
+    let header_size = molecule::NUMBER_SIZE * (items.len() + 1);
+    let total_size = header_size + items.iter().map(|item| item.len()).sum::<usize>();
+    let mut data = molecule::pack_number(total_size as molecule::Number).to_vec();
+    let mut offset = header_size;
+    for item in items {
+        data.extend_from_slice(&molecule::pack_number(offset as molecule::Number));
+        offset += item.len();
+    }
+    for item in items {
+        data.extend_from_slice(item);
+    }
+    data
+}
+
+fn union(item_id: molecule::Number, item: &[u8]) -> Vec<u8> {
+    let mut data = molecule::pack_number(item_id).to_vec();
+    data.extend_from_slice(item);
+    data
+}
+
+#[test]
+fn verify_sets() {
+    use collections::*;
+
+    let uint32 = |v: u32| v.to_be_bytes();
+    let (one, two) = (uint32(1), uint32(2));
+
+    let sorted = fixvec(&[&one, &two]);
+    assert!(Uint32SetReader::verify(&sorted, false).is_ok());
+    assert!(capi::uint32set_verify(&sorted));
+    let built = Uint32Set::new_builder()
+        .push(Uint32::new_unchecked(two.to_vec().into()))
+        .push(Uint32::new_unchecked(one.to_vec().into()))
+        .build();
+    assert_eq!(built.as_slice(), &sorted[..]);
+
+    for data in &[fixvec(&[&two, &one]), fixvec(&[&one, &one])] {
+        assert!(Uint32SetReader::verify(data, false).is_err());
+        assert!(!capi::uint32set_verify(data));
+    }
+
+    let (a, b, ab) = (bytes(b"a"), bytes(b"b"), bytes(b"ab"));
+    let sorted = dynvec(&[&a, &b, &ab]);
+    assert!(BytesSetReader::verify(&sorted, false).is_ok());
+    assert!(capi::bytesset_verify(&sorted));
+    for data in &[dynvec(&[&b, &a]), dynvec(&[&ab, &a]), dynvec(&[&a, &a])] {
+        assert!(BytesSetReader::verify(data, false).is_err());
+        assert!(!capi::bytesset_verify(data));
+    }
+}
+
+#[test]
+fn verify_maps() {
+    use collections::*;
+
+    let entry = |key: u32, value: u32| {
+        let mut data = key.to_be_bytes().to_vec();
+        data.extend_from_slice(&value.to_be_bytes());
+        data
+    };
+    let (one, two, other_two) = (entry(1, 10), entry(2, 20), entry(2, 21));
+
+    let sorted = fixvec(&[&one, &two]);
+    assert!(Uint32MapReader::verify(&sorted, false).is_ok());
+    assert!(capi::uint32map_verify(&sorted));
+    for data in &[fixvec(&[&two, &one]), fixvec(&[&two, &other_two])] {
+        assert!(Uint32MapReader::verify(data, false).is_err());
+        assert!(!capi::uint32map_verify(data));
+    }
+
+    let entry = |key: &[u8], value: u32| dynvec(&[&bytes(key), &value.to_le_bytes()]);
+    let (a, b, other_b) = (entry(b"a", 1), entry(b"b", 2), entry(b"b", 3));
+    let sorted = dynvec(&[&a, &b]);
+    assert!(BytesMapReader::verify(&sorted, false).is_ok());
+    assert!(capi::bytesmap_verify(&sorted));
+    for data in &[dynvec(&[&b, &a]), dynvec(&[&b, &other_b])] {
+        assert!(BytesMapReader::verify(data, false).is_err());
+        assert!(!capi::bytesmap_verify(data));
+    }
+}
+
+#[test]
+fn verify_strings() {
+    use strings::*;
+
+    let valid = bytes("名字".as_bytes());
+    assert!(NameReader::verify(&valid, false).is_ok());
+    assert!(capi::name_verify(&valid));
+    assert_eq!(Name::from_slice(&valid).unwrap().as_str(), Ok("名字"));
+
+    // an overlong encoding, a truncated sequence and a surrogate
+    let invalid: [&[u8]; 3] = [&[0xc0, 0xaf], &[0xe5, 0x90], &[0xed, 0xa0, 0x80]];
+    for invalid in &invalid {
+        let data = bytes(invalid);
+        assert!(NameReader::verify(&data, false).is_err());
+        assert!(!capi::name_verify(&data));
+    }
+
+    let person = Person::new_builder()
+        .name(Name::from("Alice"))
+        .aliases(NameVec::new_builder().push(Name::from("Al")).build())
+        .build();
+    assert!(capi::person_verify(person.as_slice()));
+    let person = person
+        .as_builder()
+        .nickname(
+            NameOpt::new_builder()
+                .set(Some(Name::from(vec![0xff])))
+                .build(),
+        )
+        .build();
+    assert!(PersonReader::verify(person.as_slice(), false).is_err());
+    assert!(!capi::person_verify(person.as_slice()));
+
+    let book = Book::new_builder()
+        .title(String::from("Molecule"))
+        .authors(
+            StringVec::new_builder()
+                .push(String::from("Nervos"))
+                .build(),
+        )
+        .build();
+    assert!(capi::book_verify(book.as_slice()));
+    let book = book
+        .as_builder()
+        .authors(
+            StringVec::new_builder()
+                .push(String::from(vec![0x80]))
+                .build(),
+        )
+        .build();
+    assert!(BookReader::verify(book.as_slice(), false).is_err());
+    assert!(!capi::book_verify(book.as_slice()));
+}
+
+#[test]
+fn verify_reserved() {
+    use reserved::*;
+
+    let uint32 = 7u32.to_le_bytes();
+    let valid = union(1, &uint32);
+    assert!(MessageReader::verify(&valid, false).is_ok());
+    assert!(capi::reserved_message_verify(&valid));
+    for item_id in &[2, 3, 5] {
+        let data = union(*item_id, &uint32);
+        assert!(MessageReader::verify(&data, false).is_err());
+        assert!(!capi::reserved_message_verify(&data));
+    }
+
+    let config = Config::default();
+    assert!(capi::config_verify(config.as_slice()));
+}
+
+#[test]
+fn verify_union_variants() {
+    use union_variants::*;
+
+    for (item_id, size) in &[(1, 1), (2, 1), (3, 4), (4, 32)] {
+        let data = union(*item_id, &vec![0; *size]);
+        assert!(MessageReader::verify(&data, false).is_ok());
+        assert!(capi::union_variants_message_verify(&data));
+    }
+    for (item_id, size) in &[(0, 1), (5, 1), (3, 1)] {
+        let data = union(*item_id, &vec![0; *size]);
+        assert!(MessageReader::verify(&data, false).is_err());
+        assert!(!capi::union_variants_message_verify(&data));
+    }
+}
@@ -16,7 +16,7 @@ fn compile_schema_rust(schema: &str) {
         .output_dir(out_dir)
         .run()
         .unwrap();
-    file_path.push("types.rs");
+    file_path.push(module_file(schema));
     Command::new("rustfmt")
         .arg(<PathBuf as AsRef<OsStr>>::as_ref(&file_path))
         .spawn()
@@ -38,7 +38,7 @@ fn compile_schema_rust_lazy_reader(schema: &str) {
         .output_dir(out_dir)
         .run()
         .unwrap();
-    file_path.push("types.rs");
+    file_path.push(module_file(schema));
     Command::new("rustfmt")
         .arg(<PathBuf as AsRef<OsStr>>::as_ref(&file_path))
         .spawn()
@@ -48,7 +48,25 @@ fn compile_schema_rust_lazy_reader(schema: &str) {
     println!("cargo:rerun-if-changed={}", schema);
 }
 
+/// The generated file of the schema file, which is named after it.
+fn module_file(schema: &str) -> String {
+    let name = path::Path::new(schema)
+        .file_stem()
+        .unwrap()
+        .to_str()
+        .unwrap();
+    format!("{}.rs", name)
+}
+
 fn main() {
     compile_schema_rust("../../test/schemas/types.mol");
     compile_schema_rust_lazy_reader("../../test/schemas/types.mol");
+    for schema in &[
+        "../../tests/collections.mol",
+        "../../tests/strings.mol",
+        "../../tests/reserved.mol",
+        "../../tests/union_variants.mol",
+    ] {
+        compile_schema_rust_lazy_reader(schema);
+    }
 }
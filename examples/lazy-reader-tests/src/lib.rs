@@ -1,6 +1,7 @@
 pub mod types_api;
 pub mod types_api2;
 
+pub mod schemas;
 pub mod types_all_data;
 pub mod types_api2_mol2;
 pub mod types_array;
@@ -0,0 +1,140 @@
+// The schemas which are verified by the lazy reader.
+pub mod collections {
+    include!(concat!(env!("OUT_DIR"), "/lazy_reader/collections.rs"));
+}
+pub mod strings {
+    include!(concat!(env!("OUT_DIR"), "/lazy_reader/strings.rs"));
+}
+pub mod reserved {
+    include!(concat!(env!("OUT_DIR"), "/lazy_reader/reserved.rs"));
+}
+pub mod union_variants {
+    include!(concat!(env!("OUT_DIR"), "/lazy_reader/union_variants.rs"));
+}
+
+#[cfg(test)]
+fn fixvec(items: &[&[u8]]) -> Vec<u8> {
+    let mut data = molecule::pack_number(items.len() as molecule::Number).to_vec();
+    for item in items {
+        data.extend_from_slice(item);
+    }
+    data
+}
+
+#[cfg(test)]
+fn bytes(data: &[u8]) -> Vec<u8> {
+    let mut bytes = molecule::pack_number(data.len() as molecule::Number).to_vec();
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+#[cfg(test)]
+fn dynvec(items: &[&[u8]]) -> Vec<u8> {
+    let header_size = molecule::NUMBER_SIZE * (items.len() + 1);
+    let total_size = header_size + items.iter().map(|item| item.len()).sum::<usize>();
+    let mut data = molecule::pack_number(total_size as molecule::Number).to_vec();
+    let mut offset = header_size;
+    for item in items {
+        data.extend_from_slice(&molecule::pack_number(offset as molecule::Number));
+        offset += item.len();
+    }
+    for item in items {
+        data.extend_from_slice(item);
+    }
+    data
+}
+
+#[cfg(test)]
+fn union(item_id: molecule::Number, item: &[u8]) -> Vec<u8> {
+    let mut data = molecule::pack_number(item_id).to_vec();
+    data.extend_from_slice(item);
+    data
+}
+
+#[test]
+fn test_verify_ordering() {
+    use collections::*;
+    use molecule::lazy_reader::Cursor;
+
+    let uint32 = |v: u32| v.to_be_bytes();
+    let (one, two) = (uint32(1), uint32(2));
+    let verify = |data: &[u8]| Uint32Set::from(Cursor::from(data.to_vec())).verify(false);
+    assert!(verify(&fixvec(&[&one, &two])).is_ok());
+    assert!(verify(&fixvec(&[&two, &one])).is_err());
+    assert!(verify(&fixvec(&[&one, &one])).is_err());
+
+    let (a, b, ab) = (bytes(b"a"), bytes(b"b"), bytes(b"ab"));
+    let verify = |data: &[u8]| BytesSet::from(Cursor::from(data.to_vec())).verify(false);
+    assert!(verify(&dynvec(&[&a, &b, &ab])).is_ok());
+    assert!(verify(&dynvec(&[&b, &a])).is_err());
+    assert!(verify(&dynvec(&[&ab, &a])).is_err());
+    assert!(verify(&dynvec(&[&a, &a])).is_err());
+
+    let entry = |key: u32, value: u32| [key.to_be_bytes(), value.to_be_bytes()].concat();
+    let (one, two, other_two) = (entry(1, 10), entry(2, 20), entry(2, 21));
+    let verify = |data: &[u8]| Uint32Map::from(Cursor::from(data.to_vec())).verify(false);
+    assert!(verify(&fixvec(&[&one, &two])).is_ok());
+    assert!(verify(&fixvec(&[&two, &one])).is_err());
+    assert!(verify(&fixvec(&[&two, &other_two])).is_err());
+
+    let entry = |key: &[u8], value: u32| dynvec(&[&bytes(key), &value.to_le_bytes()]);
+    let (a, b, other_b) = (entry(b"a", 1), entry(b"b", 2), entry(b"b", 3));
+    let verify = |data: &[u8]| BytesMap::from(Cursor::from(data.to_vec())).verify(false);
+    assert!(verify(&dynvec(&[&a, &b])).is_ok());
+    assert!(verify(&dynvec(&[&b, &a])).is_err());
+    assert!(verify(&dynvec(&[&b, &other_b])).is_err());
+}
+
+#[test]
+fn test_verify_utf8() {
+    use molecule::lazy_reader::Cursor;
+    use strings::*;
+
+    let verify = |data: &[u8]| Name::from(Cursor::from(data.to_vec())).verify(false);
+    assert!(verify(&bytes("名字".as_bytes())).is_ok());
+    // an overlong encoding, a truncated sequence and a surrogate
+    let invalid: [&[u8]; 3] = [&[0xc0, 0xaf], &[0xe5, 0x90], &[0xed, 0xa0, 0x80]];
+    for invalid in &invalid {
+        assert!(verify(&bytes(invalid)).is_err());
+    }
+
+    let verify = |data: &[u8]| NameVec::from(Cursor::from(data.to_vec())).verify(false);
+    assert!(verify(&dynvec(&[&bytes(b"Al"), &bytes(b"Bob")])).is_ok());
+    assert!(verify(&dynvec(&[&bytes(b"Al"), &bytes(&[0xff])])).is_err());
+
+    // the fields of a table: name, nickname and aliases
+    let person = |name: &[u8]| dynvec(&[&bytes(name), &[], &dynvec(&[])]);
+    let verify = |data: &[u8]| Person::from(Cursor::from(data.to_vec())).verify(false);
+    assert!(verify(&person(b"Alice")).is_ok());
+    assert!(verify(&person(&[0x80])).is_err());
+
+    let book = |author: &[u8]| dynvec(&[&bytes(b"Molecule"), &dynvec(&[&bytes(author)])]);
+    let verify = |data: &[u8]| Book::from(Cursor::from(data.to_vec())).verify(false);
+    assert!(verify(&book(b"Nervos")).is_ok());
+    assert!(verify(&book(&[0xc0, 0xaf])).is_err());
+}
+
+#[test]
+fn test_verify_union_items() {
+    use molecule::lazy_reader::Cursor;
+
+    let verify = |data: Vec<u8>| {
+        reserved::Message::try_from(Cursor::from(data)).and_then(|message| message.verify(false))
+    };
+    assert!(verify(union(1, &7u32.to_le_bytes())).is_ok());
+    assert!(verify(union(4, &bytes(b"data"))).is_ok());
+    for item_id in &[2, 3, 5] {
+        assert!(verify(union(*item_id, &7u32.to_le_bytes())).is_err());
+    }
+
+    let verify = |data: Vec<u8>| {
+        union_variants::Message::try_from(Cursor::from(data))
+            .and_then(|message| message.verify(false))
+    };
+    for (item_id, size) in &[(1, 1), (2, 1), (3, 4), (4, 32)] {
+        assert!(verify(union(*item_id, &vec![0; *size])).is_ok());
+    }
+    for item_id in &[0, 5] {
+        assert!(verify(union(*item_id, &[0])).is_err());
+    }
+}
@@ -54,13 +54,131 @@ fn compile_intermediate_dev(schema: &str) {
     println!("cargo:rerun-if-changed={}", schema);
 }
 
+fn compile_latest_intermediate_dev(schema: &str) {
+    let out_dir = std::path::PathBuf::from(&std::env::var("OUT_DIR").unwrap())
+        .join("dev")
+        .join("latest");
+    std::fs::create_dir_all(&out_dir).unwrap();
+
+    let mut compiler = codegen_dev::Compiler::new();
+    compiler
+        .input_schema_file(schema)
+        .generate_intermediate(codegen_dev::IntermediateFormat::JSON)
+        .intermediate_format_version(codegen_dev::ir::Ir::FORMAT_VERSION)
+        .output_dir(out_dir)
+        .run()
+        .unwrap();
+    println!("cargo:rerun-if-changed={}", schema);
+}
+
+fn compile_only_types_dev(schema: &str, types: &[&str]) {
+    let out_dir = std::path::PathBuf::from(&std::env::var("OUT_DIR").unwrap()).join("dev");
+    std::fs::create_dir_all(&out_dir).unwrap();
+
+    let mut compiler = codegen_dev::Compiler::new();
+    compiler
+        .input_schema_file(schema)
+        .only_types(types)
+        .output_dir(&out_dir);
+    compiler
+        .generate_code(codegen_dev::Language::Rust)
+        .run()
+        .unwrap();
+    compiler
+        .generate_intermediate(codegen_dev::IntermediateFormat::JSON)
+        .run()
+        .unwrap();
+    println!("cargo:rerun-if-changed={}", schema);
+}
+
+fn compile_with_options_dev(
+    schema: &str,
+    dir: &str,
+    language: codegen_dev::Language,
+    options: &codegen_dev::GeneratorOptions,
+) {
+    let out_dir = std::path::PathBuf::from(&std::env::var("OUT_DIR").unwrap()).join(dir);
+    std::fs::create_dir_all(&out_dir).unwrap();
+
+    let mut compiler = codegen_dev::Compiler::new();
+    compiler
+        .input_schema_file(schema)
+        .generate_code(language)
+        .generator_options(options.clone())
+        .output_dir(out_dir)
+        .run()
+        .unwrap();
+    println!("cargo:rerun-if-changed={}", schema);
+}
+
 fn main() {
     println!("cargo:rerun-if-changed=./union_foo_0_7_3.mol");
     println!("cargo:rerun-if-changed=./union_foo_with_custom_id.mol");
+    println!("cargo:rerun-if-changed=./collections.mol");
+    println!("cargo:rerun-if-changed=./strings.mol");
+    println!("cargo:rerun-if-changed=./anonymous_types.mol");
+    println!("cargo:rerun-if-changed=./union_variants.mol");
+    println!("cargo:rerun-if-changed=./reserved.mol");
+    println!("cargo:rerun-if-changed=./defaults.mol");
+    println!("cargo:rerun-if-changed=./only_types.mol");
+    println!("cargo:rerun-if-changed=./only_types_common.mol");
 
     compile_intermediate_0_7_3("./union_foo_0_7_3.mol");
     compile_intermediate_dev("./union_foo_with_custom_id.mol");
+    compile_intermediate_dev("./collections.mol");
+    compile_intermediate_dev("./strings.mol");
+    compile_intermediate_dev("./union_variants.mol");
+    compile_intermediate_dev("./reserved.mol");
+    compile_intermediate_dev("./defaults.mol");
+    compile_latest_intermediate_dev("./defaults.mol");
 
     compile_schema_0_7_3("./union_foo_0_7_3.mol");
     compile_schema_dev("./union_foo_with_custom_id.mol");
+    compile_schema_dev("./collections.mol");
+    compile_schema_dev("./strings.mol");
+    compile_schema_dev("./anonymous_types.mol");
+    compile_schema_dev("./union_variants.mol");
+    compile_schema_dev("./reserved.mol");
+    compile_schema_dev("./defaults.mol");
+
+    compile_only_types_dev("./only_types.mol", &["Block"]);
+
+    let mut options = codegen_dev::GeneratorOptions::new();
+    options.crate_path("crate::generator_options_test::tests::reexported::molecule");
+    for schema in &["./only_types_common.mol", "./only_types.mol"] {
+        let rust = codegen_dev::Language::Rust;
+        compile_with_options_dev(schema, "crate_path", rust, &options);
+        let lazy_reader = codegen_dev::Language::RustLazyReader;
+        compile_with_options_dev(schema, "crate_path/lazy_reader", lazy_reader, &options);
+    }
+    let mut options = codegen_dev::GeneratorOptions::new();
+    options.module_path("crate::generator_options_test::tests::module_path");
+    for schema in &["./only_types_common.mol", "./only_types.mol"] {
+        let rust = codegen_dev::Language::Rust;
+        compile_with_options_dev(schema, "module_path", rust, &options);
+    }
+    let mut options = codegen_dev::GeneratorOptions::new();
+    options.derives(&["PartialEq", "Eq", "Hash"]);
+    for schema in &["./only_types_common.mol", "./only_types.mol"] {
+        let rust = codegen_dev::Language::Rust;
+        compile_with_options_dev(schema, "derives", rust, &options);
+    }
+
+    let mut options = codegen_dev::GeneratorOptions::new();
+    options.split(codegen_dev::Split::Type);
+    for schema in &["./only_types_common.mol", "./only_types.mol"] {
+        let rust = codegen_dev::Language::Rust;
+        compile_with_options_dev(schema, "split_type", rust, &options);
+    }
+    options.feature_gates(true);
+    for schema in &["./only_types_common.mol", "./only_types.mol"] {
+        let rust = codegen_dev::Language::Rust;
+        compile_with_options_dev(schema, "feature_gates", rust, &options);
+    }
+    let mut options = codegen_dev::GeneratorOptions::new();
+    options.split(codegen_dev::Split::Kind);
+    for schema in &["./only_types_common.mol", "./only_types.mol"] {
+        let rust = codegen_dev::Language::Rust;
+        compile_with_options_dev(schema, "split_kind", rust, &options);
+    }
 }
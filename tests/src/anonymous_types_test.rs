@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod tests {
+    use molecule::prelude::*;
+
+    mod anonymous_types_dev {
+        #![allow(clippy::all, dead_code)]
+        include!(concat!(env!("OUT_DIR"), "/dev/anonymous_types.rs"));
+    }
+
+    use anonymous_types_dev::*;
+
+    #[test]
+    fn test_anonymous_types() {
+        let id = Byte20::from([1u8; 20]);
+        let account = Account::new_builder().id(id.clone()).build();
+        let wallet = Wallet::new_builder()
+            .accounts(AccountVec::new_builder().push(account).build())
+            .labels(
+                BytesVec::new_builder()
+                    .push(Bytes::from(b"main".to_vec()))
+                    .build(),
+            )
+            .owner(Byte20Opt::new_builder().set(Some(id.clone())).build())
+            .build();
+        let reader = WalletReader::from_slice(wallet.as_slice()).unwrap();
+        assert_eq!(reader.accounts().len(), 1);
+        assert_eq!(
+            reader.accounts().get(0).unwrap().id().as_slice(),
+            id.as_slice()
+        );
+        assert_eq!(reader.labels().get(0).unwrap().raw_data(), b"main");
+        assert_eq!(reader.owner().to_opt().unwrap().as_slice(), id.as_slice());
+        assert_eq!(reader.checkpoints().as_slice(), &[0u8; 8][..]);
+        let _: Uint32Array2 = wallet.checkpoints();
+    }
+}
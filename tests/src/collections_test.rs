@@ -0,0 +1,196 @@
+#[cfg(test)]
+mod tests {
+    use molecule::prelude::*;
+
+    static COLLECTIONS_DEV_JSON_INTERMEDIATE: &str =
+        include_str!(concat!(env!("OUT_DIR"), "/dev/collections.json"));
+
+    mod collections_dev {
+        #![allow(clippy::all, dead_code)]
+        include!(concat!(env!("OUT_DIR"), "/dev/collections.rs"));
+    }
+
+    use collections_dev::*;
+
+    fn uint32(v: u32) -> Uint32 {
+        Uint32::new_unchecked(v.to_be_bytes().to_vec().into())
+    }
+
+    fn bytes(v: &[u8]) -> Bytes {
+        v.to_vec().into()
+    }
+
+    #[test]
+    fn test_recover_collections_ir() {
+        use codegen_dev::ast::{Collection, TopDecl};
+
+        let format = codegen_dev::IntermediateFormat::JSON;
+        let ast = format
+            .recover(COLLECTIONS_DEV_JSON_INTERMEDIATE.as_bytes())
+            .unwrap();
+        let collections = ast
+            .decls()
+            .iter()
+            .filter_map(|decl| match decl.as_ref() {
+                TopDecl::FixVec(inner) => inner.collection(),
+                TopDecl::DynVec(inner) => inner.collection(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            collections,
+            vec![
+                Collection::Set,
+                Collection::Set,
+                Collection::Map,
+                Collection::Map
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_builder_sorts_and_deduplicates() {
+        let set = Uint32Set::new_builder()
+            .push(uint32(3))
+            .push(uint32(1))
+            .push(uint32(2))
+            .push(uint32(1))
+            .build();
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.get(0).unwrap().as_slice(), uint32(1).as_slice());
+        assert_eq!(set.get(2).unwrap().as_slice(), uint32(3).as_slice());
+        assert!(set.contains(uint32(2).as_reader()));
+        assert!(!set.contains(uint32(4).as_reader()));
+        assert!(Uint32SetReader::verify(set.as_slice(), false).is_ok());
+
+        let set = BytesSet::new_builder()
+            .push(bytes(b"b"))
+            .push(bytes(b"ab"))
+            .push(bytes(b"a"))
+            .push(bytes(b"b"))
+            .build();
+        assert_eq!(set.len(), 3);
+        // items are ordered by their serialized bytes, which start with the length
+        let reader = set.as_reader();
+        let items = reader
+            .iter()
+            .map(|item| item.raw_data())
+            .collect::<Vec<_>>();
+        assert_eq!(items, vec![&b"a"[..], &b"b"[..], &b"ab"[..]]);
+        assert!(reader.contains(bytes(b"ab").as_reader()));
+        assert!(!reader.contains(bytes(b"c").as_reader()));
+        assert!(BytesSetReader::verify(set.as_slice(), false).is_ok());
+    }
+
+    #[test]
+    fn test_map_builder_keeps_the_last_value() {
+        let map = Uint32Map::new_builder()
+            .push(
+                Uint32MapEntry::new_builder()
+                    .key(uint32(2))
+                    .value(uint32(20))
+                    .build(),
+            )
+            .push(
+                Uint32MapEntry::new_builder()
+                    .key(uint32(1))
+                    .value(uint32(10))
+                    .build(),
+            )
+            .push(
+                Uint32MapEntry::new_builder()
+                    .key(uint32(2))
+                    .value(uint32(21))
+                    .build(),
+            )
+            .build();
+        assert_eq!(map.len(), 2);
+        assert_eq!(
+            map.get(uint32(2).as_reader()).unwrap().as_slice(),
+            uint32(21).as_slice()
+        );
+        assert_eq!(
+            map.as_reader()
+                .get(uint32(1).as_reader())
+                .unwrap()
+                .as_slice(),
+            uint32(10).as_slice()
+        );
+        assert!(map.get(uint32(3).as_reader()).is_none());
+        assert!(map.contains_key(uint32(1).as_reader()));
+        assert_eq!(
+            map.get_entry(0).unwrap().key().as_slice(),
+            uint32(1).as_slice()
+        );
+
+        let map = BytesMap::new_builder()
+            .push(
+                BytesMapEntry::new_builder()
+                    .key(bytes(b"key"))
+                    .value(uint32(1))
+                    .build(),
+            )
+            .push(
+                BytesMapEntry::new_builder()
+                    .key(bytes(b"a"))
+                    .value(uint32(2))
+                    .build(),
+            )
+            .build();
+        assert_eq!(
+            map.get(bytes(b"key").as_reader()).unwrap().as_slice(),
+            uint32(1).as_slice()
+        );
+        assert!(BytesMapReader::verify(map.as_slice(), false).is_ok());
+    }
+
+    #[test]
+    fn test_expected_length_of_deduplicated_items() {
+        let builder = Uint32Set::new_builder()
+            .push(uint32(1))
+            .push(uint32(2))
+            .push(uint32(1));
+        assert_eq!(builder.expected_length(), 4 + 4 * 2);
+        assert_eq!(builder.expected_length(), builder.build().as_slice().len());
+
+        let builder = BytesSet::new_builder()
+            .push(bytes(b"ab"))
+            .push(bytes(b"a"))
+            .push(bytes(b"ab"));
+        assert_eq!(builder.expected_length(), builder.build().as_slice().len());
+
+        let entry = |key: &[u8], value| {
+            BytesMapEntry::new_builder()
+                .key(bytes(key))
+                .value(uint32(value))
+                .build()
+        };
+        let builder = BytesMap::new_builder()
+            .push(entry(b"key", 1))
+            .push(entry(b"a", 2))
+            .push(entry(b"key", 3));
+        assert_eq!(builder.expected_length(), builder.build().as_slice().len());
+    }
+
+    #[test]
+    fn test_verify_rejects_unsorted_or_duplicated_items() {
+        let unsorted = Uint32Vec::new_builder()
+            .push(uint32(2))
+            .push(uint32(1))
+            .build();
+        assert!(Uint32SetReader::verify(unsorted.as_slice(), false).is_err());
+
+        let duplicated = Uint32Vec::new_builder()
+            .push(uint32(1))
+            .push(uint32(1))
+            .build();
+        assert!(Uint32SetReader::verify(duplicated.as_slice(), false).is_err());
+        assert!(Uint32Set::from_slice(duplicated.as_slice()).is_err());
+
+        let sorted = Uint32Vec::new_builder()
+            .push(uint32(1))
+            .push(uint32(2))
+            .build();
+        assert!(Uint32SetReader::verify(sorted.as_slice(), false).is_ok());
+    }
+}
@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod tests {
+    use molecule::prelude::*;
+
+    static DEFAULTS_DEV_JSON_INTERMEDIATE: &str =
+        include_str!(concat!(env!("OUT_DIR"), "/dev/defaults.json"));
+
+    mod defaults_dev {
+        #![allow(clippy::all, dead_code)]
+        include!(concat!(env!("OUT_DIR"), "/dev/defaults.rs"));
+    }
+
+    use defaults_dev::*;
+
+    #[test]
+    fn test_recover_defaults_ir() {
+        use codegen_dev::ast::TopDecl;
+
+        assert!(DEFAULTS_DEV_JSON_INTERMEDIATE.contains(r#""default": "0x0a000000""#));
+        let format = codegen_dev::IntermediateFormat::JSON;
+        let ast = format
+            .recover(DEFAULTS_DEV_JSON_INTERMEDIATE.as_bytes())
+            .unwrap();
+        let defaults = ast
+            .decls()
+            .iter()
+            .filter_map(|decl| match decl.as_ref() {
+                TopDecl::Struct(inner) => Some(inner.fields()),
+                TopDecl::Table(inner) => Some(inner.fields()),
+                _ => None,
+            })
+            .flatten()
+            .map(|field| (field.name(), field.default().cloned()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            defaults,
+            vec![
+                ("major", Some(vec![1])),
+                ("minor", Some(vec![2, 3])),
+                ("version", None),
+                ("timeout", Some(vec![10, 0, 0, 0])),
+                ("retries", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_struct_defaults() {
+        let version = Version::default();
+        assert_eq!(version.as_slice(), &[1, 2, 3]);
+        assert_eq!(Version::new_builder().build().as_slice(), &[1, 2, 3]);
+        let version = Version::new_builder().major(Byte::new(2)).build();
+        assert_eq!(version.as_slice(), &[2, 2, 3]);
+    }
+
+    #[test]
+    fn test_table_defaults() {
+        let config = Config::default();
+        assert_eq!(config.version().as_slice(), &[1, 2, 3]);
+        assert_eq!(config.timeout().as_slice(), &[10, 0, 0, 0]);
+        assert_eq!(config.retries().as_slice(), &[0, 0]);
+        assert_eq!(Config::new_builder().build().as_slice(), config.as_slice());
+    }
+}
@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod tests {
+    /// The molecule crate which is re-exported by another crate, e.g. `ckb_types::molecule`.
+    pub mod reexported {
+        pub use molecule;
+    }
+
+    // The generated code should use the molecule crate only by the option `crate-path`, so the
+    // name `molecule` is shadowed by an empty module.
+    mod crate_path {
+        pub mod only_types_common {
+            #![allow(clippy::all, dead_code)]
+            mod molecule {}
+            include!(concat!(env!("OUT_DIR"), "/crate_path/only_types_common.rs"));
+        }
+
+        pub mod only_types {
+            #![allow(clippy::all, dead_code)]
+            mod molecule {}
+            include!(concat!(env!("OUT_DIR"), "/crate_path/only_types.rs"));
+        }
+
+        pub mod lazy_reader {
+            pub mod only_types_common {
+                #![allow(clippy::all, dead_code)]
+                mod molecule {}
+                include!(concat!(
+                    env!("OUT_DIR"),
+                    "/crate_path/lazy_reader/only_types_common.rs"
+                ));
+            }
+
+            pub mod only_types {
+                #![allow(clippy::all, dead_code)]
+                mod molecule {}
+                include!(concat!(
+                    env!("OUT_DIR"),
+                    "/crate_path/lazy_reader/only_types.rs"
+                ));
+            }
+        }
+    }
+
+    // The imported schema files are found by the option `module-path`, instead of `super`.
+    pub mod module_path {
+        pub mod only_types_common {
+            #![allow(clippy::all, dead_code)]
+            include!(concat!(
+                env!("OUT_DIR"),
+                "/module_path/only_types_common.rs"
+            ));
+        }
+    }
+
+    mod module_path_users {
+        pub mod only_types {
+            #![allow(clippy::all, dead_code)]
+            include!(concat!(env!("OUT_DIR"), "/module_path/only_types.rs"));
+        }
+    }
+
+    // The entities derive the traits by the option `derives`.
+    mod derives {
+        pub mod only_types_common {
+            #![allow(clippy::all, dead_code)]
+            include!(concat!(env!("OUT_DIR"), "/derives/only_types_common.rs"));
+        }
+
+        pub mod only_types {
+            #![allow(clippy::all, dead_code)]
+            include!(concat!(env!("OUT_DIR"), "/derives/only_types.rs"));
+        }
+    }
+
+    use reexported::molecule::{lazy_reader, prelude::*};
+
+    #[test]
+    fn test_crate_path() {
+        use crate_path::{lazy_reader as lazy, only_types::*, only_types_common::*};
+
+        let header = Header::new_builder()
+            .number(Uint32::new_builder().nth0(Byte::new(1)).build())
+            .build();
+        let block = Block::new_builder()
+            .header(header.clone())
+            .uncles(Hashes::new_builder().push(Hash::default()).build())
+            .build();
+        let block = Block::from_slice(block.as_slice()).unwrap();
+        assert_eq!(block.header().as_slice(), header.as_slice());
+
+        let cursor = lazy_reader::Cursor::from(block.as_slice().to_vec());
+        let block = lazy::only_types::Block::from(cursor);
+        block.verify(false).unwrap();
+        assert_eq!(block.uncles().unwrap().len().unwrap(), 1);
+        let number = block.header().unwrap().number().unwrap();
+        assert_eq!(number, 1);
+    }
+
+    #[test]
+    fn test_module_path() {
+        use {module_path::only_types_common::*, module_path_users::only_types::*};
+
+        let block = Block::new_builder()
+            .uncles(Hashes::new_builder().push(Hash::default()).build())
+            .build();
+        let block = BlockReader::from_slice(block.as_slice()).unwrap();
+        assert_eq!(block.uncles().len(), 1);
+        assert_eq!(block.header().as_slice(), Header::default().as_slice());
+    }
+
+    #[test]
+    fn test_derives() {
+        use derives::{only_types::*, only_types_common::*};
+        use std::collections::HashSet;
+
+        let header = Header::new_builder()
+            .number(Uint32::new_builder().nth0(Byte::new(1)).build())
+            .build();
+        assert_eq!(header, Header::from_slice(header.as_slice()).unwrap());
+        assert_ne!(header, Header::default());
+
+        let block = Block::new_builder().header(header).build();
+        let blocks = vec![block.clone(), Block::default(), block]
+            .into_iter()
+            .collect::<HashSet<_>>();
+        assert_eq!(blocks.len(), 2);
+    }
+}
@@ -0,0 +1,20 @@
+#[cfg(test)]
+mod tests {
+    static UNION_FOO_DEV_JSON_INTERMEDIATE: &str = include_str!(concat!(
+        env!("OUT_DIR"),
+        "/dev/union_foo_with_custom_id.json"
+    ));
+
+    #[test]
+    fn test_recover_dev_intermediate_by_0_9_1_ir_recover() {
+        let format = codegen_0_9_1::IntermediateFormat::JSON;
+        let ast_result = format.recover(UNION_FOO_DEV_JSON_INTERMEDIATE.as_bytes());
+        assert!(ast_result.is_ok(), "{:?}", ast_result.err());
+    }
+
+    #[test]
+    fn test_default_intermediate_is_in_the_first_format_version() {
+        assert!(!UNION_FOO_DEV_JSON_INTERMEDIATE.contains("\"format_version\""));
+        assert!(!UNION_FOO_DEV_JSON_INTERMEDIATE.contains("\"schema_hash\""));
+    }
+}
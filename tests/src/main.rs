@@ -1,3 +1,14 @@
+mod anonymous_types_test;
+mod collections_test;
+mod defaults_test;
+mod generator_options_test;
+mod ir_compatibility_test;
+mod only_types_test;
+mod reserved_test;
+mod schema_hash_test;
+mod split_test;
+mod strings_test;
 mod union_compatibility_test;
+mod union_variants_test;
 
 fn main() {}
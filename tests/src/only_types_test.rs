@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use molecule::prelude::*;
+
+    static ONLY_TYPES_DEV_JSON_INTERMEDIATE: &str =
+        include_str!(concat!(env!("OUT_DIR"), "/dev/only_types.json"));
+
+    // The imported types which are required are generated in the same file, so the module
+    // of the imported schema is not required.
+    mod only_types_dev {
+        #![allow(clippy::all, dead_code)]
+        include!(concat!(env!("OUT_DIR"), "/dev/only_types.rs"));
+    }
+
+    use only_types_dev::*;
+
+    #[test]
+    fn test_only_types_ir() {
+        use codegen_dev::ast::HasName as _;
+
+        let format = codegen_dev::IntermediateFormat::JSON;
+        let ast = format
+            .recover(ONLY_TYPES_DEV_JSON_INTERMEDIATE.as_bytes())
+            .unwrap();
+        assert!(ast.imports().is_empty());
+        let names = ast
+            .major_decls()
+            .iter()
+            .map(|decl| decl.name().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Uint32", "Hash", "Header", "Hashes", "Block"]);
+    }
+
+    #[test]
+    fn test_only_types() {
+        let header = Header::new_builder()
+            .number(Uint32::new_builder().nth0(Byte::new(1)).build())
+            .build();
+        let block = Block::new_builder()
+            .header(header.clone())
+            .uncles(Hashes::new_builder().push(Hash::default()).build())
+            .build();
+        let block = Block::from_slice(block.as_slice()).unwrap();
+        assert_eq!(block.header().as_slice(), header.as_slice());
+        assert_eq!(block.uncles().len(), 1);
+    }
+}
@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod tests {
+    use molecule::prelude::*;
+
+    static RESERVED_DEV_JSON_INTERMEDIATE: &str =
+        include_str!(concat!(env!("OUT_DIR"), "/dev/reserved.json"));
+
+    mod reserved_dev {
+        #![allow(clippy::all, dead_code)]
+        include!(concat!(env!("OUT_DIR"), "/dev/reserved.rs"));
+    }
+
+    use reserved_dev::*;
+
+    #[test]
+    fn test_recover_reserved_ir() {
+        use codegen_dev::ast::TopDecl;
+
+        let format = codegen_dev::IntermediateFormat::JSON;
+        let ast = format
+            .recover(RESERVED_DEV_JSON_INTERMEDIATE.as_bytes())
+            .unwrap();
+        for decl in ast.decls() {
+            match decl.as_ref() {
+                TopDecl::Union(inner) => {
+                    assert_eq!(inner.reserved_ids(), &[2, 3]);
+                }
+                TopDecl::Table(inner) => {
+                    let reserved = inner
+                        .fields()
+                        .iter()
+                        .filter(|field| field.reserved())
+                        .map(|field| field.name())
+                        .collect::<Vec<_>>();
+                    assert_eq!(reserved, vec!["_reserved"]);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_reserved_table_field() {
+        let config = Config::new_builder()
+            .version(Uint32::new_unchecked(vec![1, 0, 0, 0].into()))
+            .name(Bytes::new_unchecked(vec![1, 0, 0, 0, 0x61].into()))
+            .build();
+        assert_eq!(Config::FIELD_COUNT, 3);
+        assert!(ConfigReader::verify(config.as_slice(), false).is_ok());
+        assert_eq!(config.name().raw_data().as_ref(), b"a");
+        assert_eq!(
+            format!("{}", config),
+            "Config { version: Uint32(0x01000000), name: Bytes(0x61) }"
+        );
+
+        // the reserved slot is kept as an empty bytes
+        let slice = config.as_slice();
+        let reserved_start = molecule::unpack_number(&slice[8..]) as usize;
+        let reserved_end = molecule::unpack_number(&slice[12..]) as usize;
+        assert_eq!(&slice[reserved_start..reserved_end], &[0, 0, 0, 0]);
+
+        let rebuilt = config.clone().as_builder().build();
+        assert_eq!(rebuilt.as_slice(), config.as_slice());
+    }
+
+    #[test]
+    fn test_reserved_union_ids() {
+        let reserved = MessageReader::verify(&[2, 0, 0, 0, 0, 0, 0, 0], false);
+        assert!(reserved.is_err());
+        let message = Message::new_builder()
+            .set(Uint32::new_unchecked(vec![1, 0, 0, 0].into()))
+            .build();
+        assert_eq!(message.item_id(), 1);
+        assert_eq!(Message::ITEMS_COUNT, 2);
+    }
+}
@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    static DEFAULTS_DEV_LATEST_JSON_INTERMEDIATE: &str =
+        include_str!(concat!(env!("OUT_DIR"), "/dev/latest/defaults.json"));
+
+    mod defaults_dev {
+        #![allow(clippy::all, dead_code)]
+        include!(concat!(env!("OUT_DIR"), "/dev/defaults.rs"));
+    }
+
+    use defaults_dev::*;
+
+    #[test]
+    fn test_schema_hash() {
+        use codegen_dev::ast::{HasName as _, SchemaHash as _};
+
+        let format = codegen_dev::IntermediateFormat::JSON;
+        let ast = format
+            .recover(DEFAULTS_DEV_LATEST_JSON_INTERMEDIATE.as_bytes())
+            .unwrap();
+        let hash = |name: &str| {
+            ast.decls()
+                .iter()
+                .find(|decl| decl.name() == name)
+                .unwrap()
+                .schema_hash()
+        };
+        assert_eq!(Uint16::SCHEMA_HASH, hash("Uint16"));
+        assert_eq!(Version::SCHEMA_HASH, hash("Version"));
+        assert_eq!(Config::SCHEMA_HASH, hash("Config"));
+        assert_ne!(Uint16::SCHEMA_HASH, Uint32::SCHEMA_HASH);
+        assert!(DEFAULTS_DEV_LATEST_JSON_INTERMEDIATE.contains(&format!(
+            r#""schema_hash": "0x{}""#,
+            molecule::hex_string(&Config::SCHEMA_HASH)
+        )));
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't match its structure")]
+    fn test_recover_ir_with_bad_schema_hash() {
+        let intermediate = DEFAULTS_DEV_LATEST_JSON_INTERMEDIATE.replacen(
+            r#""schema_hash": "0x"#,
+            r#""schema_hash": "0xff"#,
+            1,
+        );
+        let format = codegen_dev::IntermediateFormat::JSON;
+        let _should_panic = format.recover(intermediate.as_bytes());
+    }
+}
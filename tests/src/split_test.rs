@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use molecule::prelude::*;
+
+    // The modules of the split code are in the directories which are named after the schema
+    // files, the modules of the imported schema files are found by `super`.
+    mod split_type {
+        pub mod only_types_common {
+            #![allow(clippy::all, dead_code)]
+            include!(concat!(
+                env!("OUT_DIR"),
+                "/split_type/only_types_common/mod.rs"
+            ));
+        }
+
+        pub mod only_types {
+            #![allow(clippy::all, dead_code)]
+            include!(concat!(env!("OUT_DIR"), "/split_type/only_types/mod.rs"));
+        }
+    }
+
+    mod split_kind {
+        pub mod only_types_common {
+            #![allow(clippy::all, dead_code)]
+            include!(concat!(
+                env!("OUT_DIR"),
+                "/split_kind/only_types_common/mod.rs"
+            ));
+        }
+
+        pub mod only_types {
+            #![allow(clippy::all, dead_code)]
+            include!(concat!(env!("OUT_DIR"), "/split_kind/only_types/mod.rs"));
+        }
+    }
+
+    // Only the features of `Block` and its dependencies are enabled, see `Cargo.toml`.
+    mod feature_gates {
+        pub mod only_types_common {
+            #![allow(clippy::all, dead_code)]
+            include!(concat!(
+                env!("OUT_DIR"),
+                "/feature_gates/only_types_common/mod.rs"
+            ));
+        }
+
+        pub mod only_types {
+            #![allow(clippy::all, dead_code)]
+            include!(concat!(env!("OUT_DIR"), "/feature_gates/only_types/mod.rs"));
+        }
+    }
+
+    static ONLY_TYPES_FEATURES: &str = include_str!(concat!(
+        env!("OUT_DIR"),
+        "/feature_gates/only_types/features.toml"
+    ));
+    static ONLY_TYPES_COMMON_FEATURES: &str = include_str!(concat!(
+        env!("OUT_DIR"),
+        "/feature_gates/only_types_common/features.toml"
+    ));
+    static CARGO_TOML: &str = include_str!("../Cargo.toml");
+
+    macro_rules! test_block {
+        ($module:ident) => {{
+            use $module::{only_types::*, only_types_common::*};
+
+            let header = Header::new_builder()
+                .number(Uint32::new_builder().nth0(Byte::new(1)).build())
+                .build();
+            let block = Block::new_builder()
+                .header(header.clone())
+                .uncles(Hashes::new_builder().push(Hash::default()).build())
+                .build();
+            let block = BlockReader::from_slice(block.as_slice()).unwrap();
+            assert_eq!(block.header().as_slice(), header.as_slice());
+            assert_eq!(block.uncles().len(), 1);
+        }};
+    }
+
+    #[test]
+    fn test_split_type() {
+        test_block!(split_type);
+        let _ = split_type::only_types::Unused::default();
+    }
+
+    #[test]
+    fn test_split_kind() {
+        test_block!(split_kind);
+        let _ = split_kind::only_types::Unused::default();
+    }
+
+    #[test]
+    fn test_feature_gates() {
+        test_block!(feature_gates);
+        assert!(!cfg!(feature = "only_types-unused"));
+        assert!(!cfg!(feature = "only_types_common-uint64"));
+    }
+
+    #[test]
+    fn test_features_are_up_to_date() {
+        for features in [ONLY_TYPES_FEATURES, ONLY_TYPES_COMMON_FEATURES] {
+            for feature in features.lines().filter(|line| !line.starts_with('#')) {
+                assert!(
+                    CARGO_TOML.lines().any(|line| line == feature),
+                    "the feature is not in Cargo.toml: {}",
+                    feature
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests {
+    use molecule::prelude::*;
+
+    static STRINGS_DEV_JSON_INTERMEDIATE: &str =
+        include_str!(concat!(env!("OUT_DIR"), "/dev/strings.json"));
+
+    mod strings_dev {
+        #![allow(clippy::all, dead_code)]
+        include!(concat!(env!("OUT_DIR"), "/dev/strings.rs"));
+    }
+
+    use strings_dev::*;
+
+    #[test]
+    fn test_recover_strings_ir() {
+        use codegen_dev::ast::TopDecl;
+
+        let format = codegen_dev::IntermediateFormat::JSON;
+        let ast = format
+            .recover(STRINGS_DEV_JSON_INTERMEDIATE.as_bytes())
+            .unwrap();
+        let strings = ast
+            .decls()
+            .iter()
+            .filter_map(|decl| match decl.as_ref() {
+                TopDecl::FixVec(inner) if inner.utf8() => Some(inner.name()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(strings, vec!["String", "Name"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "requires syntax version 2")]
+    fn test_recover_strings_ir_with_old_syntax_version() {
+        let ir = STRINGS_DEV_JSON_INTERMEDIATE.replacen(r#""version": 2"#, r#""version": 1"#, 1);
+        let format = codegen_dev::IntermediateFormat::JSON;
+        let _should_panic = format.recover(ir.as_bytes());
+    }
+
+    #[test]
+    fn test_string_conversions() {
+        let name = Name::from("molecule");
+        assert_eq!(name.as_str(), Ok("molecule"));
+        assert_eq!(name.as_reader().as_str(), Ok("molecule"));
+        let s: &str = name.as_reader().try_into().unwrap();
+        assert_eq!(s, "molecule");
+
+        let name = Name::new_builder().set_str("名字").build();
+        assert_eq!(name.as_str(), Ok("名字"));
+        assert_eq!(name.len(), "名字".len());
+        // strings are encoded as bytes
+        let bytes: Bytes = "名字".as_bytes().to_vec().into();
+        assert_eq!(name.as_slice(), bytes.as_slice());
+
+        let person = Person::new_builder()
+            .name(Name::from("alice"))
+            .nickname(NameOpt::new_builder().set(Some("al".into())).build())
+            .aliases(NameVec::from(vec![Name::from("a"), Name::from("ali")]))
+            .build();
+        let reader = person.as_reader();
+        assert_eq!(reader.name().as_str(), Ok("alice"));
+        assert_eq!(reader.nickname().to_opt().unwrap().as_str(), Ok("al"));
+        assert_eq!(reader.aliases().get(1).unwrap().as_str(), Ok("ali"));
+    }
+
+    #[test]
+    fn test_string_fields() {
+        let book = Book::new_builder()
+            .title(String::from("Molecule"))
+            .authors(StringVec::from(vec![String::from("a"), String::from("b")]))
+            .build();
+        let reader = BookReader::from_slice(book.as_slice()).unwrap();
+        assert_eq!(reader.title().as_str(), Ok("Molecule"));
+        assert_eq!(reader.authors().get(1).unwrap().as_str(), Ok("b"));
+    }
+
+    #[test]
+    fn test_as_str_of_invalid_utf8() {
+        let name = Name::from(vec![Byte::new(0xff)]);
+        let err = name.as_str().unwrap_err();
+        assert_eq!(err.valid_up_to(), 0);
+        assert!(name.as_reader().as_str().is_err());
+        let s: Result<&str, _> = name.as_reader().try_into();
+        assert!(s.is_err());
+    }
+
+    #[test]
+    fn test_verify_rejects_invalid_utf8() {
+        let valid: Bytes = "ok".as_bytes().to_vec().into();
+        assert!(NameReader::verify(valid.as_slice(), false).is_ok());
+        for invalid in [&b"\xff"[..], b"\xc0\xaf", b"a\xe2\x82", b"\xed\xa0\x80"] {
+            let invalid: Bytes = invalid.to_vec().into();
+            assert!(NameReader::verify(invalid.as_slice(), false).is_err());
+            assert!(Name::from_slice(invalid.as_slice()).is_err());
+        }
+
+        let invalid: Bytes = b"\xff".to_vec().into();
+        let person = Person::new_builder()
+            .name(Name::new_unchecked(invalid.as_bytes()))
+            .build();
+        assert!(PersonReader::verify(person.as_slice(), false).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod tests {
+    use molecule::prelude::*;
+
+    static UNION_VARIANTS_DEV_JSON_INTERMEDIATE: &str =
+        include_str!(concat!(env!("OUT_DIR"), "/dev/union_variants.json"));
+
+    mod union_variants_dev {
+        #![allow(clippy::all, dead_code)]
+        include!(concat!(env!("OUT_DIR"), "/dev/union_variants.rs"));
+    }
+
+    use union_variants_dev::*;
+
+    #[test]
+    fn test_recover_union_variants_ir() {
+        use codegen_dev::ast::TopDecl;
+
+        let format = codegen_dev::IntermediateFormat::JSON;
+        let ast = format
+            .recover(UNION_VARIANTS_DEV_JSON_INTERMEDIATE.as_bytes())
+            .unwrap();
+        let union = ast
+            .decls()
+            .iter()
+            .find_map(|decl| match decl.as_ref() {
+                TopDecl::Union(inner) => Some(inner),
+                _ => None,
+            })
+            .unwrap();
+        let items = union
+            .items()
+            .iter()
+            .map(|item| (item.name(), item.id()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            vec![("Ping", 1), ("Pong", 2), ("Nonce", 3), ("Retry", 4)]
+        );
+    }
+
+    #[test]
+    fn test_union_variants_with_same_type() {
+        let pong = Message::new_builder()
+            .set(MessageUnion::Pong(Empty::default()))
+            .build();
+        assert_eq!(pong.item_id(), 2);
+        assert_eq!(pong.to_enum().item_name(), "Pong");
+        assert!(matches!(pong.to_enum(), MessageUnion::Pong(_)));
+        assert!(matches!(
+            pong.as_reader().to_enum(),
+            MessageUnionReader::Pong(_)
+        ));
+        assert_eq!(
+            format!("{}", pong.to_enum()),
+            "MessageUnion::Pong(Empty(0x00))"
+        );
+
+        let ping = MessageReader::from_slice(&[1, 0, 0, 0, 0])
+            .unwrap()
+            .to_entity();
+        assert!(matches!(ping.to_enum(), MessageUnion::Ping(_)));
+        assert_eq!(ping.to_enum().as_reader().item_name(), "Ping");
+
+        // the variants of the same type display differently
+        assert_eq!(format!("{}", ping), "Message(Ping(Empty(0x00)))");
+        assert_eq!(format!("{}", pong), "Message(Pong(Empty(0x00)))");
+        assert_eq!(
+            format!("{}", pong.as_reader()),
+            "MessageReader(Pong(EmptyReader(0x00)))"
+        );
+        let nonce = Message::new_builder().set(Nonce::default()).build();
+        assert_eq!(format!("{}", nonce), "Message(Nonce(0x00000000))");
+    }
+
+    #[test]
+    fn test_union_variants_default_name() {
+        // the type which appears only once still converts into the union
+        let nonce = Message::new_builder().set(Nonce::default()).build();
+        assert_eq!(nonce.item_id(), 3);
+        assert_eq!(nonce.to_enum().item_name(), "Nonce");
+
+        let retry = Message::new_builder()
+            .set(MessageUnion::Retry(Hash::default()))
+            .build();
+        assert_eq!(retry.item_id(), 4);
+        assert_eq!(retry.to_enum().item_name(), "Retry");
+    }
+}
@@ -1,9 +1,10 @@
 pub(crate) mod raw;
 pub(crate) mod verified;
 
-pub use raw::SyntaxVersion;
+pub use raw::{Feature, SyntaxVersion};
 
 pub use verified::{
-    Array, Ast, DefaultContent, DynVec, FieldDecl, FixVec, HasName, ImportStmt, ItemDecl, Option_,
-    Primitive, Struct, Table, TopDecl, Union, UnionItemDecl,
+    Array, Ast, Collection, DefaultContent, DynVec, Encoding, FieldDecl, FieldLayout, FixVec,
+    HasName, HeaderLayout, ImportStmt, ItemDecl, ItemLayout, Layout, Option_, Primitive,
+    SchemaHash, Struct, Table, TopDecl, Union, UnionItemDecl, UnionItemLayout, SCHEMA_HASH_SIZE,
 };
@@ -1,10 +1,14 @@
-use std::path::PathBuf;
+use std::{ops::Range, path::PathBuf, ptr};
 
+#[cfg(feature = "compiler-plugin")]
+use schemars::JsonSchema;
 #[cfg(feature = "compiler-plugin")]
 use serde::{Deserialize, Serialize};
 
 use property::Property;
 
+use crate::parser::Span;
+
 mod utils;
 
 #[derive(Debug, Default, Property)]
@@ -13,6 +17,9 @@ pub(crate) struct Ast {
     namespace: String,
     imports: Vec<ImportStmt>,
     decls: Vec<TopDecl>,
+    // The spans of the names of the declarations, in the same order as the declarations.
+    spans: Vec<Span>,
+    anonymous_decls: Vec<TopDecl>,
 }
 
 impl Default for SyntaxVersion {
@@ -25,13 +32,103 @@ impl Default for SyntaxVersion {
 #[property(get(public))]
 #[cfg_attr(
     feature = "compiler-plugin",
-    derive(Deserialize, Serialize),
+    derive(Deserialize, Serialize, JsonSchema),
     serde(deny_unknown_fields)
 )]
 pub struct SyntaxVersion {
     version: usize,
 }
 
+/// The language features which are not available in all syntax versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// `set` and `map` declarations.
+    Collections,
+    /// `string` declarations.
+    Strings,
+    /// Anonymous types in field declarations.
+    AnonymousTypes,
+    /// Explicit variant names for union items.
+    UnionVariantNames,
+    /// Reserved union ids and reserved table fields.
+    Reserved,
+    /// Default values for struct and table fields.
+    FieldDefaults,
+}
+
+impl Feature {
+    pub const ALL: &'static [Self] = &[
+        Self::Collections,
+        Self::Strings,
+        Self::AnonymousTypes,
+        Self::UnionVariantNames,
+        Self::Reserved,
+        Self::FieldDefaults,
+    ];
+
+    /// The syntax version which introduced the feature.
+    pub fn since(self) -> usize {
+        match self {
+            Self::Collections
+            | Self::Strings
+            | Self::AnonymousTypes
+            | Self::UnionVariantNames
+            | Self::Reserved
+            | Self::FieldDefaults => 2,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Collections => "collections",
+            Self::Strings => "strings",
+            Self::AnonymousTypes => "anonymous types",
+            Self::UnionVariantNames => "union variant names",
+            Self::Reserved => "reserved",
+            Self::FieldDefaults => "field defaults",
+        }
+    }
+}
+
+impl SyntaxVersion {
+    pub const LATEST: usize = 2;
+
+    pub(crate) fn new(version: usize) -> Self {
+        if version == 0 || version > Self::LATEST {
+            panic!(
+                "unknown syntax version {}, the supported versions are 1 to {}",
+                version,
+                Self::LATEST
+            );
+        }
+        Self { version }
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        feature.since() <= self.version
+    }
+
+    /// All features which are enabled in this syntax version.
+    pub fn features(&self) -> Vec<Feature> {
+        Feature::ALL
+            .iter()
+            .copied()
+            .filter(|feature| self.supports(*feature))
+            .collect()
+    }
+
+    pub(crate) fn require(&self, feature: Feature) {
+        if !self.supports(feature) {
+            panic!(
+                "the feature `{}` requires syntax version {}, but the syntax version is {}",
+                feature.name(),
+                feature.since(),
+                self.version
+            );
+        }
+    }
+}
+
 #[derive(Debug, Clone, Property)]
 pub(crate) struct ImportStmt {
     name: String,
@@ -39,6 +136,10 @@ pub(crate) struct ImportStmt {
     path_supers: usize,
     imported_base: PathBuf,
     imported_depth: usize,
+    // The range of the statement in the importing file.
+    range: Range<usize>,
+    // The declarations in the imported file and the files which are imported by it.
+    decls: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -49,6 +150,9 @@ pub(crate) enum TopDecl {
     Struct(StructDecl),
     Vector(VectorDecl),
     Table(TableDecl),
+    String_(StringDecl),
+    Set(SetDecl),
+    Map(MapDecl),
 }
 
 #[derive(Debug, Property)]
@@ -62,6 +166,7 @@ pub(crate) struct OptionDecl {
 pub(crate) struct UnionDecl {
     name: String,
     items: Vec<CustomUnionItemDecl>,
+    reserved_ids: Vec<usize>,
     imported_depth: usize,
 }
 
@@ -94,6 +199,27 @@ pub(crate) struct TableDecl {
     imported_depth: usize,
 }
 
+#[derive(Debug, Property)]
+pub(crate) struct StringDecl {
+    name: String,
+    imported_depth: usize,
+}
+
+#[derive(Debug, Property)]
+pub(crate) struct SetDecl {
+    name: String,
+    item: ItemDecl,
+    imported_depth: usize,
+}
+
+#[derive(Debug, Property)]
+pub(crate) struct MapDecl {
+    name: String,
+    key: ItemDecl,
+    value: ItemDecl,
+    imported_depth: usize,
+}
+
 #[derive(Debug, Property)]
 pub(crate) struct ItemDecl {
     typ: String,
@@ -101,6 +227,7 @@ pub(crate) struct ItemDecl {
 
 #[derive(Debug, Property)]
 pub(crate) struct CustomUnionItemDecl {
+    name: Option<String>,
     typ: String,
     id: usize,
 }
@@ -109,6 +236,49 @@ pub(crate) struct CustomUnionItemDecl {
 pub(crate) struct FieldDecl {
     name: String,
     typ: String,
+    default: Option<String>,
+}
+
+impl ImportStmt {
+    pub(crate) fn span(&self) -> Span {
+        Span::new(&self.imported_base, self.range.clone())
+    }
+
+    pub(crate) fn file_path(&self) -> PathBuf {
+        let mut path_buf = self.imported_base().clone();
+        path_buf.pop();
+        for _ in 0..self.path_supers() {
+            path_buf.push("..");
+        }
+        for p in self.paths() {
+            path_buf.push(p);
+        }
+        path_buf.push(self.name());
+        path_buf.set_extension("mol");
+        path_buf
+    }
+
+    /// The path of the imported file, which is searched in the import paths if it's not found
+    /// relative to the importing file.
+    pub(crate) fn resolved_file_path(&self, import_paths: &[PathBuf]) -> PathBuf {
+        let path_buf = self.file_path();
+        if path_buf.is_file() || self.path_supers() > 0 {
+            return path_buf;
+        }
+        import_paths
+            .iter()
+            .map(|import_path| {
+                let mut path_buf = import_path.to_owned();
+                for p in self.paths() {
+                    path_buf.push(p);
+                }
+                path_buf.push(self.name());
+                path_buf.set_extension("mol");
+                path_buf
+            })
+            .find(|path_buf| path_buf.is_file())
+            .unwrap_or(path_buf)
+    }
 }
 
 impl Ast {
@@ -116,8 +286,44 @@ impl Ast {
         self.imports.push(stmt);
     }
 
-    pub(crate) fn add_decl(&mut self, decl: impl Into<TopDecl>) {
+    pub(crate) fn add_decl(&mut self, decl: impl Into<TopDecl>, span: Span) {
         self.decls.push(decl.into());
+        self.spans.push(span);
+    }
+
+    /// The span of the name of a declaration, anonymous types have no spans.
+    pub(crate) fn span(&self, decl: &TopDecl) -> Option<&Span> {
+        self.decls
+            .iter()
+            .position(|existed| ptr::eq(existed, decl))
+            .map(|index| &self.spans[index])
+    }
+
+    /// The span of the name of the declaration which is named `name`.
+    pub(crate) fn span_of_name(&self, name: &str) -> Option<&Span> {
+        self.decls
+            .iter()
+            .position(|decl| decl.name() == name)
+            .map(|index| &self.spans[index])
+    }
+
+    /// Adds an anonymous type which is declared inline in a field.
+    ///
+    /// Anonymous types are identified by their names, the same type which is used in
+    /// several files is kept only once, with the deepest imported depth.
+    pub(crate) fn add_anonymous_decl(&mut self, decl: impl Into<TopDecl>) {
+        let decl = decl.into();
+        if let Some(existed) = self
+            .anonymous_decls
+            .iter_mut()
+            .find(|existed| existed.name() == decl.name())
+        {
+            if existed.imported_depth() < decl.imported_depth() {
+                *existed = decl;
+            }
+        } else {
+            self.anonymous_decls.push(decl);
+        }
     }
 }
 
@@ -130,8 +336,47 @@ impl TopDecl {
             TopDecl::Struct(inner) => inner.name(),
             TopDecl::Vector(inner) => inner.name(),
             TopDecl::Table(inner) => inner.name(),
+            TopDecl::String_(inner) => inner.name(),
+            TopDecl::Set(inner) => inner.name(),
+            TopDecl::Map(inner) => inner.name(),
+        }
+    }
+}
+
+impl TopDecl {
+    pub(crate) fn imported_depth(&self) -> usize {
+        match self {
+            TopDecl::Option_(inner) => inner.imported_depth(),
+            TopDecl::Union(inner) => inner.imported_depth(),
+            TopDecl::Array(inner) => inner.imported_depth(),
+            TopDecl::Struct(inner) => inner.imported_depth(),
+            TopDecl::Vector(inner) => inner.imported_depth(),
+            TopDecl::Table(inner) => inner.imported_depth(),
+            TopDecl::String_(inner) => inner.imported_depth(),
+            TopDecl::Set(inner) => inner.imported_depth(),
+            TopDecl::Map(inner) => inner.imported_depth(),
         }
     }
+
+    /// Checks whether an anonymous type has the same structure as a declaration.
+    pub(crate) fn is_same_structure(&self, other: &Self) -> bool {
+        match (self, other) {
+            (TopDecl::Option_(a), TopDecl::Option_(b)) => a.item().typ() == b.item().typ(),
+            (TopDecl::Vector(a), TopDecl::Vector(b)) => a.item().typ() == b.item().typ(),
+            (TopDecl::Array(a), TopDecl::Array(b)) => {
+                a.item().typ() == b.item().typ() && a.item_count() == b.item_count()
+            }
+            (TopDecl::String_(_), TopDecl::String_(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl MapDecl {
+    /// The name of the key/value pair type which is generated for the map.
+    pub(crate) fn entry_name(&self) -> String {
+        format!("{}Entry", self.name)
+    }
 }
 
 macro_rules! impl_into_top_decl_for {
@@ -150,3 +395,6 @@ impl_into_top_decl_for!(Array, ArrayDecl);
 impl_into_top_decl_for!(Struct, StructDecl);
 impl_into_top_decl_for!(Vector, VectorDecl);
 impl_into_top_decl_for!(Table, TableDecl);
+impl_into_top_decl_for!(String_, StringDecl);
+impl_into_top_decl_for!(Set, SetDecl);
+impl_into_top_decl_for!(Map, MapDecl);
@@ -1,7 +1,16 @@
 use std::collections::HashSet;
-use std::{ffi, fs, io::Read as _, path::Path, str::FromStr};
+use std::{
+    ffi, fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use pest::{error::Error as PestError, iterators::Pairs, Parser as _};
+use pest::{
+    error::{Error as PestError, InputLocation},
+    iterators::{Pair, Pairs},
+    Parser as _,
+};
 use same_file::is_same_file;
 
 use crate::{
@@ -43,16 +52,25 @@ impl utils::PairsUtils for Pairs<'_, parser::Rule> {
         ret
     }
 
-    fn next_custom_union_items(&mut self) -> Vec<CustomUnionItemDecl> {
+    fn next_custom_union_items(&mut self) -> (Vec<CustomUnionItemDecl>, Vec<usize>) {
         let mut previous_id: Option<usize> = None;
         let mut ret = Vec::new();
+        let mut reserved_ids = Vec::new();
 
         let mut custom_ids = HashSet::new();
         for item in self {
             match item.as_rule() {
+                parser::Rule::union_reserved_decl => {
+                    reserved_ids.extend(
+                        item.into_inner()
+                            .map(|id| usize::from_str(id.as_str()).unwrap()),
+                    );
+                    continue;
+                }
                 parser::Rule::item_decl => {
                     let mut pair = item.into_inner();
                     let node = ast::CustomUnionItemDecl {
+                        name: None,
                         typ: pair.next_string(),
                         id: if let Some(pre_id) = previous_id {
                             pre_id + 1
@@ -66,12 +84,32 @@ impl utils::PairsUtils for Pairs<'_, parser::Rule> {
                 parser::Rule::custom_union_item_decl => {
                     let mut pair = item.into_inner();
                     let node = ast::CustomUnionItemDecl {
+                        name: None,
                         typ: pair.next_string(),
                         id: pair.next_usize(),
                     };
                     pair.next_should_be_none();
                     ret.push(node);
                 }
+                parser::Rule::named_union_item_decl => {
+                    let mut pair = item.into_inner();
+                    let name = pair.next_string();
+                    let typ = pair.next_string();
+                    let id = if pair.peek().is_some() {
+                        pair.next_usize()
+                    } else if let Some(pre_id) = previous_id {
+                        pre_id + 1
+                    } else {
+                        0
+                    };
+                    let node = ast::CustomUnionItemDecl {
+                        name: Some(name),
+                        typ,
+                        id,
+                    };
+                    pair.next_should_be_none();
+                    ret.push(node);
+                }
                 _ => unreachable!(),
             }
 
@@ -85,10 +123,10 @@ impl utils::PairsUtils for Pairs<'_, parser::Rule> {
         }
         // union items should be sorted by custom ID
         ret.sort_by_key(|item| item.id);
-        ret
+        (ret, reserved_ids)
     }
 
-    fn next_fields(&mut self) -> Vec<ast::FieldDecl> {
+    fn next_fields(&mut self, ast: &mut ast::Ast, imported_depth: usize) -> Vec<ast::FieldDecl> {
         let mut ret = Vec::new();
         for field in self {
             if field.as_rule() != parser::Rule::field_decl {
@@ -97,7 +135,8 @@ impl utils::PairsUtils for Pairs<'_, parser::Rule> {
             let mut pair = field.into_inner();
             let node = ast::FieldDecl {
                 name: pair.next_string(),
-                typ: pair.next_string(),
+                typ: field_type(pair.next().unwrap(), ast, imported_depth),
+                default: pair.next().map(|value| value.as_str().to_owned()),
             };
             pair.next_should_be_none();
             ret.push(node);
@@ -109,6 +148,7 @@ impl utils::PairsUtils for Pairs<'_, parser::Rule> {
         &mut self,
         imported_base: &P,
         imported_depth: usize,
+        range: Range<usize>,
     ) -> ast::ImportStmt {
         let mut paths = Vec::new();
         let mut path_supers = 0;
@@ -137,6 +177,8 @@ impl utils::PairsUtils for Pairs<'_, parser::Rule> {
             path_supers,
             imported_base: imported_base.as_ref().to_path_buf(),
             imported_depth,
+            range,
+            decls: Vec::new(),
         }
     }
 
@@ -147,8 +189,86 @@ impl utils::PairsUtils for Pairs<'_, parser::Rule> {
     }
 }
 
+/// Returns the name of the field type.
+///
+/// For an anonymous type, a deterministic name is synthesized from its structure, and the
+/// type is added into the AST.
+fn field_type(pair: Pair<'_, parser::Rule>, ast: &mut ast::Ast, imported_depth: usize) -> String {
+    match pair.as_rule() {
+        parser::Rule::identifier => pair.as_str().to_owned(),
+        parser::Rule::vector_type => {
+            let mut pair = pair.into_inner();
+            let item = field_type(pair.next().unwrap(), ast, imported_depth);
+            pair.next_should_be_none();
+            let name = if item == "byte" {
+                "Bytes".to_owned()
+            } else {
+                format!("{}Vec", item)
+            };
+            ast.add_anonymous_decl(ast::VectorDecl {
+                name: name.clone(),
+                item: ast::ItemDecl { typ: item },
+                imported_depth,
+            });
+            name
+        }
+        parser::Rule::option_type => {
+            let mut pair = pair.into_inner();
+            let item = field_type(pair.next().unwrap(), ast, imported_depth);
+            pair.next_should_be_none();
+            let name = if item == "byte" {
+                "ByteOpt".to_owned()
+            } else {
+                format!("{}Opt", item)
+            };
+            ast.add_anonymous_decl(ast::OptionDecl {
+                name: name.clone(),
+                item: ast::ItemDecl { typ: item },
+                imported_depth,
+            });
+            name
+        }
+        parser::Rule::array_type => {
+            let mut pair = pair.into_inner();
+            let item = field_type(pair.next().unwrap(), ast, imported_depth);
+            let item_count = pair.next_usize();
+            pair.next_should_be_none();
+            let name = if item == "byte" {
+                format!("Byte{}", item_count)
+            } else {
+                format!("{}Array{}", item, item_count)
+            };
+            ast.add_anonymous_decl(ast::ArrayDecl {
+                name: name.clone(),
+                item: ast::ItemDecl { typ: item },
+                item_count,
+                imported_depth,
+            });
+            name
+        }
+        parser::Rule::string_type => {
+            let name = "String".to_owned();
+            ast.add_anonymous_decl(ast::StringDecl {
+                name: name.clone(),
+                imported_depth,
+            });
+            name
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn range_of(pair: &Pair<'_, parser::Rule>) -> Range<usize> {
+    let span = pair.as_span();
+    span.start()..span.end()
+}
+
 impl utils::ParserUtils for parser::Parser {
-    fn preprocess<P: AsRef<Path>>(path: &P) -> Result<ast::Ast, Box<PestError<parser::Rule>>> {
+    fn preprocess_source<P: AsRef<Path>>(
+        path: &P,
+        sources: &dyn Fn(&Path) -> Option<String>,
+        import_paths: &[PathBuf],
+    ) -> Result<ast::Ast, Box<PestError<parser::Rule>>> {
         let namespace = path
             .as_ref()
             .file_stem()
@@ -163,30 +283,33 @@ impl utils::ParserUtils for parser::Parser {
 
         let mut imported_depth = 0;
 
-        Self::preprocess_single(&mut ast, path, imported_depth)?;
+        let source = sources(path.as_ref());
+        Self::preprocess_single(&mut ast, path, source, imported_depth)?;
 
-        let mut path_bufs = Vec::new();
+        let mut path_bufs: Vec<PathBuf> = Vec::new();
 
         let mut imports = Vec::new();
 
+        // the declarations in each imported file, and the files which each file imports
+        let mut file_decls = Vec::new();
+        let mut edges = Vec::new();
+
         while !ast.imports.is_empty() {
             imported_depth += 1;
             while !ast.imports.is_empty() {
                 let stmt = ast.imports.remove(0);
-                let mut path_buf = stmt.imported_base().clone();
-                path_buf.pop();
-                for _ in 0..stmt.path_supers() {
-                    path_buf.push("..");
-                }
-                for p in stmt.paths() {
-                    path_buf.push(p);
-                }
-                path_buf.push(stmt.name());
-                path_buf.set_extension("mol");
+                let path_buf = stmt.resolved_file_path(import_paths);
                 let path_new = path_buf.as_path();
-                if is_same_file(path, path_new).unwrap() {
-                    panic!("found cyclic dependencies");
+                {
+                    let _scope = parser::SpanScope::enter(stmt.span());
+                    if !path_new.is_file() {
+                        panic!("the imported file {} is not found", path_new.display());
+                    }
+                    if is_same_file(path, path_new).unwrap() {
+                        panic!("found cyclic dependencies");
+                    }
                 }
+                edges.push((stmt.imported_base().clone(), path_buf.clone()));
 
                 if path_bufs
                     .iter()
@@ -195,12 +318,43 @@ impl utils::ParserUtils for parser::Parser {
                     continue;
                 } else {
                     imports.push(stmt);
-                    Self::preprocess_single(&mut ast, &path_new, imported_depth)?;
+                    let decls_count = ast.decls.len();
+                    let source = sources(path_new);
+                    Self::preprocess_single(&mut ast, &path_new, source, imported_depth)?;
+                    let decls = ast.decls[decls_count..]
+                        .iter()
+                        .map(|decl| decl.name().to_owned())
+                        .collect::<Vec<_>>();
+                    file_decls.push((path_buf.clone(), decls));
                     path_bufs.push(path_buf);
                 }
             }
         }
 
+        for stmt in imports.iter_mut().filter(|stmt| stmt.imported_depth() == 0) {
+            let mut visited: Vec<PathBuf> = Vec::new();
+            let mut pending = vec![stmt.resolved_file_path(import_paths)];
+            while let Some(current) = pending.pop() {
+                if visited
+                    .iter()
+                    .any(|path_old| is_same_file(path_old, &current).unwrap())
+                {
+                    continue;
+                }
+                for (path_old, decls) in &file_decls {
+                    if is_same_file(path_old, &current).unwrap() {
+                        stmt.decls.extend(decls.iter().cloned());
+                    }
+                }
+                for (from, to) in &edges {
+                    if is_same_file(from, &current).unwrap() {
+                        pending.push(to.clone());
+                    }
+                }
+                visited.push(current);
+            }
+        }
+
         ast.imports = imports;
 
         Ok(ast)
@@ -211,15 +365,24 @@ impl parser::Parser {
     fn preprocess_single<P: AsRef<Path>>(
         ast: &mut ast::Ast,
         path: &P,
+        source: Option<String>,
         imported_depth: usize,
     ) -> Result<(), Box<PestError<parser::Rule>>> {
-        let buffer = {
-            let mut buffer = String::new();
-            let mut file_in = fs::OpenOptions::new().read(true).open(path).unwrap();
-            file_in.read_to_string(&mut buffer).unwrap();
-            buffer
+        let buffer = if let Some(source) = source {
+            source
+        } else {
+            fs::read_to_string(path)
+                .unwrap_or_else(|err| panic!("failed to read {}: {}", path.as_ref().display(), err))
         };
-        let mut file_content = parser::InnerParser::parse(parser::Rule::grammar, &buffer)?;
+        let mut file_content =
+            parser::InnerParser::parse(parser::Rule::grammar, &buffer).map_err(|err| {
+                let range = match err.location {
+                    InputLocation::Pos(pos) => pos..pos,
+                    InputLocation::Span((start, end)) => start..end,
+                };
+                parser::set_span(Some(parser::Span::new(path, range)));
+                err
+            })?;
         let grammar = file_content
             .next()
             .unwrap_or_else(|| panic!("grammar should only have one pair"));
@@ -231,12 +394,19 @@ impl parser::Parser {
             if eoi {
                 panic!("grammar should have only one EOI");
             }
+            // the errors of a declaration are located at its name
+            let range = match pair.as_rule() {
+                parser::Rule::syntax_version_stmt
+                | parser::Rule::import_stmt
+                | parser::Rule::EOI => range_of(&pair),
+                _ => range_of(&pair.clone().into_inner().next().unwrap()),
+            };
+            let span = parser::Span::new(path, range.clone());
+            let _scope = parser::SpanScope::enter(span.clone());
             match pair.as_rule() {
                 parser::Rule::syntax_version_stmt => {
                     let mut pair = pair.into_inner();
-                    let syntax_version = SyntaxVersion {
-                        version: pair.next_usize(),
-                    };
+                    let syntax_version = SyntaxVersion::new(pair.next_usize());
                     pair.next_should_be_none();
                     if ast.syntax_version.is_some() {
                         // compare ast.syntax_version and syntax_version
@@ -250,7 +420,7 @@ impl parser::Parser {
                 }
                 parser::Rule::import_stmt => {
                     let mut pair = pair.into_inner();
-                    let node = pair.next_import(path, imported_depth);
+                    let node = pair.next_import(path, imported_depth, range);
                     pair.next_should_be_none();
                     ast.add_import(node);
                 }
@@ -262,17 +432,20 @@ impl parser::Parser {
                         imported_depth,
                     };
                     pair.next_should_be_none();
-                    ast.add_decl(node);
+                    ast.add_decl(node, span);
                 }
                 parser::Rule::union_decl => {
                     let mut pair = pair.into_inner();
+                    let name = pair.next_string();
+                    let (items, reserved_ids) = pair.next_custom_union_items();
                     let node = ast::UnionDecl {
-                        name: pair.next_string(),
-                        items: pair.next_custom_union_items(),
+                        name,
+                        items,
+                        reserved_ids,
                         imported_depth,
                     };
                     pair.next_should_be_none();
-                    ast.add_decl(node);
+                    ast.add_decl(node, span);
                 }
                 parser::Rule::array_decl => {
                     let mut pair = pair.into_inner();
@@ -283,17 +456,17 @@ impl parser::Parser {
                         imported_depth,
                     };
                     pair.next_should_be_none();
-                    ast.add_decl(node);
+                    ast.add_decl(node, span);
                 }
                 parser::Rule::struct_decl => {
                     let mut pair = pair.into_inner();
                     let node = ast::StructDecl {
                         name: pair.next_string(),
-                        fields: pair.next_fields(),
+                        fields: pair.next_fields(ast, imported_depth),
                         imported_depth,
                     };
                     pair.next_should_be_none();
-                    ast.add_decl(node);
+                    ast.add_decl(node, span);
                 }
                 parser::Rule::vector_decl => {
                     let mut pair = pair.into_inner();
@@ -303,17 +476,47 @@ impl parser::Parser {
                         imported_depth,
                     };
                     pair.next_should_be_none();
-                    ast.add_decl(node);
+                    ast.add_decl(node, span);
                 }
                 parser::Rule::table_decl => {
                     let mut pair = pair.into_inner();
                     let node = ast::TableDecl {
                         name: pair.next_string(),
-                        fields: pair.next_fields(),
+                        fields: pair.next_fields(ast, imported_depth),
+                        imported_depth,
+                    };
+                    pair.next_should_be_none();
+                    ast.add_decl(node, span);
+                }
+                parser::Rule::string_decl => {
+                    let mut pair = pair.into_inner();
+                    let node = ast::StringDecl {
+                        name: pair.next_string(),
+                        imported_depth,
+                    };
+                    pair.next_should_be_none();
+                    ast.add_decl(node, span);
+                }
+                parser::Rule::set_decl => {
+                    let mut pair = pair.into_inner();
+                    let node = ast::SetDecl {
+                        name: pair.next_string(),
+                        item: pair.next_item(),
+                        imported_depth,
+                    };
+                    pair.next_should_be_none();
+                    ast.add_decl(node, span);
+                }
+                parser::Rule::map_decl => {
+                    let mut pair = pair.into_inner();
+                    let node = ast::MapDecl {
+                        name: pair.next_string(),
+                        key: pair.next_item(),
+                        value: pair.next_item(),
                         imported_depth,
                     };
                     pair.next_should_be_none();
-                    ast.add_decl(node);
+                    ast.add_decl(node, span);
                 }
                 parser::Rule::EOI => {
                     if eoi {
@@ -361,7 +564,7 @@ mod tests {
         use utils::ParserUtils;
         // get path of  file
         let mut schema_file = tempfile::NamedTempFile::new().unwrap();
-        let test_version = SyntaxVersion { version: 7 };
+        let test_version = SyntaxVersion { version: 2 };
         schema_file
             .write_fmt(format_args!("syntax = {};", test_version.version))
             .unwrap();
@@ -374,6 +577,21 @@ mod tests {
         assert_eq!(ast.syntax_version, Some(test_version));
     }
 
+    #[test]
+    #[should_panic]
+    fn test_unknown_syntax_version_should_panic() {
+        use utils::ParserUtils;
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        let _ = schema_file
+            .write(b"syntax = 99; array uint32 [byte; 4];")
+            .unwrap();
+        schema_file.flush().unwrap();
+
+        let file = schema_file.into_temp_path();
+
+        parser::Parser::preprocess(&file).unwrap();
+    }
+
     #[test]
     #[should_panic]
     // if A `syntax = 1` schema file imports a `syntax = 2` schema file, it should panic
@@ -4,6 +4,7 @@ use std::{
 };
 
 use super::super::raw;
+use crate::parser::SpanScope;
 
 trait CompleteRawDecl {
     fn complete(&self, deps: &super::Deps) -> Option<super::TopDecl>;
@@ -18,6 +19,7 @@ impl CompleteRawDecl for raw::OptionDecl {
                 name,
                 item,
                 imported_depth: self.imported_depth(),
+                schema_hash: Default::default(),
             }
             .into()
         })
@@ -32,17 +34,22 @@ impl CompleteRawDecl for raw::UnionDecl {
         self.items()
             .iter()
             .map(|raw_item| {
-                deps.get(raw_item.typ())
-                    .map(|typ| super::UnionItemDecl::new(typ, raw_item.id()))
+                deps.get(raw_item.typ()).map(|typ| {
+                    super::UnionItemDecl::new(
+                        raw_item.name().map(String::as_str),
+                        typ,
+                        raw_item.id(),
+                    )
+                })
             })
             .collect::<Option<Vec<_>>>()
             .map(|items| {
-                let name = self.name().to_owned();
-                super::Union {
-                    name,
+                super::Union::new(
+                    self.name(),
                     items,
-                    imported_depth: self.imported_depth(),
-                }
+                    self.reserved_ids().to_owned(),
+                    self.imported_depth(),
+                )
                 .into()
             })
     }
@@ -70,6 +77,7 @@ impl CompleteRawDecl for raw::ArrayDecl {
                 item_count,
                 imported_depth: self.imported_depth(),
                 item_size,
+                schema_hash: Default::default(),
             }
             .into()
         })
@@ -92,7 +100,15 @@ impl CompleteRawDecl for raw::StructDecl {
                         self.name(),
                     );
                 }
-                let field = super::FieldDecl::new(field_name, dep);
+                let field = super::FieldDecl::new(field_name, dep)
+                    .with_default(raw_field.default().map(String::as_str), self.name());
+                if field.reserved() {
+                    panic!(
+                        "the reserved field ({}) is only allowed in tables, not in struct ({})",
+                        field_name,
+                        self.name(),
+                    );
+                }
                 fields.push(field);
             } else {
                 break;
@@ -111,6 +127,8 @@ impl CompleteRawDecl for raw::StructDecl {
                 fields,
                 imported_depth: self.imported_depth(),
                 field_sizes,
+                default_content: Default::default(),
+                schema_hash: Default::default(),
             }
             .into(),
         )
@@ -128,6 +146,9 @@ impl CompleteRawDecl for raw::VectorDecl {
                     item,
                     imported_depth: self.imported_depth(),
                     item_size,
+                    collection: None,
+                    utf8: false,
+                    schema_hash: Default::default(),
                 }
                 .into()
             } else {
@@ -135,6 +156,8 @@ impl CompleteRawDecl for raw::VectorDecl {
                     name,
                     item,
                     imported_depth: self.imported_depth(),
+                    collection: None,
+                    schema_hash: Default::default(),
                 }
                 .into()
             }
@@ -142,14 +165,81 @@ impl CompleteRawDecl for raw::VectorDecl {
     }
 }
 
+impl CompleteRawDecl for raw::StringDecl {
+    fn complete(&self, deps: &super::Deps) -> Option<super::TopDecl> {
+        deps.get("byte").map(|dep| {
+            let name = self.name().to_owned();
+            let item = super::ItemDecl::new(dep);
+            super::FixVec {
+                name,
+                item,
+                imported_depth: self.imported_depth(),
+                item_size: 1,
+                collection: None,
+                utf8: true,
+                schema_hash: Default::default(),
+            }
+            .into()
+        })
+    }
+}
+
+impl CompleteRawDecl for raw::SetDecl {
+    fn complete(&self, deps: &super::Deps) -> Option<super::TopDecl> {
+        deps.get(self.item().typ()).map(|dep| {
+            let name = self.name().to_owned();
+            super::TopDecl::new_collection(name, dep, self.imported_depth(), super::Collection::Set)
+        })
+    }
+}
+
+impl CompleteRawDecl for raw::MapDecl {
+    fn complete(&self, deps: &super::Deps) -> Option<super::TopDecl> {
+        let key = deps.get(self.key().typ())?;
+        let value = deps.get(self.value().typ())?;
+        let entry_name = self.entry_name();
+        let fields = vec![
+            super::FieldDecl::new("key", key),
+            super::FieldDecl::new("value", value),
+        ];
+        let entry: super::TopDecl = match (key.total_size(), value.total_size()) {
+            (Some(key_size), Some(value_size)) => super::Struct {
+                name: entry_name,
+                fields,
+                imported_depth: self.imported_depth(),
+                field_sizes: vec![key_size, value_size],
+                default_content: Default::default(),
+                schema_hash: Default::default(),
+            }
+            .into(),
+            _ => super::Table {
+                name: entry_name,
+                fields,
+                imported_depth: self.imported_depth(),
+                schema_hash: Default::default(),
+            }
+            .into(),
+        };
+        let name = self.name().to_owned();
+        Some(super::TopDecl::new_collection(
+            name,
+            &Rc::new(entry),
+            self.imported_depth(),
+            super::Collection::Map,
+        ))
+    }
+}
+
 impl CompleteRawDecl for raw::TableDecl {
     fn complete(&self, deps: &super::Deps) -> Option<super::TopDecl> {
         self.fields()
             .iter()
             .map(|raw_field| {
                 let field_name = raw_field.name();
-                deps.get(raw_field.typ())
-                    .map(|dep| super::FieldDecl::new(field_name, dep))
+                deps.get(raw_field.typ()).map(|dep| {
+                    super::FieldDecl::new(field_name, dep)
+                        .with_default(raw_field.default().map(String::as_str), self.name())
+                })
             })
             .collect::<Option<Vec<_>>>()
             .map(|fields| {
@@ -158,6 +248,7 @@ impl CompleteRawDecl for raw::TableDecl {
                     name,
                     fields,
                     imported_depth: self.imported_depth(),
+                    schema_hash: Default::default(),
                 }
                 .into()
             })
@@ -166,9 +257,29 @@ impl CompleteRawDecl for raw::TableDecl {
 
 impl super::Ast {
     pub(crate) fn complete(raw: raw::Ast) -> Self {
+        // the anonymous types which are the same as the declared types are not required
+        let anonymous_decls = raw
+            .anonymous_decls()
+            .iter()
+            .filter(|anonymous| {
+                if let Some(decl) = raw.decls().iter().find(|d| d.name() == anonymous.name()) {
+                    let _scope = raw.span(decl).cloned().map(SpanScope::enter);
+                    if !decl.is_same_structure(anonymous) {
+                        panic!(
+                            "the name `{}` is used more than once, it's the name of an anonymous type",
+                            anonymous.name()
+                        );
+                    }
+                    false
+                } else {
+                    true
+                }
+            })
+            .collect::<Vec<_>>();
         let mut decls_idx = HashMap::new();
         let mut decls_keys = HashSet::new();
-        for decl in raw.decls() {
+        for decl in anonymous_decls.iter().copied().chain(raw.decls()) {
+            let _scope = raw.span(decl).cloned().map(SpanScope::enter);
             let name = decl.name();
             if super::TopDecl::new_primitive(name.to_lowercase().as_str()).is_some() {
                 panic!("the name `{}` is reserved", name);
@@ -186,6 +297,22 @@ impl super::Ast {
                 panic!("the name `{}` is used more than once, It seems that only the capitalization is inconsistent", name);
             }
         }
+        for decl in raw.decls() {
+            if let raw::TopDecl::Map(map) = decl {
+                let _scope = raw.span(decl).cloned().map(SpanScope::enter);
+                let entry_name = map.entry_name();
+                if decls_keys
+                    .iter()
+                    .any(|name| name.to_lowercase() == entry_name.to_lowercase())
+                {
+                    panic!(
+                        "the name `{}` is used more than once, it's the entry type of the map ({})",
+                        entry_name,
+                        map.name()
+                    );
+                }
+            }
+        }
         let mut decls_result = HashMap::new();
         decls_result.insert(
             "byte",
@@ -198,6 +325,7 @@ impl super::Ast {
             let incompleted = decls_keys.len();
             decls_keys.retain(|&name| {
                 let decl_raw = decls_idx.get(name).unwrap();
+                let _scope = raw.span(decl_raw).cloned().map(SpanScope::enter);
                 if let Some(decl) = super::TopDecl::complete(decl_raw, &decls_result) {
                     decls_result.insert(name, Rc::new(decl));
                     false
@@ -206,6 +334,14 @@ impl super::Ast {
                 }
             });
             if decls_keys.len() == incompleted {
+                // locate the error at the first declaration which is unable to be completed
+                let _scope = raw
+                    .decls()
+                    .iter()
+                    .find(|decl| decls_keys.contains(decl.name()))
+                    .and_then(|decl| raw.span(decl))
+                    .cloned()
+                    .map(SpanScope::enter);
                 panic!(
                     "there are {} types which are unable to be completed: {:?}",
                     incompleted, decls_keys
@@ -220,20 +356,33 @@ impl super::Ast {
             .map(super::ImportStmt::complete)
             .collect();
         // remove the primitive types and keep the order
-        let mut decls = Vec::with_capacity(raw.decls().len());
-        for decl in raw.decls() {
+        let mut decls = Vec::with_capacity(anonymous_decls.len() + raw.decls().len());
+        for decl in anonymous_decls.iter().copied().chain(raw.decls()) {
             let result = decls_result.get(decl.name()).unwrap();
+            if let raw::TopDecl::Map(_) = decl {
+                // the entry type of a map is declared just before the map
+                if let super::TopDecl::FixVec(inner) = result.as_ref() {
+                    decls.push(Rc::clone(inner.item().typ()));
+                } else if let super::TopDecl::DynVec(inner) = result.as_ref() {
+                    decls.push(Rc::clone(inner.item().typ()));
+                }
+            }
             decls.push(Rc::clone(result));
         }
 
         let syntax_version = raw.syntax_version().unwrap().to_owned();
+        if !raw.anonymous_decls().is_empty() {
+            syntax_version.require(super::Feature::AnonymousTypes);
+        }
 
-        Self {
+        let ast = Self {
             syntax_version,
             namespace,
             imports,
             decls,
-        }
+        };
+        ast.check_features(|name| raw.span_of_name(name).cloned());
+        ast
     }
 }
 
@@ -243,6 +392,7 @@ impl super::ImportStmt {
             name: raw.name().to_owned(),
             paths: raw.paths().to_owned(),
             path_supers: raw.path_supers(),
+            decls: raw.decls().to_owned(),
         }
     }
 }
@@ -256,6 +406,39 @@ impl super::TopDecl {
             raw::TopDecl::Struct(inner) => inner.complete(deps),
             raw::TopDecl::Vector(inner) => inner.complete(deps),
             raw::TopDecl::Table(inner) => inner.complete(deps),
+            raw::TopDecl::String_(inner) => inner.complete(deps),
+            raw::TopDecl::Set(inner) => inner.complete(deps),
+            raw::TopDecl::Map(inner) => inner.complete(deps),
+        }
+    }
+
+    fn new_collection(
+        name: String,
+        dep: &Rc<Self>,
+        imported_depth: usize,
+        collection: super::Collection,
+    ) -> Self {
+        let item = super::ItemDecl::new(dep);
+        if let Some(item_size) = dep.total_size() {
+            super::FixVec {
+                name,
+                item,
+                imported_depth,
+                item_size,
+                collection: Some(collection),
+                utf8: false,
+                schema_hash: Default::default(),
+            }
+            .into()
+        } else {
+            super::DynVec {
+                name,
+                item,
+                imported_depth,
+                collection: Some(collection),
+                schema_hash: Default::default(),
+            }
+            .into()
         }
     }
 }
@@ -36,7 +36,16 @@ impl DefaultContent for super::Array {
 
 impl DefaultContent for super::Struct {
     fn default_content(&self) -> Vec<u8> {
-        vec![0; self.total_size()]
+        // cached, since the same struct could be used by many fields
+        self.default_content
+            .get_or_init(|| {
+                let mut content = Vec::with_capacity(self.total_size());
+                for field in self.fields() {
+                    content.extend_from_slice(&field.default_content());
+                }
+                content
+            })
+            .clone()
     }
 }
 
@@ -75,7 +84,7 @@ impl DefaultContent for super::Table {
                 ),
                 |(mut current_offset, mut offsets, mut field_data), field| {
                     offsets.push(current_offset);
-                    let data = field.typ.default_content();
+                    let data = field.default_content();
                     current_offset += data.len();
                     field_data.push(data);
                     (current_offset, offsets, field_data)
@@ -96,6 +105,14 @@ impl DefaultContent for super::Table {
     }
 }
 
+impl DefaultContent for super::FieldDecl {
+    fn default_content(&self) -> Vec<u8> {
+        self.default
+            .clone()
+            .unwrap_or_else(|| self.typ.default_content())
+    }
+}
+
 impl DefaultContent for super::TopDecl {
     fn default_content(&self) -> Vec<u8> {
         match self {
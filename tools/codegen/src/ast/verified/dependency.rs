@@ -0,0 +1,189 @@
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use super::{
+    Array, Ast, DynVec, FieldDecl, FixVec, HasName as _, ItemDecl, Option_, Struct, Table, TopDecl,
+    Union, UnionItemDecl,
+};
+
+impl TopDecl {
+    /// The types which are used by the declaration directly, the primitive types are not
+    /// included.
+    pub fn dependencies(&self) -> Vec<&Rc<TopDecl>> {
+        let deps: Vec<&Rc<TopDecl>> = match self {
+            Self::Primitive(_) => Vec::new(),
+            Self::Option_(inner) => vec![inner.item().typ()],
+            Self::Union(inner) => inner.items().iter().map(|item| item.typ()).collect(),
+            Self::Array(inner) => vec![inner.item().typ()],
+            Self::Struct(inner) => inner.fields().iter().map(|field| field.typ()).collect(),
+            Self::FixVec(inner) => vec![inner.item().typ()],
+            Self::DynVec(inner) => vec![inner.item().typ()],
+            Self::Table(inner) => inner.fields().iter().map(|field| field.typ()).collect(),
+        };
+        let mut names = HashSet::new();
+        deps.into_iter()
+            .filter(|dep| !matches!(dep.as_ref(), Self::Primitive(_)))
+            .filter(|dep| names.insert(dep.name()))
+            .collect()
+    }
+
+    /// Copies the declaration as it's not imported, the dependencies are replaced by the
+    /// localized declarations.
+    fn localize(&self, localized: &HashMap<String, Rc<TopDecl>>) -> Self {
+        let dep = |typ: &Rc<TopDecl>| {
+            localized
+                .get(typ.name())
+                .map(Rc::clone)
+                .unwrap_or_else(|| Rc::clone(typ))
+        };
+        let item = |item: &ItemDecl| ItemDecl {
+            typ: dep(&item.typ),
+        };
+        let fields = |fields: &[FieldDecl]| {
+            fields
+                .iter()
+                .map(|field| FieldDecl {
+                    name: field.name.clone(),
+                    typ: dep(&field.typ),
+                    reserved: field.reserved,
+                    default: field.default.clone(),
+                })
+                .collect()
+        };
+        match self {
+            Self::Primitive(_) => unreachable!(),
+            Self::Option_(inner) => Self::Option_(Option_ {
+                name: inner.name.clone(),
+                item: item(&inner.item),
+                imported_depth: 0,
+                schema_hash: Default::default(),
+            }),
+            Self::Union(inner) => Self::Union(Union {
+                name: inner.name.clone(),
+                items: inner
+                    .items
+                    .iter()
+                    .map(|union_item| UnionItemDecl {
+                        name: union_item.name.clone(),
+                        typ: dep(&union_item.typ),
+                        id: union_item.id,
+                    })
+                    .collect(),
+                reserved_ids: inner.reserved_ids.clone(),
+                imported_depth: 0,
+                schema_hash: Default::default(),
+            }),
+            Self::Array(inner) => Self::Array(Array {
+                name: inner.name.clone(),
+                item: item(&inner.item),
+                item_count: inner.item_count,
+                imported_depth: 0,
+                item_size: inner.item_size,
+                schema_hash: Default::default(),
+            }),
+            Self::Struct(inner) => Self::Struct(Struct {
+                name: inner.name.clone(),
+                fields: fields(&inner.fields),
+                imported_depth: 0,
+                field_sizes: inner.field_sizes.clone(),
+                default_content: Default::default(),
+                schema_hash: Default::default(),
+            }),
+            Self::FixVec(inner) => Self::FixVec(FixVec {
+                name: inner.name.clone(),
+                item: item(&inner.item),
+                imported_depth: 0,
+                item_size: inner.item_size,
+                collection: inner.collection,
+                utf8: inner.utf8,
+                schema_hash: Default::default(),
+            }),
+            Self::DynVec(inner) => Self::DynVec(DynVec {
+                name: inner.name.clone(),
+                item: item(&inner.item),
+                imported_depth: 0,
+                collection: inner.collection,
+                schema_hash: Default::default(),
+            }),
+            Self::Table(inner) => Self::Table(Table {
+                name: inner.name.clone(),
+                fields: fields(&inner.fields),
+                imported_depth: 0,
+                schema_hash: Default::default(),
+            }),
+        }
+    }
+}
+
+impl Ast {
+    /// All declarations, including the imported declarations, in an order which each
+    /// declaration is after all its dependencies.
+    ///
+    /// The declarations which don't depend on each other are kept in the original order.
+    pub fn dependency_order(&self) -> Vec<Rc<TopDecl>> {
+        let mut visited = HashSet::new();
+        let mut ordered = Vec::with_capacity(self.decls.len());
+        for decl in &self.decls {
+            visit(decl, &mut visited, &mut ordered);
+        }
+        ordered
+    }
+
+    /// The declarations which are reachable from the roots, including the roots, in the
+    /// dependency order.
+    pub fn reachable_decls(&self, roots: &[&str]) -> Result<Vec<Rc<TopDecl>>, String> {
+        let mut visited = HashSet::new();
+        let mut ordered = Vec::new();
+        for root in roots {
+            let decl = self
+                .decls
+                .iter()
+                .find(|decl| decl.name() == *root)
+                .ok_or_else(|| format!("the type `{}` is not found", root))?;
+            visit(decl, &mut visited, &mut ordered);
+        }
+        let reachable = ordered
+            .iter()
+            .map(|decl| decl.name().to_owned())
+            .collect::<HashSet<_>>();
+        // keep the dependency order of all declarations
+        Ok(self
+            .dependency_order()
+            .into_iter()
+            .filter(|decl| reachable.contains(decl.name()))
+            .collect())
+    }
+
+    /// Only keeps the declarations which are reachable from the roots.
+    ///
+    /// The imported declarations which are reachable are declared in the AST itself, and the
+    /// imports are removed, so the code which is generated from the AST doesn't depend on the
+    /// code of the imported files.
+    pub(crate) fn into_reachable(self, roots: &[&str]) -> Result<Self, String> {
+        let mut localized: HashMap<String, Rc<TopDecl>> = HashMap::new();
+        let mut decls = Vec::new();
+        for decl in self.reachable_decls(roots)? {
+            let decl = Rc::new(decl.localize(&localized));
+            localized.insert(decl.name().to_owned(), Rc::clone(&decl));
+            decls.push(decl);
+        }
+        Ok(Self {
+            syntax_version: self.syntax_version,
+            namespace: self.namespace,
+            imports: Vec::new(),
+            decls,
+        })
+    }
+}
+
+fn visit(decl: &Rc<TopDecl>, visited: &mut HashSet<String>, ordered: &mut Vec<Rc<TopDecl>>) {
+    if !visited.insert(decl.name().to_owned()) {
+        return;
+    }
+    for dep in decl.dependencies() {
+        visit(dep, visited, ordered);
+    }
+    ordered.push(Rc::clone(decl));
+}
@@ -0,0 +1,337 @@
+use std::fmt;
+
+use property::Property;
+
+#[cfg(feature = "compiler-plugin")]
+use schemars::JsonSchema;
+#[cfg(feature = "compiler-plugin")]
+use serde::{Deserialize, Serialize};
+
+use super::{HasName as _, TopDecl};
+
+/// The encoding of a declaration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "compiler-plugin",
+    derive(Deserialize, Serialize, JsonSchema),
+    serde(rename_all = "lowercase")
+)]
+pub enum Encoding {
+    Byte,
+    Option,
+    Union,
+    Array,
+    Struct,
+    FixVec,
+    DynVec,
+    Table,
+}
+
+/// The layout of the serialized data of a declaration.
+///
+/// A size is `None` if it's dynamic.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property)]
+#[property(get(public))]
+#[cfg_attr(
+    feature = "compiler-plugin",
+    derive(Deserialize, Serialize, JsonSchema)
+)]
+pub struct Layout {
+    pub(crate) name: String,
+    #[property(get(type = "copy"))]
+    pub(crate) encoding: Encoding,
+    #[property(get(type = "copy"))]
+    pub(crate) total_size: Option<usize>,
+    #[cfg_attr(
+        feature = "compiler-plugin",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) header: Option<HeaderLayout>,
+    #[cfg_attr(
+        feature = "compiler-plugin",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) item: Option<ItemLayout>,
+    #[cfg_attr(
+        feature = "compiler-plugin",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub(crate) fields: Vec<FieldLayout>,
+    #[cfg_attr(
+        feature = "compiler-plugin",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub(crate) items: Vec<UnionItemLayout>,
+}
+
+/// The header size is `size + size_per_item * item_count`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Property)]
+#[property(get(public, type = "copy"))]
+#[cfg_attr(
+    feature = "compiler-plugin",
+    derive(Deserialize, Serialize, JsonSchema)
+)]
+pub struct HeaderLayout {
+    pub(crate) size: usize,
+    pub(crate) size_per_item: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property)]
+#[property(get(public))]
+#[cfg_attr(
+    feature = "compiler-plugin",
+    derive(Deserialize, Serialize, JsonSchema)
+)]
+pub struct ItemLayout {
+    #[cfg_attr(feature = "compiler-plugin", serde(rename = "type"))]
+    pub(crate) typ: String,
+    #[property(get(type = "copy"))]
+    pub(crate) size: Option<usize>,
+    #[property(get(type = "copy"))]
+    #[cfg_attr(
+        feature = "compiler-plugin",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) count: Option<usize>,
+}
+
+/// A field of a struct or a table.
+///
+/// The offset is only known for the fields of a struct.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property)]
+#[property(get(public))]
+#[cfg_attr(
+    feature = "compiler-plugin",
+    derive(Deserialize, Serialize, JsonSchema)
+)]
+pub struct FieldLayout {
+    pub(crate) name: String,
+    #[cfg_attr(feature = "compiler-plugin", serde(rename = "type"))]
+    pub(crate) typ: String,
+    #[property(get(type = "copy"))]
+    #[cfg_attr(
+        feature = "compiler-plugin",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) offset: Option<usize>,
+    #[property(get(type = "copy"))]
+    pub(crate) size: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property)]
+#[property(get(public))]
+#[cfg_attr(
+    feature = "compiler-plugin",
+    derive(Deserialize, Serialize, JsonSchema)
+)]
+pub struct UnionItemLayout {
+    #[property(get(type = "copy"))]
+    pub(crate) id: usize,
+    pub(crate) name: String,
+    #[cfg_attr(feature = "compiler-plugin", serde(rename = "type"))]
+    pub(crate) typ: String,
+    #[property(get(type = "copy"))]
+    pub(crate) size: Option<usize>,
+}
+
+impl Encoding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Byte => "byte",
+            Self::Option => "option",
+            Self::Union => "union",
+            Self::Array => "array",
+            Self::Struct => "struct",
+            Self::FixVec => "fixvec",
+            Self::DynVec => "dynvec",
+            Self::Table => "table",
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl TopDecl {
+    pub fn layout(&self) -> Layout {
+        let mut layout = Layout {
+            name: self.name().to_owned(),
+            encoding: Encoding::Byte,
+            total_size: self.total_size(),
+            header: None,
+            item: None,
+            fields: Vec::new(),
+            items: Vec::new(),
+        };
+        match self {
+            Self::Primitive(_) => {}
+            Self::Option_(inner) => {
+                layout.encoding = Encoding::Option;
+                layout.item = Some(ItemLayout::new(inner.item().typ(), None));
+            }
+            Self::Union(inner) => {
+                layout.encoding = Encoding::Union;
+                layout.header = Some(HeaderLayout::new(molecule::NUMBER_SIZE, 0));
+                layout.items = inner
+                    .items()
+                    .iter()
+                    .map(|item| UnionItemLayout {
+                        id: item.id(),
+                        name: item.name().to_owned(),
+                        typ: item.typ().name().to_owned(),
+                        size: item.typ().total_size(),
+                    })
+                    .collect();
+            }
+            Self::Array(inner) => {
+                layout.encoding = Encoding::Array;
+                layout.item = Some(ItemLayout::new(
+                    inner.item().typ(),
+                    Some(inner.item_count()),
+                ));
+            }
+            Self::Struct(inner) => {
+                layout.encoding = Encoding::Struct;
+                let mut offset = 0;
+                layout.fields = inner
+                    .fields()
+                    .iter()
+                    .zip(inner.field_sizes())
+                    .map(|(field, size)| {
+                        let field_layout = FieldLayout {
+                            name: field.name().to_owned(),
+                            typ: field.typ().name().to_owned(),
+                            offset: Some(offset),
+                            size: Some(*size),
+                        };
+                        offset += size;
+                        field_layout
+                    })
+                    .collect();
+            }
+            Self::FixVec(inner) => {
+                layout.encoding = Encoding::FixVec;
+                layout.header = Some(HeaderLayout::new(molecule::NUMBER_SIZE, 0));
+                layout.item = Some(ItemLayout::new(inner.item().typ(), None));
+            }
+            Self::DynVec(inner) => {
+                layout.encoding = Encoding::DynVec;
+                layout.header = Some(HeaderLayout::new(
+                    molecule::NUMBER_SIZE,
+                    molecule::NUMBER_SIZE,
+                ));
+                layout.item = Some(ItemLayout::new(inner.item().typ(), None));
+            }
+            Self::Table(inner) => {
+                layout.encoding = Encoding::Table;
+                layout.header = Some(HeaderLayout::new(
+                    molecule::NUMBER_SIZE * (inner.fields().len() + 1),
+                    0,
+                ));
+                layout.fields = inner
+                    .fields()
+                    .iter()
+                    .map(|field| FieldLayout {
+                        name: field.name().to_owned(),
+                        typ: field.typ().name().to_owned(),
+                        offset: None,
+                        size: field.typ().total_size(),
+                    })
+                    .collect();
+            }
+        }
+        layout
+    }
+}
+
+impl HeaderLayout {
+    fn new(size: usize, size_per_item: usize) -> Self {
+        Self {
+            size,
+            size_per_item,
+        }
+    }
+}
+
+impl ItemLayout {
+    fn new(typ: &TopDecl, count: Option<usize>) -> Self {
+        Self {
+            typ: typ.name().to_owned(),
+            size: typ.total_size(),
+            count,
+        }
+    }
+}
+
+struct Size(Option<usize>);
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(1) => write!(f, "1 byte"),
+            Some(size) => write!(f, "{} bytes", size),
+            None => write!(f, "dynamic"),
+        }
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {}", self.encoding, self.name)?;
+        writeln!(f, "    size: {}", Size(self.total_size))?;
+        if let Some(header) = self.header {
+            if header.size_per_item == 0 {
+                writeln!(f, "    header: {}", Size(Some(header.size)))?;
+            } else {
+                writeln!(
+                    f,
+                    "    header: {} + {} * item_count bytes",
+                    header.size, header.size_per_item
+                )?;
+            }
+        }
+        if let Some(ref item) = self.item {
+            write!(f, "    item: {}, {}", item.typ, Size(item.size))?;
+            if let Some(count) = item.count {
+                write!(f, ", count {}", count)?;
+            }
+            writeln!(f)?;
+        }
+        for (index, field) in self.fields.iter().enumerate() {
+            if let (Some(offset), Some(size)) = (field.offset, field.size) {
+                writeln!(
+                    f,
+                    "    field {}: {} @ [{}..{}], {}",
+                    field.name,
+                    field.typ,
+                    offset,
+                    offset + size,
+                    Size(field.size)
+                )?;
+            } else {
+                writeln!(
+                    f,
+                    "    field {}: {} @ #{}, {}",
+                    field.name,
+                    field.typ,
+                    index,
+                    Size(field.size)
+                )?;
+            }
+        }
+        for item in &self.items {
+            writeln!(
+                f,
+                "    id {} => {}: {}, {}",
+                item.id,
+                item.name,
+                item.typ,
+                Size(item.size)
+            )?;
+        }
+        Ok(())
+    }
+}
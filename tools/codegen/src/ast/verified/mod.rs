@@ -1,18 +1,35 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{
+    cell::OnceCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
 use property::Property;
 
+#[cfg(feature = "compiler-plugin")]
+use schemars::JsonSchema;
+#[cfg(feature = "compiler-plugin")]
+use serde::{Deserialize, Serialize};
+
 mod complete;
 mod default_content;
+mod dependency;
 mod has_name;
+mod layout;
+mod schema_hash;
 
 #[cfg(feature = "compiler-plugin")]
 mod recover;
 
 pub use default_content::DefaultContent;
 pub use has_name::HasName;
+pub use layout::{Encoding, FieldLayout, HeaderLayout, ItemLayout, Layout, UnionItemLayout};
+pub use schema_hash::{SchemaHash, SCHEMA_HASH_SIZE};
 
-use crate::ast::SyntaxVersion;
+use crate::{
+    ast::{Feature, SyntaxVersion},
+    parser::{Span, SpanScope},
+};
 
 type Deps<'a> = HashMap<&'a str, Rc<super::TopDecl>>;
 
@@ -31,6 +48,9 @@ pub struct ImportStmt {
     name: String,
     paths: Vec<String>,
     path_supers: usize,
+    /// The names of the declarations in the imported file and the files which are imported
+    /// by it, it's empty if the AST is recovered from the intermediate data.
+    decls: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -58,6 +78,8 @@ pub struct Option_ {
     name: String,
     item: ItemDecl,
     imported_depth: usize,
+    #[property(skip)]
+    schema_hash: OnceCell<[u8; SCHEMA_HASH_SIZE]>,
 }
 
 #[derive(Debug, Property)]
@@ -65,7 +87,10 @@ pub struct Option_ {
 pub struct Union {
     name: String,
     items: Vec<UnionItemDecl>,
+    reserved_ids: Vec<usize>,
     imported_depth: usize,
+    #[property(skip)]
+    schema_hash: OnceCell<[u8; SCHEMA_HASH_SIZE]>,
 }
 
 #[derive(Debug, Property)]
@@ -76,6 +101,8 @@ pub struct Array {
     item_count: usize,
     imported_depth: usize,
     item_size: usize,
+    #[property(skip)]
+    schema_hash: OnceCell<[u8; SCHEMA_HASH_SIZE]>,
 }
 
 #[derive(Debug, Property)]
@@ -85,6 +112,10 @@ pub struct Struct {
     fields: Vec<FieldDecl>,
     imported_depth: usize,
     field_sizes: Vec<usize>,
+    #[property(skip)]
+    default_content: OnceCell<Vec<u8>>,
+    #[property(skip)]
+    schema_hash: OnceCell<[u8; SCHEMA_HASH_SIZE]>,
 }
 
 #[derive(Debug, Property)]
@@ -94,6 +125,11 @@ pub struct FixVec {
     item: ItemDecl,
     imported_depth: usize,
     item_size: usize,
+    #[property(get(type = "copy"))]
+    collection: Option<Collection>,
+    utf8: bool,
+    #[property(skip)]
+    schema_hash: OnceCell<[u8; SCHEMA_HASH_SIZE]>,
 }
 
 #[derive(Debug, Property)]
@@ -102,6 +138,25 @@ pub struct DynVec {
     name: String,
     item: ItemDecl,
     imported_depth: usize,
+    #[property(get(type = "copy"))]
+    collection: Option<Collection>,
+    #[property(skip)]
+    schema_hash: OnceCell<[u8; SCHEMA_HASH_SIZE]>,
+}
+
+/// The kind of sorted collection which a vector is declared as.
+///
+/// The items of a set, or the keys of a map, should be strictly increasing
+/// by their serialized bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "compiler-plugin",
+    derive(Deserialize, Serialize, JsonSchema),
+    serde(rename_all = "lowercase")
+)]
+pub enum Collection {
+    Set,
+    Map,
 }
 
 #[derive(Debug, Property)]
@@ -110,6 +165,8 @@ pub struct Table {
     name: String,
     fields: Vec<FieldDecl>,
     imported_depth: usize,
+    #[property(skip)]
+    schema_hash: OnceCell<[u8; SCHEMA_HASH_SIZE]>,
 }
 
 #[derive(Debug, Property)]
@@ -121,6 +178,8 @@ pub struct ItemDecl {
 #[derive(Debug, Property)]
 #[property(get(public))]
 pub struct UnionItemDecl {
+    #[property(get(name = "explicit_name"))]
+    name: Option<String>,
     typ: Rc<TopDecl>,
     id: usize,
 }
@@ -130,6 +189,9 @@ pub struct UnionItemDecl {
 pub struct FieldDecl {
     name: String,
     typ: Rc<TopDecl>,
+    #[property(get(type = "copy"))]
+    reserved: bool,
+    default: Option<Vec<u8>>,
 }
 
 impl Ast {
@@ -140,9 +202,91 @@ impl Ast {
             .map(Rc::clone)
             .collect()
     }
+
+    /// The layouts of the declarations which are not imported.
+    pub fn layouts(&self) -> Vec<Layout> {
+        self.major_decls()
+            .iter()
+            .map(|decl| decl.layout())
+            .collect()
+    }
+
+    /// The features which are used by the declarations.
+    ///
+    /// Anonymous types are not included, since they are the same as the declared types
+    /// after verified.
+    pub fn used_features(&self) -> Vec<Feature> {
+        let mut features = HashSet::new();
+        for decl in &self.decls {
+            decl.insert_used_features(&mut features);
+        }
+        Feature::ALL
+            .iter()
+            .copied()
+            .filter(|feature| features.contains(feature))
+            .collect()
+    }
+
+    /// Checks the features of each declaration, the errors are located by `span`.
+    fn check_features(&self, span: impl Fn(&str) -> Option<Span>) {
+        for decl in &self.decls {
+            let _scope = span(decl.name()).map(SpanScope::enter);
+            let mut features = HashSet::new();
+            decl.insert_used_features(&mut features);
+            for feature in Feature::ALL {
+                if features.contains(feature) {
+                    self.syntax_version.require(*feature);
+                }
+            }
+        }
+    }
 }
 
 impl TopDecl {
+    fn insert_used_features(&self, features: &mut HashSet<Feature>) {
+        match self {
+            TopDecl::Union(inner) => {
+                if inner
+                    .items()
+                    .iter()
+                    .any(|item| item.explicit_name().is_some())
+                {
+                    features.insert(Feature::UnionVariantNames);
+                }
+                if !inner.reserved_ids().is_empty() {
+                    features.insert(Feature::Reserved);
+                }
+            }
+            TopDecl::Struct(inner) => {
+                if inner.fields().iter().any(|f| f.default().is_some()) {
+                    features.insert(Feature::FieldDefaults);
+                }
+            }
+            TopDecl::FixVec(inner) => {
+                if inner.collection().is_some() {
+                    features.insert(Feature::Collections);
+                }
+                if inner.utf8() {
+                    features.insert(Feature::Strings);
+                }
+            }
+            TopDecl::DynVec(inner) => {
+                if inner.collection().is_some() {
+                    features.insert(Feature::Collections);
+                }
+            }
+            TopDecl::Table(inner) => {
+                if inner.fields().iter().any(|f| f.default().is_some()) {
+                    features.insert(Feature::FieldDefaults);
+                }
+                if inner.fields().iter().any(|f| f.reserved()) {
+                    features.insert(Feature::Reserved);
+                }
+            }
+            TopDecl::Primitive(_) | TopDecl::Option_(_) | TopDecl::Array(_) => {}
+        }
+    }
+
     fn new_primitive(name: &str) -> Option<Self> {
         match name {
             "byte" => Some(Primitive {
@@ -162,6 +306,20 @@ impl TopDecl {
         }
     }
 
+    /// Returns the key field and the value field if the type is an entry of a map.
+    pub fn entry_fields(&self) -> Option<(&FieldDecl, &FieldDecl)> {
+        let fields = match self {
+            Self::Struct(inner) => inner.fields(),
+            Self::Table(inner) => inner.fields(),
+            _ => return None,
+        };
+        if fields.len() == 2 && fields[0].name() == "key" && fields[1].name() == "value" {
+            Some((&fields[0], &fields[1]))
+        } else {
+            None
+        }
+    }
+
     fn imported_depth(&self) -> usize {
         match self {
             Self::Primitive(_) => usize::MAX,
@@ -175,7 +333,7 @@ impl TopDecl {
         }
     }
 
-    fn total_size(&self) -> Option<usize> {
+    pub(crate) fn total_size(&self) -> Option<usize> {
         match self {
             Self::Primitive(inner) => Some(inner.size),
             Self::Option_(_) => None,
@@ -195,6 +353,60 @@ impl Array {
     }
 }
 
+impl Union {
+    fn new(
+        name: &str,
+        items: Vec<UnionItemDecl>,
+        mut reserved_ids: Vec<usize>,
+        imported_depth: usize,
+    ) -> Self {
+        let mut names = HashSet::new();
+        for item in &items {
+            if !names.insert(item.name()) {
+                panic!(
+                    "the variant name `{}` is used more than once in union ({})",
+                    item.name(),
+                    name
+                );
+            }
+        }
+        let mut ids = HashSet::new();
+        for id in &reserved_ids {
+            if !ids.insert(*id) {
+                panic!(
+                    "the id {} is reserved more than once in union ({})",
+                    id, name
+                );
+            }
+        }
+        if let Some(item) = items.iter().find(|item| ids.contains(&item.id())) {
+            panic!(
+                "the id {} of item ({}) is reserved in union ({})",
+                item.id(),
+                item.name(),
+                name
+            );
+        }
+        reserved_ids.sort_unstable();
+        Self {
+            name: name.to_owned(),
+            items,
+            reserved_ids,
+            imported_depth,
+            schema_hash: Default::default(),
+        }
+    }
+
+    /// Whether the type of the item doesn't appear in other items of the union.
+    pub fn is_unique_item_type(&self, item: &UnionItemDecl) -> bool {
+        self.items()
+            .iter()
+            .filter(|other| other.typ().name() == item.typ().name())
+            .count()
+            == 1
+    }
+}
+
 impl Struct {
     pub fn total_size(&self) -> usize {
         self.field_sizes().iter().sum::<usize>()
@@ -229,12 +441,18 @@ impl ItemDecl {
 }
 
 impl UnionItemDecl {
-    fn new(top_decl: &Rc<TopDecl>, customize_id: usize) -> Self {
+    fn new(name: Option<&str>, top_decl: &Rc<TopDecl>, customize_id: usize) -> Self {
         Self {
+            name: name.map(ToOwned::to_owned),
             typ: Rc::clone(top_decl),
             id: customize_id,
         }
     }
+
+    /// The variant name of the item, which defaults to the name of its type.
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or_else(|| self.typ().name())
+    }
 }
 
 impl FieldDecl {
@@ -242,6 +460,53 @@ impl FieldDecl {
         Self {
             name: name.to_owned(),
             typ: Rc::clone(top_decl),
+            reserved: name.starts_with("_reserved"),
+            default: None,
+        }
+    }
+
+    // The default value could be a number, which is encoded in little-endian,
+    // or the hex of the whole bytes.
+    fn with_default(mut self, literal: Option<&str>, owner: &str) -> Self {
+        if let Some(literal) = literal {
+            let size = self.typ().total_size().unwrap_or_else(|| {
+                panic!(
+                    "the field ({}) in ({}) has a default value, but its type doesn't have fixed size",
+                    self.name(),
+                    owner
+                )
+            });
+            let value = if let Some(hex) = literal.strip_prefix("0x") {
+                if hex.len() != size * 2 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    panic!(
+                        "the default value of field ({}) in ({}) should be {} bytes in hex",
+                        self.name(),
+                        owner,
+                        size
+                    );
+                }
+                (0..size)
+                    .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap())
+                    .collect::<Vec<_>>()
+            } else {
+                let too_large = || -> ! {
+                    panic!(
+                        "the default value of field ({}) in ({}) doesn't fit in {} bytes",
+                        self.name(),
+                        owner,
+                        size
+                    )
+                };
+                let number = literal.parse::<u128>().unwrap_or_else(|_| too_large());
+                let mut value = number.to_le_bytes().to_vec();
+                if value.iter().skip(size).any(|b| *b != 0) {
+                    too_large();
+                }
+                value.resize(size, 0);
+                value
+            };
+            self.default = Some(value);
         }
+        self
     }
 }
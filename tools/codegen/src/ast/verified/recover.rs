@@ -9,6 +9,15 @@ trait RecoverFromIr {
     fn recover(&self, deps: &super::Deps) -> Option<super::TopDecl>;
 }
 
+fn check_collection_item(name: &str, item: &super::TopDecl, collection: Option<super::Collection>) {
+    if collection == Some(super::Collection::Map) && item.entry_fields().is_none() {
+        panic!(
+            "the item of the map ({}) should have only two fields: `key` and `value`",
+            name
+        );
+    }
+}
+
 impl RecoverFromIr for ir::Option_ {
     fn recover(&self, deps: &super::Deps) -> Option<super::TopDecl> {
         deps.get(self.item().typ()).map(|dep| {
@@ -18,6 +27,7 @@ impl RecoverFromIr for ir::Option_ {
                 name,
                 item,
                 imported_depth: self.imported_depth(),
+                schema_hash: Default::default(),
             }
             .into()
         })
@@ -33,17 +43,22 @@ impl RecoverFromIr for ir::Union {
         self.items()
             .iter()
             .map(|ir_item| {
-                deps.get(ir_item.typ())
-                    .map(|item| super::UnionItemDecl::new(item, ir_item.id()))
+                deps.get(ir_item.typ()).map(|item| {
+                    super::UnionItemDecl::new(
+                        ir_item.name().map(String::as_str),
+                        item,
+                        ir_item.id(),
+                    )
+                })
             })
             .collect::<Option<Vec<_>>>()
             .map(|items| {
-                let name = self.name().to_owned();
-                super::Union {
-                    name,
+                super::Union::new(
+                    self.name(),
                     items,
-                    imported_depth: self.imported_depth(),
-                }
+                    self.reserved_ids().to_owned(),
+                    self.imported_depth(),
+                )
                 .into()
             })
     }
@@ -71,6 +86,7 @@ impl RecoverFromIr for ir::Array {
                 item_count,
                 imported_depth: self.imported_depth(),
                 item_size,
+                schema_hash: Default::default(),
             }
             .into()
         })
@@ -93,7 +109,15 @@ impl RecoverFromIr for ir::Struct {
                         self.name(),
                     );
                 }
-                let field = super::FieldDecl::new(field_name, dep);
+                let field = super::FieldDecl::new(field_name, dep)
+                    .with_default(ir_field.default().map(String::as_str), self.name());
+                if field.reserved() {
+                    panic!(
+                        "the reserved field ({}) is only allowed in tables, not in struct ({})",
+                        field_name,
+                        self.name(),
+                    );
+                }
                 fields.push(field);
             } else {
                 break;
@@ -112,6 +136,8 @@ impl RecoverFromIr for ir::Struct {
                 fields,
                 imported_depth: self.imported_depth(),
                 field_sizes,
+                default_content: Default::default(),
+                schema_hash: Default::default(),
             }
             .into(),
         )
@@ -124,11 +150,18 @@ impl RecoverFromIr for ir::FixVec {
             let name = self.name().to_owned();
             let item = super::ItemDecl::new(dep);
             let item_size = dep.total_size().unwrap();
+            check_collection_item(&name, dep, self.collection);
+            if self.utf8 && !dep.is_byte() {
+                panic!("the item of the string ({}) should be byte", name);
+            }
             super::FixVec {
                 name,
                 item,
                 imported_depth: self.imported_depth(),
                 item_size,
+                collection: self.collection,
+                utf8: self.utf8,
+                schema_hash: Default::default(),
             }
             .into()
         })
@@ -140,10 +173,13 @@ impl RecoverFromIr for ir::DynVec {
         deps.get(self.item().typ()).map(|dep| {
             let name = self.name().to_owned();
             let item = super::ItemDecl::new(dep);
+            check_collection_item(&name, dep, self.collection);
             super::DynVec {
                 name,
                 item,
                 imported_depth: self.imported_depth(),
+                collection: self.collection,
+                schema_hash: Default::default(),
             }
             .into()
         })
@@ -156,8 +192,18 @@ impl RecoverFromIr for ir::Table {
             .iter()
             .map(|ir_field| {
                 let field_name = ir_field.name();
-                deps.get(ir_field.typ())
-                    .map(|dep| super::FieldDecl::new(field_name, dep))
+                deps.get(ir_field.typ()).map(|dep| {
+                    let field = super::FieldDecl::new(field_name, dep)
+                        .with_default(ir_field.default().map(String::as_str), self.name());
+                    if field.reserved() != ir_field.reserved() {
+                        panic!(
+                            "the reserved flag of field ({}) in table ({}) doesn't match its name",
+                            field_name,
+                            self.name(),
+                        );
+                    }
+                    field
+                })
             })
             .collect::<Option<Vec<_>>>()
             .map(|fields| {
@@ -166,6 +212,7 @@ impl RecoverFromIr for ir::Table {
                     name,
                     fields,
                     imported_depth: self.imported_depth(),
+                    schema_hash: Default::default(),
                 }
                 .into()
             })
@@ -198,6 +245,16 @@ impl super::Ast {
             decls_keys.retain(|&name| {
                 let decl_ir = decls_idx.get(name).unwrap();
                 if let Some(decl) = super::TopDecl::recover(decl_ir, &decls_result) {
+                    if let Some(schema_hash) = decl_ir.schema_hash() {
+                        if *schema_hash != ir::schema_hash_hex(&decl) {
+                            panic!("the schema hash of `{}` doesn't match its structure", name);
+                        }
+                    }
+                    if let Some(layout) = decl_ir.layout() {
+                        if *layout != decl.layout() {
+                            panic!("the layout of `{}` doesn't match its structure", name);
+                        }
+                    }
                     decls_result.insert(name, Rc::new(decl));
                     false
                 } else {
@@ -224,13 +281,15 @@ impl super::Ast {
             decls.push(Rc::clone(result));
         }
 
-        let syntax_version = ir.syntax_version().to_owned();
-        Self {
+        let syntax_version = super::SyntaxVersion::new(ir.syntax_version().version());
+        let ast = Self {
             syntax_version,
             namespace,
             imports,
             decls,
-        }
+        };
+        ast.check_features(|_| None);
+        ast
     }
 }
 
@@ -240,6 +299,7 @@ impl super::ImportStmt {
             name: ir.name().to_owned(),
             paths: ir.paths().to_owned(),
             path_supers: ir.path_supers(),
+            decls: Vec::new(),
         }
     }
 }
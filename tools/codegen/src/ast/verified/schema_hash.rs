@@ -0,0 +1,174 @@
+use blake2b_ref::{Blake2b, Blake2bBuilder};
+
+pub const SCHEMA_HASH_SIZE: usize = 32;
+
+/// The structural hash of a declaration.
+///
+/// Only the encoding is covered: the names of the types, the variant names of union items,
+/// the reserved union ids and the default values are not included, so the hash doesn't depend
+/// on how the types are named or imported.
+///
+/// The hashes of the declarations are cached, so the hash of each declaration is only computed
+/// once, no matter how many times it's used.
+pub trait SchemaHash {
+    fn schema_hash(&self) -> [u8; SCHEMA_HASH_SIZE] {
+        compute(self)
+    }
+
+    fn update_schema_hash(&self, hasher: &mut Blake2b);
+}
+
+fn compute<T: SchemaHash + ?Sized>(decl: &T) -> [u8; SCHEMA_HASH_SIZE] {
+    let mut hasher = Blake2bBuilder::new(SCHEMA_HASH_SIZE).build();
+    decl.update_schema_hash(&mut hasher);
+    let mut hash = [0u8; SCHEMA_HASH_SIZE];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+macro_rules! cached_schema_hash {
+    () => {
+        fn schema_hash(&self) -> [u8; SCHEMA_HASH_SIZE] {
+            *self.schema_hash.get_or_init(|| compute(self))
+        }
+    };
+}
+
+fn update_number(hasher: &mut Blake2b, number: usize) {
+    hasher.update(&(number as u64).to_le_bytes());
+}
+
+fn update_str(hasher: &mut Blake2b, s: &str) {
+    update_number(hasher, s.len());
+    hasher.update(s.as_bytes());
+}
+
+fn update_item(hasher: &mut Blake2b, item: &super::TopDecl) {
+    hasher.update(&item.schema_hash());
+}
+
+fn update_fields(hasher: &mut Blake2b, fields: &[super::FieldDecl]) {
+    update_number(hasher, fields.len());
+    for field in fields {
+        update_str(hasher, field.name());
+        update_item(hasher, field.typ());
+    }
+}
+
+fn update_collection(hasher: &mut Blake2b, collection: Option<super::Collection>) {
+    let tag = match collection {
+        None => "",
+        Some(super::Collection::Set) => "set",
+        Some(super::Collection::Map) => "map",
+    };
+    update_str(hasher, tag);
+}
+
+impl SchemaHash for super::Primitive {
+    fn update_schema_hash(&self, hasher: &mut Blake2b) {
+        update_str(hasher, "primitive");
+        update_number(hasher, self.size());
+    }
+}
+
+impl SchemaHash for super::Option_ {
+    cached_schema_hash!();
+
+    fn update_schema_hash(&self, hasher: &mut Blake2b) {
+        update_str(hasher, "option");
+        update_item(hasher, self.item().typ());
+    }
+}
+
+impl SchemaHash for super::Union {
+    cached_schema_hash!();
+
+    fn update_schema_hash(&self, hasher: &mut Blake2b) {
+        update_str(hasher, "union");
+        update_number(hasher, self.items().len());
+        for item in self.items() {
+            update_number(hasher, item.id());
+            update_item(hasher, item.typ());
+        }
+    }
+}
+
+impl SchemaHash for super::Array {
+    cached_schema_hash!();
+
+    fn update_schema_hash(&self, hasher: &mut Blake2b) {
+        update_str(hasher, "array");
+        update_item(hasher, self.item().typ());
+        update_number(hasher, self.item_size());
+        update_number(hasher, self.item_count());
+    }
+}
+
+impl SchemaHash for super::Struct {
+    cached_schema_hash!();
+
+    fn update_schema_hash(&self, hasher: &mut Blake2b) {
+        update_str(hasher, "struct");
+        update_fields(hasher, self.fields());
+        update_number(hasher, self.total_size());
+    }
+}
+
+impl SchemaHash for super::FixVec {
+    cached_schema_hash!();
+
+    fn update_schema_hash(&self, hasher: &mut Blake2b) {
+        update_str(hasher, "fixvec");
+        update_item(hasher, self.item().typ());
+        update_number(hasher, self.item_size());
+        update_collection(hasher, self.collection());
+        update_number(hasher, usize::from(self.utf8()));
+    }
+}
+
+impl SchemaHash for super::DynVec {
+    cached_schema_hash!();
+
+    fn update_schema_hash(&self, hasher: &mut Blake2b) {
+        update_str(hasher, "dynvec");
+        update_item(hasher, self.item().typ());
+        update_collection(hasher, self.collection());
+    }
+}
+
+impl SchemaHash for super::Table {
+    cached_schema_hash!();
+
+    fn update_schema_hash(&self, hasher: &mut Blake2b) {
+        update_str(hasher, "table");
+        update_fields(hasher, self.fields());
+    }
+}
+
+impl SchemaHash for super::TopDecl {
+    fn schema_hash(&self) -> [u8; SCHEMA_HASH_SIZE] {
+        match self {
+            super::TopDecl::Primitive(inner) => inner.schema_hash(),
+            super::TopDecl::Option_(inner) => inner.schema_hash(),
+            super::TopDecl::Union(inner) => inner.schema_hash(),
+            super::TopDecl::Array(inner) => inner.schema_hash(),
+            super::TopDecl::Struct(inner) => inner.schema_hash(),
+            super::TopDecl::FixVec(inner) => inner.schema_hash(),
+            super::TopDecl::DynVec(inner) => inner.schema_hash(),
+            super::TopDecl::Table(inner) => inner.schema_hash(),
+        }
+    }
+
+    fn update_schema_hash(&self, hasher: &mut Blake2b) {
+        match self {
+            super::TopDecl::Primitive(inner) => inner.update_schema_hash(hasher),
+            super::TopDecl::Option_(inner) => inner.update_schema_hash(hasher),
+            super::TopDecl::Union(inner) => inner.update_schema_hash(hasher),
+            super::TopDecl::Array(inner) => inner.update_schema_hash(hasher),
+            super::TopDecl::Struct(inner) => inner.update_schema_hash(hasher),
+            super::TopDecl::FixVec(inner) => inner.update_schema_hash(hasher),
+            super::TopDecl::DynVec(inner) => inner.update_schema_hash(hasher),
+            super::TopDecl::Table(inner) => inner.update_schema_hash(hasher),
+        }
+    }
+}
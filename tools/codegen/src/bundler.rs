@@ -0,0 +1,107 @@
+use crate::{ast, generator};
+
+/// The bundler for schema files.
+///
+/// It writes a schema and all schemas which are imported by it into a single schema, which
+/// has no imports. The declarations are deduplicated and placed in the dependency order.
+#[derive(Default)]
+pub struct Bundler {
+    roots: Vec<String>,
+}
+
+impl Bundler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only keeps the types which are reachable from the roots.
+    ///
+    /// All types are kept if there are no roots.
+    pub fn add_root(&mut self, name: &str) -> &mut Self {
+        self.roots.push(name.to_owned());
+        self
+    }
+
+    pub fn bundle(&self, ast: &ast::Ast) -> Result<String, String> {
+        let decls = if self.roots.is_empty() {
+            ast.dependency_order()
+        } else {
+            let roots = self.roots.iter().map(String::as_str).collect::<Vec<_>>();
+            ast.reachable_decls(&roots)?
+        };
+        let mut output = Vec::new();
+        generator::write_schema(&mut output, ast.syntax_version(), &[], &decls)
+            .map_err(|err| format!("failed to write the bundled schema: {}", err))?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::Bundler;
+    use crate::{ast::HasName as _, Parser};
+
+    #[test]
+    fn test_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("common")).unwrap();
+        fs::write(
+            dir.path().join("common").join("basic_types.mol"),
+            "array Uint32 [byte; 4];\narray Hash [byte; 32];\nvector Hashes <Hash>;\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("common").join("header.mol"),
+            "import basic_types;\nstruct Header { number: Uint32, parent: Hash, }\n",
+        )
+        .unwrap();
+        let path = dir.path().join("block.mol");
+        fs::write(
+            &path,
+            "import common/basic_types;\nimport common/header;\n\
+             table Block { header: Header, uncles: Hashes, }\n\
+             table Unused { number: Uint32, }\n",
+        )
+        .unwrap();
+        let ast = Parser::parse(&path);
+
+        let bundled = Bundler::new().bundle(&ast).unwrap();
+        let decls = bundled.split("syntax = 1;\n\n").nth(1).unwrap();
+        assert_eq!(
+            decls,
+            "array Uint32 [byte; 4];
+array Hash [byte; 32];
+struct Header {
+    number: Uint32,
+    parent: Hash,
+}
+vector Hashes <Hash>;
+table Block {
+    header: Header,
+    uncles: Hashes,
+}
+table Unused {
+    number: Uint32,
+}
+"
+        );
+        let bundled_path = dir.path().join("bundled.mol");
+        fs::write(&bundled_path, &bundled).unwrap();
+        let bundled_ast = Parser::parse(&bundled_path);
+        assert!(bundled_ast.imports().is_empty());
+        assert_eq!(bundled_ast.major_decls().len(), ast.decls().len());
+
+        let bundled = Bundler::new().add_root("Header").bundle(&ast).unwrap();
+        fs::write(&bundled_path, &bundled).unwrap();
+        let names = Parser::parse(&bundled_path)
+            .major_decls()
+            .iter()
+            .map(|decl| decl.name().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Uint32", "Hash", "Header"]);
+
+        assert!(Bundler::new().add_root("Missing").bundle(&ast).is_err());
+    }
+}
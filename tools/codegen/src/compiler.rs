@@ -1,17 +1,24 @@
 use std::{env, ffi, fs, io, io::Write as _, path};
 
 #[cfg(feature = "compiler-plugin")]
-use std::process;
+use std::{process, thread};
 
 use crate::{generator, parser};
 
 #[cfg(feature = "compiler-plugin")]
-use crate::ir;
+use crate::{ir, plugin};
 
 pub struct Compiler {
     target: Option<generator::Target>,
     input: Option<Input>,
     output: Option<Output>,
+    only_types: Vec<String>,
+    import_paths: Vec<path::PathBuf>,
+    generator_options: generator::GeneratorOptions,
+    #[cfg(feature = "compiler-plugin")]
+    intermediate_format_version: usize,
+    #[cfg(feature = "compiler-plugin")]
+    intermediate_layouts: bool,
 }
 
 pub(crate) enum Input {
@@ -25,6 +32,8 @@ pub(crate) enum Output {
     Stdout,
     #[cfg(feature = "compiler-plugin")]
     PluginProcess(process::Child),
+    #[cfg(feature = "compiler-plugin")]
+    Plugin(process::Child, plugin::Request),
 }
 
 impl Default for Compiler {
@@ -39,6 +48,13 @@ impl Compiler {
             target: None,
             input: None,
             output: Some(Output::Stdout),
+            only_types: Vec::new(),
+            import_paths: Vec::new(),
+            generator_options: generator::GeneratorOptions::default(),
+            #[cfg(feature = "compiler-plugin")]
+            intermediate_format_version: 1,
+            #[cfg(feature = "compiler-plugin")]
+            intermediate_layouts: false,
         }
     }
 
@@ -47,12 +63,44 @@ impl Compiler {
         self
     }
 
+    /// Generates the code by a generator which is implemented outside this crate.
+    pub fn generate_with(&mut self, generator: Box<dyn generator::Generator>) -> &mut Self {
+        self.target.replace(generator::Target::Custom(generator));
+        self
+    }
+
+    /// The options of the built-in generator of [`generate_code`](Self::generate_code).
+    pub fn generator_options(&mut self, options: generator::GeneratorOptions) -> &mut Self {
+        self.generator_options = options;
+        self
+    }
+
     #[cfg(feature = "compiler-plugin")]
     pub fn generate_intermediate(&mut self, format: ir::Format) -> &mut Self {
         self.target.replace(generator::Target::Intermediate(format));
         self
     }
 
+    /// The version of the [intermediate format](ir::Ir::FORMAT_VERSION) which is generated.
+    ///
+    /// It's 1 by default, which could be read by all plugins. Set it to the version in the
+    /// [capabilities](plugin::Capabilities) of the plugin to include the schema hashes.
+    #[cfg(feature = "compiler-plugin")]
+    pub fn intermediate_format_version(&mut self, version: usize) -> &mut Self {
+        self.intermediate_format_version = version;
+        self
+    }
+
+    /// Includes the layout of each declaration and the dependency order in the intermediate
+    /// data, so the plugins don't have to compute them.
+    ///
+    /// The intermediate data is generated in the latest version of the format.
+    #[cfg(feature = "compiler-plugin")]
+    pub fn intermediate_layouts(&mut self, enabled: bool) -> &mut Self {
+        self.intermediate_layouts = enabled;
+        self
+    }
+
     pub fn input_schema_file<P: AsRef<path::Path>>(&mut self, path: P) -> &mut Self {
         self.input
             .replace(Input::SchemaFile(path.as_ref().to_path_buf()));
@@ -65,6 +113,22 @@ impl Compiler {
         self
     }
 
+    /// Only generates the code for the types and the types which they depend on.
+    ///
+    /// The imported types which are required are generated with them, so the generated code
+    /// doesn't depend on the code of the imported schema files.
+    pub fn only_types(&mut self, types: &[&str]) -> &mut Self {
+        self.only_types = types.iter().map(|typ| (*typ).to_owned()).collect();
+        self
+    }
+
+    /// Searches the imported schema files in the directories, in order, if they are not found
+    /// relative to the importing files.
+    pub fn import_paths<P: AsRef<path::Path>>(&mut self, paths: &[P]) -> &mut Self {
+        self.import_paths = paths.iter().map(|p| p.as_ref().to_path_buf()).collect();
+        self
+    }
+
     pub fn output_dir_set_default(&mut self) -> &mut Self {
         let out_dir = path::PathBuf::from(&env::var("OUT_DIR").unwrap_or_else(|_| ".".to_string()));
         self.output_dir(out_dir)
@@ -82,16 +146,38 @@ impl Compiler {
         self
     }
 
+    /// Sends the request to a plugin which supports the [plugin protocol](crate::plugin), the
+    /// stdin and the stdout of the plugin should be piped.
+    ///
+    /// The files in the response are written into the output directory of the request, or
+    /// written to the stdout if there is no output directory and only one file.
+    #[cfg(feature = "compiler-plugin")]
+    pub fn output_plugin(&mut self, child: process::Child, request: plugin::Request) -> &mut Self {
+        self.target
+            .replace(generator::Target::Intermediate(request.format));
+        self.output.replace(Output::Plugin(child, request));
+        self
+    }
+
     pub fn run(&mut self) -> Result<(), String> {
         let Self {
-            target,
+            ref target,
             ref input,
             #[cfg(not(feature = "compiler-plugin"))]
             ref output,
             #[cfg(feature = "compiler-plugin")]
             ref mut output,
+            ref only_types,
+            ref import_paths,
+            ref generator_options,
+            #[cfg(feature = "compiler-plugin")]
+            intermediate_format_version,
+            #[cfg(feature = "compiler-plugin")]
+            intermediate_layouts,
         } = self;
-        let target = target.ok_or("target is not set: generate code or intermediate data")?;
+        let target = target
+            .as_ref()
+            .ok_or("target is not set: generate code or intermediate data")?;
         let input = input
             .as_ref()
             .ok_or("input is not set: schema file or intermediate data")?;
@@ -113,57 +199,191 @@ impl Compiler {
                     .file_name()
                     .and_then(ffi::OsStr::to_str)
                     .clone_into(&mut file_name);
-                parser::Parser::parse(file_path)
+                parser::Parser::parse_with_import_paths(file_path, import_paths)
             }
             #[cfg(feature = "compiler-plugin")]
             Input::Intermediate(format, ref data) => format.recover(data)?,
         };
-        let generator = generator::Generator::new(ast);
+        let ast = if only_types.is_empty() {
+            ast
+        } else {
+            let roots = only_types.iter().map(String::as_str).collect::<Vec<_>>();
+            match ast.into_reachable(&roots) {
+                Ok(ast) => ast,
+                Err(err) => {
+                    // the plugin is waiting for the input, which will never come
+                    #[cfg(feature = "compiler-plugin")]
+                    if let Output::PluginProcess(ref mut process)
+                    | Output::Plugin(ref mut process, _) = output
+                    {
+                        let _ = process.kill();
+                        let _ = process.wait();
+                    }
+                    return Err(err);
+                }
+            }
+        };
+        let emitter = generator::Emitter::new(ast).generator_options(generator_options.clone());
+        #[cfg(feature = "compiler-plugin")]
+        let emitter = emitter
+            .intermediate_format_version(*intermediate_format_version)
+            .intermediate_layouts(*intermediate_layouts);
 
         let mut output_data = Vec::<u8>::new();
-        generator
-            .generate(target, &mut output_data)
-            .map_err(|err| format!("failed to write data by generator: {}", err))?;
+        if !matches!(output, Output::Directory(_)) {
+            emitter
+                .generate(target, &mut output_data)
+                .map_err(|err| format!("failed to write data by generator: {}", err))?;
+        }
 
         match output {
             Output::Directory(ref out_dir) => {
-                let file_name = file_name.unwrap();
-                let mut out_file = out_dir.to_owned();
-                out_file.push(file_name);
-                out_file.set_extension(target.extension());
-                let mut file_out = fs::OpenOptions::new()
-                    .create(true)
-                    .write(true)
-                    .truncate(true)
-                    .open(&out_file)
-                    .unwrap();
-                file_out.write_all(&output_data).unwrap();
-                file_out.flush().unwrap();
+                let name = file_name
+                    .map(path::Path::new)
+                    .and_then(path::Path::file_stem)
+                    .and_then(ffi::OsStr::to_str)
+                    .ok_or("the output file name requires an input schema file")?;
+                let files = emitter
+                    .generate_files(target, name)
+                    .map_err(|err| format!("failed to write data by generator: {}", err))?;
+                for (file, content) in files {
+                    let out_file = out_dir.join(file);
+                    if let Some(parent) = out_file.parent() {
+                        fs::create_dir_all(parent).map_err(|err| {
+                            format!("failed to create {}: {}", parent.display(), err)
+                        })?;
+                    }
+                    fs::write(&out_file, &content).map_err(|err| {
+                        format!("failed to write {}: {}", out_file.display(), err)
+                    })?;
+                }
             }
             Output::Stdout => {
                 let stdout = io::stdout();
                 let mut stdout_handle = stdout.lock();
-                stdout_handle.write_all(&output_data).unwrap();
-                stdout_handle.flush().unwrap();
+                stdout_handle
+                    .write_all(&output_data)
+                    .and_then(|_| stdout_handle.flush())
+                    .map_err(|err| format!("failed to write to the stdout: {}", err))?;
             }
             #[cfg(feature = "compiler-plugin")]
             Output::PluginProcess(ref mut process) => {
                 {
-                    let child_stdin = process.stdin.as_mut().unwrap();
-                    child_stdin.write_all(&output_data).unwrap();
-                    child_stdin.flush().unwrap();
+                    // the stdin is closed when it's dropped, then the plugin knows the end of the data
+                    let mut child_stdin = process
+                        .stdin
+                        .take()
+                        .ok_or("the stdin of the plugin is not piped")?;
+                    child_stdin
+                        .write_all(&output_data)
+                        .and_then(|_| child_stdin.flush())
+                        .map_err(|err| format!("failed to write data to the plugin: {}", err))?;
                 }
-                if let Ok(status) = process.wait() {
-                    if !status.success() {
-                        process::exit(1)
-                    }
-                } else {
-                    eprintln!("Error: failed to execute the plugin");
-                    process::exit(1)
+                let status = process
+                    .wait()
+                    .map_err(|err| format!("failed to execute the plugin: {}", err))?;
+                if !status.success() {
+                    return Err(format!("the plugin failed: {}", status));
+                }
+            }
+            #[cfg(feature = "compiler-plugin")]
+            Output::Plugin(ref mut process, ref request) => {
+                let response = run_plugin(process, request, &output_data)?;
+                for diagnostic in response.diagnostics() {
+                    eprintln!("{}", diagnostic);
                 }
+                if response.has_errors() {
+                    return Err("the plugin reported errors".to_owned());
+                }
+                write_plugin_files(request, response.files())?;
             }
         }
 
         Ok(())
     }
 }
+
+#[cfg(feature = "compiler-plugin")]
+fn run_plugin(
+    process: &mut process::Child,
+    request: &plugin::Request,
+    data: &[u8],
+) -> Result<plugin::Response, String> {
+    let mut stdin = process
+        .stdin
+        .take()
+        .ok_or("the stdin of the plugin is not piped")?;
+    let mut stdout = process
+        .stdout
+        .take()
+        .ok_or("the stdout of the plugin is not piped")?;
+    // the request is written by another thread, so the plugins could write the response before
+    // they read all of the input, without blocking each other on the full pipes
+    let (written, read) = thread::scope(|scope| {
+        let writer = scope.spawn(move || {
+            // the stdin is closed when it's dropped, then the plugin knows the end of the data
+            request.write(&mut stdin, data)
+        });
+        let mut response = Vec::new();
+        let read = io::Read::read_to_end(&mut stdout, &mut response).map(|_| response);
+        let written = writer
+            .join()
+            .expect("the thread which writes the request panicked");
+        (written, read)
+    });
+    let response =
+        read.map_err(|err| format!("failed to read the response of the plugin: {}", err))?;
+    let status = process
+        .wait()
+        .map_err(|err| format!("failed to execute the plugin: {}", err))?;
+    if !status.success() {
+        return Err(format!("the plugin failed: {}", status));
+    }
+    written.map_err(|err| format!("failed to write the request to the plugin: {}", err))?;
+    plugin::Response::from_json(&response)
+}
+
+#[cfg(feature = "compiler-plugin")]
+fn write_plugin_files(
+    request: &plugin::Request,
+    files: &[plugin::OutputFile],
+) -> Result<(), String> {
+    let out_dir = if let Some(out_dir) = request.output_dir() {
+        path::Path::new(out_dir)
+    } else {
+        return match files {
+            [file] => {
+                let stdout = io::stdout();
+                let mut stdout_handle = stdout.lock();
+                stdout_handle
+                    .write_all(file.content().as_bytes())
+                    .and_then(|_| stdout_handle.flush())
+                    .map_err(|err| format!("failed to write to the stdout: {}", err))
+            }
+            _ => Err(format!(
+                "the plugin generated {} files, an output directory is required",
+                files.len()
+            )),
+        };
+    };
+    for file in files {
+        let name = path::Path::new(file.name());
+        let is_relative = name
+            .components()
+            .all(|component| matches!(component, path::Component::Normal(_)));
+        if !is_relative || file.name().is_empty() {
+            return Err(format!(
+                "the plugin generated a file outside the output directory: {}",
+                file.name()
+            ));
+        }
+        let out_file = out_dir.join(name);
+        if let Some(parent) = out_file.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| format!("failed to create {}: {}", parent.display(), err))?;
+        }
+        fs::write(&out_file, file.content())
+            .map_err(|err| format!("failed to write {}: {}", out_file.display(), err))?;
+    }
+    Ok(())
+}
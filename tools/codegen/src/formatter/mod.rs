@@ -0,0 +1,236 @@
+use crate::parser::Rule;
+
+mod tree;
+
+use tree::{Comment, Element, SyntaxTree};
+
+const INDENT: &str = "    ";
+
+/// The formatter for schema files.
+///
+/// The statements are placed as below, and the comments are kept:
+/// - The `syntax` statement is the first statement.
+/// - The `import` statements are sorted by their paths.
+/// - The fields and the items in blocks are indented by 4 spaces, one per line, and their
+///   colons are aligned, so are the types.
+/// - The empty blocks are written as `{}`.
+/// - The blank lines between declarations, fields and items are kept, but collapsed to one.
+pub struct Formatter;
+
+#[derive(Default)]
+struct Printer {
+    output: String,
+}
+
+impl Formatter {
+    pub fn format(source: &str) -> Result<String, String> {
+        let tree = SyntaxTree::parse(source)?;
+        let mut printer = Printer::default();
+        printer.tree(tree);
+        Ok(printer.output)
+    }
+
+    /// Returns `true` if the source is already formatted.
+    pub fn check(source: &str) -> Result<bool, String> {
+        Self::format(source).map(|formatted| formatted == source)
+    }
+}
+
+/// The name before the colon of a field or an item in a block, the colons are aligned by it.
+fn label(element: &Element) -> Option<&str> {
+    match element.rule {
+        Rule::field_decl | Rule::named_union_item_decl | Rule::custom_union_item_decl => {
+            element.text.split_once(':').map(|(name, _)| name)
+        }
+        _ => None,
+    }
+}
+
+impl Printer {
+    fn line(&mut self, indent: usize, text: &str, trailing: &[String]) {
+        for _ in 0..indent {
+            self.output.push_str(INDENT);
+        }
+        self.output.push_str(text);
+        for comment in trailing {
+            self.output.push(' ');
+            self.output.push_str(comment);
+        }
+        self.output.push('\n');
+    }
+
+    fn blank_line(&mut self) {
+        if !self.output.is_empty() && !self.output.ends_with("\n\n") {
+            self.output.push('\n');
+        }
+    }
+
+    fn comments(&mut self, indent: usize, comments: &[Comment], first: &mut bool) {
+        for comment in comments {
+            if comment.blank_line_before && !*first {
+                self.blank_line();
+            }
+            self.line(indent, &comment.text, &[]);
+            *first = false;
+        }
+    }
+
+    /// Prints an element, the name of a field or an item is padded to `width` to align the
+    /// colons.
+    fn element(&mut self, indent: usize, element: &Element, width: usize, first: &mut bool) {
+        self.comments(indent, &element.leading, first);
+        if element.blank_line_before && !*first {
+            self.blank_line();
+        }
+        *first = false;
+        if let Some(ref block) = element.block {
+            if block.is_empty() {
+                let text = format!("{}}}", element.text);
+                self.line(indent, &text, &element.trailing);
+                return;
+            }
+            self.line(indent, &element.text, &block.open_trailing);
+            let width = block.items.iter().filter_map(label).map(str::len).max();
+            let mut first = true;
+            for item in &block.items {
+                self.element(indent + 1, item, width.unwrap_or_default(), &mut first);
+            }
+            self.comments(indent + 1, &block.dangling, &mut first);
+            self.line(indent, "}", &element.trailing);
+        } else if let Some(name) = label(element) {
+            let text = format!("{:width$}{}", name, &element.text[name.len()..]);
+            self.line(indent, &text, &element.trailing);
+        } else {
+            self.line(indent, &element.text, &element.trailing);
+        }
+    }
+
+    fn tree(&mut self, tree: SyntaxTree) {
+        let SyntaxTree {
+            mut elements,
+            dangling,
+        } = tree;
+        // The comments at the top of the file, which are separated from the first statement
+        // by a blank line, are the header of the file.
+        let header = match elements.first_mut() {
+            Some(first) if first.blank_line_before => std::mem::take(&mut first.leading),
+            _ => Vec::new(),
+        };
+        let mut first = true;
+        self.comments(0, &header, &mut first);
+
+        let (syntax, others): (Vec<_>, Vec<_>) = elements
+            .into_iter()
+            .partition(|element| element.rule == Rule::syntax_version_stmt);
+        let (mut imports, decls): (Vec<_>, Vec<_>) = others
+            .into_iter()
+            .partition(|element| element.rule == Rule::import_stmt);
+        imports.sort_by(|a, b| a.text.cmp(&b.text));
+        for element in imports.iter_mut() {
+            element.blank_line_before = false;
+        }
+        for section in &[syntax, imports, decls] {
+            if section.is_empty() {
+                continue;
+            }
+            self.blank_line();
+            let mut first = true;
+            for element in section {
+                self.element(0, element, 0, &mut first);
+            }
+        }
+        let mut first = self.output.is_empty();
+        self.comments(0, &dangling, &mut first);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Formatter;
+
+    #[test]
+    fn test_format() {
+        let source = "
+// The header.
+
+syntax=2;
+import   b;
+// Comment of a.
+import a ; // trailing of a
+array   Uint32[byte;4];
+
+
+// Comment of the struct.
+struct Point{ // open
+    x :Uint32,y:Uint32, // trailing of y
+    // dangling
+}   // close
+union Message {
+    Uint32 : 1,
+    reserved 2,3;
+    Msg :Point=4,
+}
+table Empty {}
+table Config {
+    _reserved: vector < byte >,
+
+    /* block
+       comment */
+    version: [ Uint32 ; 2 ] = 0x0102,
+}
+vector Bytes<byte>;map Map <Uint32,Bytes>;
+// The end.
+";
+        let expected = "// The header.
+
+syntax = 2;
+
+// Comment of a.
+import a; // trailing of a
+import b;
+
+array Uint32 [byte; 4];
+
+// Comment of the struct.
+struct Point { // open
+    x: Uint32,
+    y: Uint32, // trailing of y
+    // dangling
+} // close
+union Message {
+    Uint32: 1,
+    reserved 2, 3;
+    Msg   : Point = 4,
+}
+table Empty {}
+table Config {
+    _reserved: vector<byte>,
+
+    /* block
+       comment */
+    version  : [Uint32; 2] = 0x0102,
+}
+vector Bytes <byte>;
+map Map <Uint32, Bytes>;
+// The end.
+";
+        let formatted = Formatter::format(source).unwrap();
+        assert_eq!(formatted, expected);
+        assert!(Formatter::check(&formatted).unwrap());
+        assert!(!Formatter::check(source).unwrap());
+    }
+
+    #[test]
+    fn test_format_inner_comments() {
+        let source = "array /* a */ Uint32 [byte; 4];\nstruct /* b */ Point /* c */ {\n    x: /* d */ Uint32,\n}\n";
+        let expected = "/* a */\narray Uint32 [byte; 4];\n/* b */\n/* c */\nstruct Point {\n    /* d */\n    x: Uint32,\n}\n";
+        let formatted = Formatter::format(source).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(Formatter::format(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_bad_schema() {
+        assert!(Formatter::format("struct Point {").is_err());
+    }
+}
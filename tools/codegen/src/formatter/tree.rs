@@ -0,0 +1,281 @@
+use pest::{iterators::Pair, Parser as _};
+
+use crate::parser::{InnerParser, Rule};
+
+/// A comment which is on its own lines.
+pub(super) struct Comment {
+    pub(super) text: String,
+    pub(super) blank_line_before: bool,
+}
+
+/// A statement, or a field or an item in a block.
+pub(super) struct Element {
+    pub(super) rule: Rule,
+    pub(super) leading: Vec<Comment>,
+    pub(super) blank_line_before: bool,
+    pub(super) text: String,
+    pub(super) trailing: Vec<String>,
+    pub(super) block: Option<Block>,
+}
+
+pub(super) struct Block {
+    pub(super) open_trailing: Vec<String>,
+    pub(super) items: Vec<Element>,
+    pub(super) dangling: Vec<Comment>,
+}
+
+/// The syntax tree of a schema file, which keeps all comments.
+///
+/// Each comment is attached to the nearest element: a comment on the same line as the end of
+/// an element is a trailing comment of it, other comments are leading comments of the next
+/// element, or dangling comments at the end of a block or the file.
+pub(super) struct SyntaxTree {
+    pub(super) elements: Vec<Element>,
+    pub(super) dangling: Vec<Comment>,
+}
+
+enum Trivia {
+    Comment(usize, usize),
+    Char(usize, char),
+}
+
+struct Builder<'i> {
+    source: &'i str,
+}
+
+type Sequence = (Vec<String>, Vec<Element>, Vec<Comment>);
+
+impl SyntaxTree {
+    pub(super) fn parse(source: &str) -> Result<Self, String> {
+        let grammar = InnerParser::parse(Rule::grammar, source)
+            .map_err(|err| err.to_string())?
+            .next()
+            .unwrap();
+        let children = grammar
+            .into_inner()
+            .filter(|pair| pair.as_rule() != Rule::EOI)
+            .collect();
+        let builder = Builder { source };
+        let (_, elements, dangling) =
+            builder.sequence(0, source.len(), children, false, Builder::statement);
+        Ok(Self { elements, dangling })
+    }
+}
+
+impl Block {
+    pub(super) fn is_empty(&self) -> bool {
+        self.open_trailing.is_empty() && self.items.is_empty() && self.dangling.is_empty()
+    }
+}
+
+impl Element {
+    fn new(rule: Rule, leading: Vec<Comment>, text: String) -> Self {
+        Self {
+            rule,
+            leading,
+            blank_line_before: false,
+            text,
+            trailing: Vec::new(),
+            block: None,
+        }
+    }
+}
+
+impl<'i> Builder<'i> {
+    fn trivia(&self, start: usize, end: usize) -> Vec<Trivia> {
+        let mut ret = Vec::new();
+        let mut pos = start;
+        while pos < end {
+            let rest = &self.source[pos..end];
+            if rest.starts_with("//") || rest.starts_with('#') || rest.starts_with("/*") {
+                if let Some(comment) = InnerParser::parse(Rule::standalone_comment, rest)
+                    .ok()
+                    .and_then(|mut pairs| pairs.next())
+                {
+                    let len = comment.as_str().len();
+                    ret.push(Trivia::Comment(pos, pos + len));
+                    pos += len;
+                    continue;
+                }
+            }
+            let ch = rest.chars().next().unwrap();
+            ret.push(Trivia::Char(pos, ch));
+            pos += ch.len_utf8();
+        }
+        ret
+    }
+
+    fn comments(&self, start: usize, end: usize) -> Vec<(usize, usize)> {
+        self.trivia(start, end)
+            .into_iter()
+            .filter_map(|trivia| match trivia {
+                Trivia::Comment(start, end) => Some((start, end)),
+                Trivia::Char(..) => None,
+            })
+            .collect()
+    }
+
+    fn newlines(&self, start: usize, end: usize) -> usize {
+        self.source[start..end].matches('\n').count()
+    }
+
+    fn text(&self, start: usize, end: usize) -> String {
+        self.source[start..end].trim_end().to_owned()
+    }
+
+    fn inner_comments(&self, start: usize, end: usize) -> Vec<Comment> {
+        self.comments(start, end)
+            .into_iter()
+            .map(|(start, end)| Comment {
+                text: self.text(start, end),
+                blank_line_before: false,
+            })
+            .collect()
+    }
+
+    /// Builds the elements between `start` and `end`.
+    ///
+    /// If `anchored`, the region starts after an open brace, and a comment on the same line as
+    /// the brace is a trailing comment of the brace.
+    fn sequence<F>(
+        &self,
+        start: usize,
+        end: usize,
+        children: Vec<Pair<'i, Rule>>,
+        anchored: bool,
+        build: F,
+    ) -> Sequence
+    where
+        F: Fn(&Self, Pair<'i, Rule>) -> Element,
+    {
+        let mut open_trailing = Vec::new();
+        let mut elements: Vec<Element> = Vec::new();
+        let mut pending = Vec::new();
+        let mut cursor = start;
+        let mut collect = |from: usize,
+                           to: usize,
+                           elements: &mut Vec<Element>,
+                           pending: &mut Vec<Comment>|
+         -> usize {
+            let mut cursor = from;
+            for (start, end) in self.comments(from, to) {
+                let text = self.text(start, end);
+                if pending.is_empty() && self.newlines(cursor, start) == 0 {
+                    if let Some(element) = elements.last_mut() {
+                        element.trailing.push(text);
+                        cursor = end;
+                        continue;
+                    } else if anchored {
+                        open_trailing.push(text);
+                        cursor = end;
+                        continue;
+                    }
+                }
+                pending.push(Comment {
+                    text,
+                    blank_line_before: self.newlines(cursor, start) > 1,
+                });
+                cursor = end;
+            }
+            cursor
+        };
+        for child in children {
+            let span = child.as_span();
+            cursor = collect(cursor, span.start(), &mut elements, &mut pending);
+            let mut element = build(self, child);
+            element.blank_line_before = self.newlines(cursor, span.start()) > 1;
+            let mut leading = std::mem::take(&mut pending);
+            leading.append(&mut element.leading);
+            element.leading = leading;
+            elements.push(element);
+            cursor = span.end();
+        }
+        collect(cursor, end, &mut elements, &mut pending);
+        (open_trailing, elements, pending)
+    }
+
+    fn statement(&self, pair: Pair<'i, Rule>) -> Element {
+        match pair.as_rule() {
+            Rule::struct_decl | Rule::table_decl | Rule::union_decl => self.block(pair),
+            _ => self.leaf(pair),
+        }
+    }
+
+    fn leaf(&self, pair: Pair<'i, Rule>) -> Element {
+        let span = pair.as_span();
+        let leading = self.inner_comments(span.start(), span.end());
+        Element::new(pair.as_rule(), leading, render(pair))
+    }
+
+    fn block(&self, pair: Pair<'i, Rule>) -> Element {
+        let span = pair.as_span();
+        let rule = pair.as_rule();
+        let keyword = match rule {
+            Rule::struct_decl => "struct",
+            Rule::table_decl => "table",
+            Rule::union_decl => "union",
+            _ => unreachable!(),
+        };
+        let mut inner = pair.into_inner();
+        let name = inner.next().unwrap();
+        let children = inner.collect::<Vec<_>>();
+        let brace = self
+            .trivia(name.as_span().end(), span.end())
+            .into_iter()
+            .find_map(|trivia| match trivia {
+                Trivia::Char(pos, '{') => Some(pos),
+                _ => None,
+            })
+            .unwrap();
+        let leading = self.inner_comments(span.start(), brace);
+        let text = format!("{} {} {{", keyword, name.as_str());
+        let (open_trailing, items, dangling) =
+            self.sequence(brace + 1, span.end() - 1, children, true, Builder::leaf);
+        let mut element = Element::new(rule, leading, text);
+        element.block = Some(Block {
+            open_trailing,
+            items,
+            dangling,
+        });
+        element
+    }
+}
+
+fn render(pair: Pair<Rule>) -> String {
+    let rule = pair.as_rule();
+    let parts = pair.into_inner().map(render_type).collect::<Vec<_>>();
+    match rule {
+        Rule::syntax_version_stmt => format!("syntax = {};", parts[0]),
+        Rule::import_stmt => format!("import {};", parts[0]),
+        Rule::option_decl => format!("option {} ({});", parts[0], parts[1]),
+        Rule::array_decl => format!("array {} [{}; {}];", parts[0], parts[1], parts[2]),
+        Rule::vector_decl => format!("vector {} <{}>;", parts[0], parts[1]),
+        Rule::string_decl => format!("string {};", parts[0]),
+        Rule::set_decl => format!("set {} <{}>;", parts[0], parts[1]),
+        Rule::map_decl => format!("map {} <{}, {}>;", parts[0], parts[1], parts[2]),
+        Rule::field_decl | Rule::named_union_item_decl => {
+            if let Some(value) = parts.get(2) {
+                format!("{}: {} = {},", parts[0], parts[1], value)
+            } else {
+                format!("{}: {},", parts[0], parts[1])
+            }
+        }
+        Rule::item_decl => format!("{},", parts[0]),
+        Rule::custom_union_item_decl => format!("{}: {},", parts[0], parts[1]),
+        Rule::union_reserved_decl => format!("reserved {};", parts.join(", ")),
+        _ => unreachable!(),
+    }
+}
+
+fn render_type(pair: Pair<Rule>) -> String {
+    match pair.as_rule() {
+        Rule::vector_type => format!("vector<{}>", render_type(pair.into_inner().next().unwrap())),
+        Rule::option_type => format!("option<{}>", render_type(pair.into_inner().next().unwrap())),
+        Rule::array_type => {
+            let mut inner = pair.into_inner();
+            let item = render_type(inner.next().unwrap());
+            format!("[{}; {}]", item, inner.next().unwrap().as_str())
+        }
+        _ => pair.as_str().to_owned(),
+    }
+}
@@ -1,9 +1,9 @@
 use std::io;
 
 use super::utilities::IdentPrefix;
-use crate::ast::{self as ast, DefaultContent, HasName};
+use crate::ast::{self as ast, DefaultContent, SchemaHash};
 
-pub(super) trait GenBuilder: IdentPrefix + DefaultContent {
+pub(super) trait GenBuilder: IdentPrefix + DefaultContent + SchemaHash {
     fn gen_builder_interfaces_internal<W: io::Write>(&self, writer: &mut W) -> io::Result<()>;
 
     fn gen_builder_function_build<W: io::Write>(&self, _writer: &mut W) -> io::Result<()> {
@@ -36,47 +36,69 @@ pub(super) trait GenBuilder: IdentPrefix + DefaultContent {
     }
 
     fn gen_default<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
-        let default_content = self.default_content();
-        let constant_name = format!(
-            "{} const uint8_t {}[{}]",
-            self.api_decorator(),
-            self.default_constant(),
-            default_content.len()
-        );
-        write!(writer, "{:64} =  {{", constant_name)?;
-        if default_content.len() > 4 {
-            for (index, byte) in default_content.into_iter().enumerate() {
-                if index % 12 == 0 {
-                    writeln!(writer)?;
-                    write!(writer, "{:4}", "")?;
-                } else {
-                    write!(writer, " ")?;
-                }
-                if byte == 0 {
-                    write!(writer, "____,")?;
-                } else {
-                    write!(writer, "0x{:02x},", byte)?;
-                }
+        write_constant(
+            writer,
+            self,
+            &self.default_constant(),
+            &self.default_content(),
+        )
+    }
+
+    fn gen_schema_hash<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_constant(
+            writer,
+            self,
+            &self.schema_hash_constant(),
+            &self.schema_hash(),
+        )
+    }
+}
+
+fn write_constant<W: io::Write, T: IdentPrefix + ?Sized>(
+    writer: &mut W,
+    decl: &T,
+    name: &str,
+    content: &[u8],
+) -> io::Result<()> {
+    let constant_name = format!(
+        "{} const uint8_t {}[{}]",
+        decl.api_decorator(),
+        name,
+        content.len()
+    );
+    write!(writer, "{:64} =  {{", constant_name)?;
+    if content.len() > 4 {
+        for (index, byte) in content.iter().enumerate() {
+            if index % 12 == 0 {
+                writeln!(writer)?;
+                write!(writer, "{:4}", "")?;
+            } else {
+                write!(writer, " ")?;
             }
-            writeln!(writer)?;
-        } else {
-            let mut not_first = false;
-            for byte in default_content.into_iter() {
-                if not_first {
-                    write!(writer, ", ")?;
-                } else {
-                    not_first = true;
-                }
-                if byte == 0 {
-                    write!(writer, "____")?;
-                } else {
-                    write!(writer, "0x{:02x}", byte)?;
-                }
+            if *byte == 0 {
+                write!(writer, "____,")?;
+            } else {
+                write!(writer, "0x{:02x},", byte)?;
+            }
+        }
+        writeln!(writer)?;
+    } else {
+        let mut not_first = false;
+        for byte in content {
+            if not_first {
+                write!(writer, ", ")?;
+            } else {
+                not_first = true;
+            }
+            if *byte == 0 {
+                write!(writer, "____")?;
+            } else {
+                write!(writer, "0x{:02x}", byte)?;
             }
         }
-        writeln!(writer, "}};")?;
-        Ok(())
     }
+    writeln!(writer, "}};")?;
+    Ok(())
 }
 
 fn calculate_capacity(used: usize) -> usize {
@@ -122,12 +144,12 @@ impl GenBuilder for ast::Union {
         for item in self.items() {
             let (macro_sig_tail, macro_content) = if item.typ().is_byte() {
                 (
-                    format!("_set_{}(b, p)", item.typ().name()),
+                    format!("_set_{}(b, p)", item.name()),
                     format!("mol_union_builder_set_byte(b, {}, p)", item.id()),
                 )
             } else {
                 (
-                    format!("_set_{}(b, p, l)", item.typ().name()),
+                    format!("_set_{}(b, p, l)", item.name()),
                     format!("mol_union_builder_set(b, {}, p, l)", item.id()),
                 )
             };
@@ -169,10 +191,18 @@ impl GenBuilder for ast::Array {
 impl GenBuilder for ast::Struct {
     fn gen_builder_interfaces_internal<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
         {
-            let macro_content = format!(
-                "mol_builder_initialize_fixed_size(b, {})",
-                self.total_size()
-            );
+            let macro_content = if self.default_content().iter().all(|b| *b == 0) {
+                format!(
+                    "mol_builder_initialize_fixed_size(b, {})",
+                    self.total_size()
+                )
+            } else {
+                format!(
+                    "mol_builder_initialize_fixed_size(b, {0}), mol_builder_set_by_offset(b, 0, {1}, {0})",
+                    self.total_size(),
+                    self.default_constant()
+                )
+            };
             self.define_builder_macro(writer, "_init(b)", &macro_content)?;
         }
         let mut field_offset = 0;
@@ -249,6 +279,9 @@ impl GenBuilder for ast::Table {
             self.define_builder_macro(writer, "_init(b)", &macro_content)?;
         }
         for (i, f) in self.fields().iter().enumerate() {
+            if f.reserved() {
+                continue;
+            }
             let (macro_sig_tail, macro_content) = if f.typ().is_byte() {
                 (
                     format!("_set_{}(b, p)", f.name()),
@@ -304,6 +337,8 @@ impl GenBuilder for ast::Table {
         if !self.fields().is_empty() {
             w!(o, "    uint8_t *src = builder.data_ptr;                   ");
         }
+        // the offset of the field in the default value of the table
+        let mut default_offset = molecule::NUMBER_SIZE * (self.fields().len() + 1);
         for (i, f) in self.fields().iter().enumerate() {
             let li = i * 2 + 1;
             let oi = i * 2;
@@ -311,7 +346,23 @@ impl GenBuilder for ast::Table {
             w!(o, "    len = builder.number_ptr[{}];              ", li);
             w!(o, "    if (len == 0) {{                               ");
             w!(o, "        len = {};                             ", len);
-            if f.typ().is_byte() {
+            if let Some(value) = f.default() {
+                if f.typ().is_byte() {
+                    w!(
+                        o,
+                        "        *dst = 0x{:02x};                       ",
+                        value[0]
+                    );
+                } else {
+                    let name = self.default_constant();
+                    w!(
+                        o,
+                        "        memcpy(dst, &{}[{}], len);      ",
+                        name,
+                        default_offset
+                    );
+                }
+            } else if f.typ().is_byte() {
                 w!(o, "        *dst = 0;                              ");
             } else {
                 let name = f.typ().default_constant();
@@ -322,6 +373,7 @@ impl GenBuilder for ast::Table {
             w!(o, "        memcpy(dst, src+of, len);                  ");
             w!(o, "    }}                                             ");
             w!(o, "    dst += len;                                    ");
+            default_offset += len;
         }
         w!(o, "    mol_builder_discard(builder);                      ");
         w!(o, "    return res;                                        ");
@@ -58,6 +58,25 @@ impl Generator {
         Ok(())
     }
 
+    /// Generates a header which includes the headers of all modules.
+    pub(super) fn umbrella<W: io::Write>(
+        writer: &mut W,
+        name: &str,
+        modules: &[&str],
+    ) -> io::Result<()> {
+        let n = name.to_snake().to_uppercase();
+        writeln!(writer, "// Generated by Molecule {}", VERSION)?;
+        writeln!(writer)?;
+        writeln!(writer, "#ifndef {}_H", n)?;
+        writeln!(writer, "#define {}_H", n)?;
+        writeln!(writer)?;
+        for module in modules {
+            writeln!(writer, r#"#include "{}.h""#, module)?;
+        }
+        writeln!(writer)?;
+        writeln!(writer, "#endif /* {}_H */", n)
+    }
+
     fn define_version<W: io::Write>(o: &mut W) -> io::Result<()> {
         let molc_ver = semver::Version::parse(VERSION)
             .map(|v| (v.major * 1000 + v.minor) * 1000 + v.patch)
@@ -72,7 +91,25 @@ impl Generator {
 }
 
 impl super::LanguageGenerator for Generator {
-    fn generate<W: io::Write>(writer: &mut W, ast: &ast::Ast) -> io::Result<()> {
+    fn generate<W: io::Write>(
+        writer: &mut W,
+        ast: &ast::Ast,
+        options: &super::GeneratorOptions,
+    ) -> io::Result<()> {
+        let prefix = options.prefix_or_default();
+        if prefix == utilities::DEFAULT_PREFIX {
+            Self::generate_code(writer, ast)
+        } else {
+            let mut code = Vec::new();
+            Self::generate_code(&mut code, ast)?;
+            let code = String::from_utf8(code).expect("the generated code is UTF-8");
+            writer.write_all(utilities::replace_prefix(&code, prefix).as_bytes())
+        }
+    }
+}
+
+impl Generator {
+    fn generate_code<W: io::Write>(writer: &mut W, ast: &ast::Ast) -> io::Result<()> {
         writeln!(writer, "// Generated by Molecule {}", VERSION)?;
         writeln!(writer)?;
         Self::define_version(writer)?;
@@ -106,6 +143,11 @@ impl super::LanguageGenerator for Generator {
             decl.gen_default(writer)?;
         }
         writeln!(writer)?;
+        Self::title(writer, "Schema Hash")?;
+        for decl in ast.major_decls() {
+            decl.gen_schema_hash(writer)?;
+        }
+        writeln!(writer)?;
         writeln!(writer, r#"#undef ____"#)?;
         writeln!(writer)?;
         Self::title(writer, "Reader Functions")?;
@@ -75,6 +75,10 @@ impl GenReader for ast::Union {
         {
             self.define_reader_macro(writer, "_unpack(s)", "mol_union_unpack(s)")?;
         }
+        for item in self.items() {
+            let macro_sig_tail = format!("_unionid_{}", item.name());
+            self.define_reader_macro(writer, &macro_sig_tail, &item.id().to_string())?;
+        }
         Ok(())
     }
 
@@ -153,9 +157,63 @@ impl GenReader for ast::Struct {
     }
 }
 
+/// The segment of the sort key of an item in a collection.
+fn collection_key(item: &ast::TopDecl, collection: ast::Collection, seg: &str) -> String {
+    match (collection, item) {
+        (ast::Collection::Set, _) => seg.to_owned(),
+        (ast::Collection::Map, ast::TopDecl::Struct(entry)) => {
+            format!(
+                "mol_slice_by_offset(&{}, 0, {})",
+                seg,
+                entry.field_sizes()[0]
+            )
+        }
+        (ast::Collection::Map, _) => format!("mol_table_slice_by_index(&{}, 0)", seg),
+    }
+}
+
+/// Checks that the key of the current item is greater than the key of the previous item.
+fn write_key_ordering<W: io::Write>(
+    o: &mut W,
+    item: &ast::TopDecl,
+    collection: ast::Collection,
+    indent: &str,
+    has_prev: &str,
+) -> io::Result<()> {
+    let key = collection_key(item, collection, "inner");
+    w!(o, "{}mol_seg_t key = {};", indent, key);
+    w!(o, "{}if ({}) {{", indent, has_prev);
+    w!(
+        o,
+        "{}    mol_num_t min_size = prev_key.size < key.size ? prev_key.size : key.size;",
+        indent
+    );
+    w!(
+        o,
+        "{}    int cmp = memcmp(prev_key.ptr, key.ptr, min_size);",
+        indent
+    );
+    w!(
+        o,
+        "{}    if (cmp > 0 || (cmp == 0 && prev_key.size >= key.size)) {{",
+        indent
+    );
+    w!(o, "{}        return MOL_ERR_DATA;", indent);
+    w!(o, "{}    }}", indent);
+    w!(o, "{}}}", indent);
+    Ok(())
+}
+
 impl GenReader for ast::FixVec {
     fn gen_reader_interfaces_internal<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
-        {
+        if self.collection().is_some() || self.utf8() {
+            self.define_reader_function(
+                writer,
+                "_verify",
+                "(const mol_seg_t*, bool)",
+                "mol_errno",
+            )?;
+        } else {
             let macro_content = format!("mol_fixvec_verify(s, {})", self.item_size());
             self.define_reader_macro(writer, "_verify(s, c)", &macro_content)?;
         }
@@ -171,6 +229,113 @@ impl GenReader for ast::FixVec {
         }
         Ok(())
     }
+
+    fn gen_reader_function_verify<W: io::Write>(&self, o: &mut W) -> io::Result<()> {
+        if self.utf8() {
+            return self.gen_reader_function_verify_utf8(o);
+        }
+        let collection = if let Some(collection) = self.collection() {
+            collection
+        } else {
+            return Ok(());
+        };
+        let func_name = format!("{}_verify", self.reader_prefix());
+        let api_decorator = self.api_decorator();
+        let item_size = self.item_size();
+        w!(
+            o,
+            "{} mol_errno {} (const mol_seg_t *input, bool compatible) {{",
+            api_decorator,
+            func_name
+        );
+        w!(
+            o,
+            "    mol_errno errno = mol_fixvec_verify(input, {});",
+            item_size
+        );
+        w!(o, "    if (errno != MOL_OK) {{                            ");
+        w!(o, "        return errno;                                  ");
+        w!(o, "    }}                                                 ");
+        w!(o, "    mol_num_t item_count = mol_fixvec_length(input);   ");
+        w!(o, "    mol_seg_t prev_key = {{0}};                        ");
+        w!(o, "    for (mol_num_t i=0; i<item_count; i++) {{          ");
+        w!(o, "        mol_seg_t inner;                               ");
+        w!(
+            o,
+            "        inner.ptr = input->ptr + MOL_NUM_T_SIZE + {} * i;",
+            item_size
+        );
+        w!(o, "        inner.size = {};", item_size);
+        write_key_ordering(o, self.item().typ(), collection, "        ", "i > 0")?;
+        w!(o, "        prev_key = key;                                ");
+        w!(o, "    }}                                                 ");
+        w!(o, "    return MOL_OK;                                     ");
+        w!(o, "}}                                                     ");
+        Ok(())
+    }
+}
+
+impl ast::FixVec {
+    fn gen_reader_function_verify_utf8<W: io::Write>(&self, o: &mut W) -> io::Result<()> {
+        let func_name = format!("{}_verify", self.reader_prefix());
+        let api_decorator = self.api_decorator();
+        w!(
+            o,
+            "{} mol_errno {} (const mol_seg_t *input, bool compatible) {{",
+            api_decorator,
+            func_name
+        );
+        w!(o, "    mol_errno errno = mol_fixvec_verify(input, 1);     ");
+        w!(o, "    if (errno != MOL_OK) {{                            ");
+        w!(o, "        return errno;                                  ");
+        w!(o, "    }}                                                 ");
+        w!(o, "    mol_num_t size = mol_fixvec_length(input);         ");
+        w!(o, "    const uint8_t *ptr = input->ptr + MOL_NUM_T_SIZE;  ");
+        w!(o, "    mol_num_t i = 0;                                   ");
+        w!(o, "    while (i < size) {{                                ");
+        w!(o, "        uint8_t c = ptr[i];                            ");
+        w!(o, "        if (c < 0x80) {{                               ");
+        w!(o, "            i++;                                       ");
+        w!(o, "            continue;                                  ");
+        w!(o, "        }}                                             ");
+        w!(o, "        mol_num_t n;                                   ");
+        w!(o, "        uint8_t lower = 0x80, upper = 0xBF;            ");
+        w!(o, "        if (c >= 0xC2 && c <= 0xDF) {{                 ");
+        w!(o, "            n = 1;                                     ");
+        w!(o, "        }} else if (c >= 0xE0 && c <= 0xEF) {{         ");
+        w!(o, "            n = 2;                                     ");
+        w!(o, "            if (c == 0xE0) {{                          ");
+        w!(o, "                lower = 0xA0;                          ");
+        w!(o, "            }} else if (c == 0xED) {{                  ");
+        w!(o, "                upper = 0x9F;                          ");
+        w!(o, "            }}                                         ");
+        w!(o, "        }} else if (c >= 0xF0 && c <= 0xF4) {{         ");
+        w!(o, "            n = 3;                                     ");
+        w!(o, "            if (c == 0xF0) {{                          ");
+        w!(o, "                lower = 0x90;                          ");
+        w!(o, "            }} else if (c == 0xF4) {{                  ");
+        w!(o, "                upper = 0x8F;                          ");
+        w!(o, "            }}                                         ");
+        w!(o, "        }} else {{                                     ");
+        w!(o, "            return MOL_ERR_DATA;                       ");
+        w!(o, "        }}                                             ");
+        w!(o, "        if (size - i <= n) {{                          ");
+        w!(o, "            return MOL_ERR_DATA;                       ");
+        w!(o, "        }}                                             ");
+        w!(o, "        if (ptr[i+1] < lower || ptr[i+1] > upper) {{   ");
+        w!(o, "            return MOL_ERR_DATA;                       ");
+        w!(o, "        }}                                             ");
+        w!(o, "        for (mol_num_t j=2; j<=n; j++) {{              ");
+        w!(o, "            if ((ptr[i+j] & 0xC0) != 0x80) {{          ");
+        w!(o, "                return MOL_ERR_DATA;                   ");
+        w!(o, "            }}                                         ");
+        w!(o, "        }}                                             ");
+        w!(o, "        i += n + 1;                                    ");
+        w!(o, "    }}                                                 ");
+        w!(o, "    return MOL_OK;                                     ");
+        w!(o, "}}                                                     ");
+        Ok(())
+    }
 }
 
 impl GenReader for ast::DynVec {
@@ -226,6 +391,9 @@ impl GenReader for ast::DynVec {
         w!(o, "        return MOL_ERR_HEADER;                         ");
         w!(o, "    }}                                                 ");
         w!(o, "    mol_num_t end;                                     ");
+        if self.collection().is_some() {
+            w!(o, "    mol_seg_t prev_key = {{0}};                        ");
+        }
         w!(o, "    for (mol_num_t i=1; i<item_count; i++) {{          ");
         w!(o, "        ptr += MOL_NUM_T_SIZE;                         ");
         w!(o, "        end = mol_unpack_number(ptr);                  ");
@@ -242,6 +410,10 @@ impl GenReader for ast::DynVec {
         w!(o, "        if (errno != MOL_OK) {{                        ");
         w!(o, "            return MOL_ERR_DATA;                       ");
         w!(o, "        }}                                             ");
+        if let Some(collection) = self.collection() {
+            write_key_ordering(o, self.item().typ(), collection, "        ", "i > 1")?;
+            w!(o, "        prev_key = key;                                ");
+        }
         w!(o, "        offset = end;                                  ");
         w!(o, "    }}                                                 ");
         w!(o, "    if (offset > total_size) {{                        ");
@@ -253,7 +425,16 @@ impl GenReader for ast::DynVec {
         w!(o, "    if (mol_contained_by(&inner, input) != MOL_OK) {{  ");
         w!(o, "        return MOL_ERR_OFFSET;                         ");
         w!(o, "    }}                                                 ");
-        w!(o, "    return {}(&inner, compatible);                  ", f);
+        if let Some(collection) = self.collection() {
+            w!(o, "    mol_errno errno = {}(&inner, compatible);       ", f);
+            w!(o, "    if (errno != MOL_OK) {{                            ");
+            w!(o, "        return errno;                                  ");
+            w!(o, "    }}                                                 ");
+            write_key_ordering(o, self.item().typ(), collection, "    ", "item_count > 1")?;
+            w!(o, "    return MOL_OK;                                     ");
+        } else {
+            w!(o, "    return {}(&inner, compatible);                  ", f);
+        }
         w!(o, "}}                                                     ");
         Ok(())
     }
@@ -281,6 +462,9 @@ impl GenReader for ast::Table {
             self.define_reader_macro(writer, "_has_extra_fields(s)", &macro_content)?;
         }
         for (i, f) in self.fields().iter().enumerate() {
+            if f.reserved() {
+                continue;
+            }
             let macro_sig_tail = format!("_get_{}(s)", f.name());
             let macro_content = format!("mol_table_slice_by_index(s, {})", i);
             self.define_reader_macro(writer, &macro_sig_tail, &macro_content)?;
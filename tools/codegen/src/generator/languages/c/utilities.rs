@@ -4,6 +4,12 @@ use crate::ast::{self as ast, HasName};
 
 pub(super) const API_DECORATOR: &str = "MOLECULE_API_DECORATOR";
 
+/// The default prefix of the readers, the builders and the constants.
+pub(super) const DEFAULT_PREFIX: &str = "Mol";
+
+/// The kinds of the identifiers which are prefixed, after the prefix.
+const PREFIXED_KINDS: &[&str] = &["Reader_", "Builder_", "Default_", "SchemaHash_"];
+
 macro_rules! w {
     ($writer:ident, $( $args:tt )*) => {
         writeln!($writer, "{}", format!($( $args )*).trim_end())?;
@@ -23,6 +29,10 @@ pub(super) trait IdentPrefix: HasName {
         format!("MolDefault_{}", self.name())
     }
 
+    fn schema_hash_constant(&self) -> String {
+        format!("MolSchemaHash_{}", self.name())
+    }
+
     fn api_decorator(&self) -> &str {
         API_DECORATOR
     }
@@ -110,6 +120,32 @@ pub(super) trait IdentPrefix: HasName {
     }
 }
 
+/// Replaces the default prefix of the identifiers in the generated code by `prefix`.
+///
+/// Only the identifiers which start with the default prefix and a prefixed kind are replaced,
+/// e.g. `MolReader_Header`, but not `MOLECULE_API_DECORATOR` or the functions of the runtime.
+pub(super) fn replace_prefix(code: &str, prefix: &str) -> String {
+    let mut output = String::with_capacity(code.len());
+    let mut rest = code;
+    while let Some(index) = rest.find(DEFAULT_PREFIX) {
+        let (head, tail) = rest.split_at(index);
+        output.push_str(head);
+        let at_start = !output
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+        let after = &tail[DEFAULT_PREFIX.len()..];
+        if at_start && PREFIXED_KINDS.iter().any(|kind| after.starts_with(kind)) {
+            output.push_str(prefix);
+        } else {
+            output.push_str(DEFAULT_PREFIX);
+        }
+        rest = after;
+    }
+    output.push_str(rest);
+    output
+}
+
 impl IdentPrefix for ast::Option_ {}
 
 impl IdentPrefix for ast::Union {}
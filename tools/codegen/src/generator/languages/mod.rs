@@ -5,16 +5,44 @@ use crate::ast;
 mod c;
 pub(crate) mod rust;
 mod rust_lazy_reader;
+pub(crate) mod schema;
 
 #[derive(Debug, Clone, Copy)]
 pub enum Language {
     C,
     Rust,
     RustLazyReader,
+    Schema,
+}
+
+/// The options of the built-in generators, the generators ignore the options which they don't
+/// support.
+#[derive(Debug, Clone, Default)]
+pub struct GeneratorOptions {
+    crate_path: Option<String>,
+    module_path: Option<String>,
+    split: Option<Split>,
+    feature_gates: bool,
+    derives: Vec<String>,
+    prefix: Option<String>,
+}
+
+/// How the generated Rust code is split into modules, which are written into a directory named
+/// after the schema file, with a `mod.rs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    /// A module per declaration.
+    Type,
+    /// The modules `entities`, `readers` and `builders`.
+    Kind,
 }
 
 pub(super) trait LanguageGenerator {
-    fn generate<W: io::Write>(writer: &mut W, ast: &ast::Ast) -> io::Result<()>;
+    fn generate<W: io::Write>(
+        writer: &mut W,
+        ast: &ast::Ast,
+        options: &GeneratorOptions,
+    ) -> io::Result<()>;
 }
 
 impl fmt::Display for Language {
@@ -23,6 +51,7 @@ impl fmt::Display for Language {
             Self::C => write!(f, "C"),
             Self::Rust => write!(f, "Rust"),
             Self::RustLazyReader => write!(f, "Rust(Lazy Reader)"),
+            Self::Schema => write!(f, "Schema"),
         }
     }
 }
@@ -34,25 +63,308 @@ impl TryFrom<&str> for Language {
             "c" => Ok(Self::C),
             "rust" => Ok(Self::Rust),
             "rust-lazy-reader" => Ok(Self::RustLazyReader),
+            "schema" => Ok(Self::Schema),
             lang => Err(format!("unsupported language: [{}]", lang)),
         }
     }
 }
 
 impl Language {
-    pub(crate) fn extension(self) -> &'static str {
+    pub fn extension(self) -> &'static str {
         match self {
             Self::C => "h",
             Self::Rust => "rs",
             Self::RustLazyReader => "rs",
+            Self::Schema => "mol",
         }
     }
 
-    pub(crate) fn generate<W: io::Write>(self, writer: &mut W, ast: &ast::Ast) -> io::Result<()> {
+    pub fn generate<W: io::Write>(self, writer: &mut W, ast: &ast::Ast) -> io::Result<()> {
+        self.generate_with_options(writer, ast, &GeneratorOptions::default())
+    }
+
+    pub fn generate_with_options<W: io::Write>(
+        self,
+        writer: &mut W,
+        ast: &ast::Ast,
+        options: &GeneratorOptions,
+    ) -> io::Result<()> {
         match self {
-            Self::C => c::Generator::generate(writer, ast),
-            Self::Rust => rust::Generator::generate(writer, ast),
-            Self::RustLazyReader => rust_lazy_reader::Generator::generate(writer, ast),
+            Self::C => c::Generator::generate(writer, ast, options),
+            Self::Rust => rust::Generator::generate(writer, ast, options),
+            Self::RustLazyReader => rust_lazy_reader::Generator::generate(writer, ast, options),
+            Self::Schema => schema::Generator::generate(writer, ast, options),
+        }
+    }
+
+    /// Generates the files into a directory, returns the paths of the files, which are relative
+    /// to the directory, and their contents.
+    ///
+    /// The file is named after the module `name` with the extension, or, if the Rust code is
+    /// split, the files are in the directory of the module.
+    pub fn generate_files(
+        self,
+        name: &str,
+        ast: &ast::Ast,
+        options: &GeneratorOptions,
+    ) -> io::Result<Vec<(String, Vec<u8>)>> {
+        match self {
+            Self::Rust if options.split.is_some() => {
+                rust::Generator::generate_files(name, ast, options)
+            }
+            _ => {
+                let mut content = Vec::new();
+                self.generate_with_options(&mut content, ast, options)?;
+                Ok(vec![(format!("{}.{}", name, self.extension()), content)])
+            }
+        }
+    }
+
+    /// The names and the descriptions of the options which are supported by the generator.
+    pub fn options(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Rust => &[
+                (
+                    GeneratorOptions::CRATE_PATH,
+                    "The path of the molecule crate, e.g. `ckb_types::molecule`, `molecule` by default.",
+                ),
+                (
+                    GeneratorOptions::MODULE_PATH,
+                    "The path of the module which contains the modules of the imported schema files, e.g. `crate::generated`, `super` by default.",
+                ),
+                (
+                    GeneratorOptions::SPLIT,
+                    "Split the code into a directory with a module per declaration (`type`) or per kind (`kind`): entities, readers and builders.",
+                ),
+                (
+                    GeneratorOptions::FEATURE_GATES,
+                    "Gate the module of each declaration by a cargo feature which is named after the schema file and it, e.g. `types-header`, requires `split=type`.",
+                ),
+                (
+                    GeneratorOptions::DERIVES,
+                    "The additional derives of the entities, separated by commas, e.g. `PartialEq,Eq,Hash`.",
+                ),
+            ],
+            Self::RustLazyReader => &[
+                (
+                    GeneratorOptions::CRATE_PATH,
+                    "The path of the molecule crate, e.g. `ckb_types::molecule`, `molecule` by default.",
+                ),
+                (
+                    GeneratorOptions::MODULE_PATH,
+                    "The path of the module which contains the modules of the imported schema files, e.g. `crate::generated`, `super` by default.",
+                ),
+            ],
+            Self::C => &[(
+                GeneratorOptions::PREFIX,
+                "The prefix of the readers, the builders and the constants, e.g. `Ckb` for `CkbReader_Header`, `Mol` by default.",
+            )],
+            Self::Schema => &[],
+        }
+    }
+
+    /// Generates an umbrella file for the generated files of the modules in the same directory,
+    /// e.g. `mod.rs` for Rust or a header for C, `name` is its file name without the extension.
+    pub fn generate_umbrella<W: io::Write>(
+        self,
+        writer: &mut W,
+        name: &str,
+        modules: &[&str],
+    ) -> io::Result<()> {
+        match self {
+            Self::C => c::Generator::umbrella(writer, name, modules),
+            Self::Rust | Self::RustLazyReader => rust::Generator::umbrella(writer, modules),
+            Self::Schema => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "an umbrella file is not supported for schema",
+            )),
+        }
+    }
+}
+
+impl GeneratorOptions {
+    pub const CRATE_PATH: &'static str = "crate-path";
+    pub const MODULE_PATH: &'static str = "module-path";
+    pub const SPLIT: &'static str = "split";
+    pub const FEATURE_GATES: &'static str = "feature-gates";
+    pub const DERIVES: &'static str = "derives";
+    pub const PREFIX: &'static str = "prefix";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets an option by its name, as the options of the plugins.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<&mut Self, String> {
+        let invalid = |err: String| format!("invalid option `{}`: {}", name, err);
+        match name {
+            Self::CRATE_PATH => {
+                check_path(value).map_err(invalid)?;
+                self.crate_path = Some(value.to_owned());
+            }
+            Self::MODULE_PATH => {
+                check_path(value).map_err(invalid)?;
+                self.module_path = Some(value.to_owned());
+            }
+            Self::SPLIT => {
+                let split = match value {
+                    "type" => Split::Type,
+                    "kind" => Split::Kind,
+                    _ => return Err(invalid(format!("`{}` is not `type` or `kind`", value))),
+                };
+                self.split = Some(split);
+            }
+            Self::FEATURE_GATES => {
+                self.feature_gates = value
+                    .parse()
+                    .map_err(|_| invalid(format!("`{}` is not `true` or `false`", value)))?;
+            }
+            Self::DERIVES => {
+                let derives = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|derive| !derive.is_empty())
+                    .collect::<Vec<_>>();
+                for derive in &derives {
+                    check_derive(derive).map_err(invalid)?;
+                }
+                self.derives = derives.into_iter().map(ToOwned::to_owned).collect();
+            }
+            Self::PREFIX => {
+                check_prefix(value).map_err(invalid)?;
+                self.prefix = Some(value.to_owned());
+            }
+            _ => return Err(format!("unknown option `{}`", name)),
+        }
+        Ok(self)
+    }
+
+    /// The path of the molecule crate in the generated Rust code, e.g. `ckb_types::molecule`
+    /// when the code is used with the molecule which is re-exported by `ckb_types`.
+    ///
+    /// Panics if the path is not a valid Rust path.
+    pub fn crate_path(&mut self, path: &str) -> &mut Self {
+        if let Err(err) = check_path(path) {
+            panic!("invalid crate path: {}", err);
+        }
+        self.crate_path = Some(path.to_owned());
+        self
+    }
+
+    /// The path of the module which contains the modules of the imported schema files in the
+    /// generated Rust code, e.g. `crate::generated`, instead of `super`.
+    ///
+    /// Panics if the path is not a valid Rust path.
+    pub fn module_path(&mut self, path: &str) -> &mut Self {
+        if let Err(err) = check_path(path) {
+            panic!("invalid module path: {}", err);
         }
+        self.module_path = Some(path.to_owned());
+        self
+    }
+
+    /// Splits the generated Rust code into modules.
+    pub fn split(&mut self, split: Split) -> &mut Self {
+        self.split = Some(split);
+        self
+    }
+
+    /// Gates the module of each declaration by a cargo feature which is named after the schema
+    /// file and the declaration in snake case, e.g. `types-header`, the feature of a declaration
+    /// should enable the features of its dependencies, which are written into `features.toml`
+    /// with the modules.
+    ///
+    /// It requires to split the code by [`Split::Type`].
+    pub fn feature_gates(&mut self, enabled: bool) -> &mut Self {
+        self.feature_gates = enabled;
+        self
+    }
+
+    /// Adds the derives to the entities in the generated Rust code, e.g. `PartialEq`, `Eq` and
+    /// `Hash`, the entities always implement `Clone`, `Debug` and `Default`.
+    ///
+    /// Panics if a derive is not a valid Rust path or it's implemented already.
+    pub fn derives(&mut self, derives: &[&str]) -> &mut Self {
+        for derive in derives {
+            if let Err(err) = check_derive(derive) {
+                panic!("invalid derive: {}", err);
+            }
+        }
+        self.derives = derives.iter().map(|derive| (*derive).to_owned()).collect();
+        self
+    }
+
+    /// The prefix of the readers, the builders and the constants in the generated C code, e.g.
+    /// `Ckb` for `CkbReader_Header` and `CkbBuilder_Header`, instead of `Mol`.
+    ///
+    /// Panics if the prefix is not a valid C identifier.
+    pub fn prefix(&mut self, prefix: &str) -> &mut Self {
+        if let Err(err) = check_prefix(prefix) {
+            panic!("invalid prefix: {}", err);
+        }
+        self.prefix = Some(prefix.to_owned());
+        self
+    }
+
+    pub(crate) fn crate_path_or_default(&self) -> &str {
+        self.crate_path.as_deref().unwrap_or("molecule")
+    }
+
+    pub(crate) fn module_path_or_default(&self) -> &str {
+        self.module_path.as_deref().unwrap_or("super")
+    }
+
+    pub(crate) fn entity_derives(&self) -> &[String] {
+        &self.derives
+    }
+
+    pub(crate) fn prefix_or_default(&self) -> &str {
+        self.prefix.as_deref().unwrap_or("Mol")
+    }
+}
+
+/// Checks a Rust path, which is the identifiers separated by `::`.
+fn check_path(path: &str) -> Result<(), String> {
+    let is_ident = |segment: &str| {
+        segment
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && segment != "_"
+    };
+    if path.split("::").all(is_ident) {
+        Ok(())
+    } else {
+        Err(format!("`{}` is not a Rust path", path))
+    }
+}
+
+/// Checks a derive, which is a Rust path and not implemented by the entities already.
+fn check_derive(derive: &str) -> Result<(), String> {
+    check_path(derive)?;
+    if matches!(derive, "Clone" | "Debug" | "Default") {
+        Err(format!(
+            "`{}` is implemented by the entities already",
+            derive
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks a prefix of the C identifiers.
+fn check_prefix(prefix: &str) -> Result<(), String> {
+    let mut chars = prefix.chars();
+    if chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        Ok(())
+    } else {
+        Err(format!("`{}` is not a C identifier", prefix))
     }
 }
@@ -90,9 +90,35 @@ fn def_builder_for_struct_or_table(self_name: &str, inner: &[ast::FieldDecl]) ->
         let field_type = entity_name(f.typ().name());
         quote!(#field_name: #field_type,)
     });
+    if inner.iter().all(|f| f.default().is_none()) {
+        return quote!(
+            #[derive(Clone, Debug, Default)]
+            pub struct #builder { #( pub(crate) #fields )* }
+        );
+    }
+    let field_defaults = inner.iter().map(|f| {
+        let field_name = field_name(f.name());
+        if let Some(value) = f.default() {
+            let field_type = entity_name(f.typ().name());
+            let value = value.iter().map(|b| usize_lit(*b as usize));
+            quote!(
+                #field_name: #field_type::new_unchecked(
+                    molecule::bytes::Bytes::from_static(&[#( #value, )*])
+                ),
+            )
+        } else {
+            quote!(#field_name: ::core::default::Default::default(),)
+        }
+    });
     quote!(
-        #[derive(Clone, Debug, Default)]
+        #[derive(Clone, Debug)]
         pub struct #builder { #( pub(crate) #fields )* }
+
+        impl ::core::default::Default for #builder {
+            fn default() -> Self {
+                Self { #( #field_defaults )* }
+            }
+        }
     )
 }
 
@@ -1,7 +1,9 @@
 use proc_macro2 as m4;
 use quote::quote;
 
-use super::super::utilities::{builder_name, entity_name, field_name, reader_name, usize_lit};
+use super::super::utilities::{
+    builder_name, collection_key, entity_name, field_name, reader_name, usize_lit,
+};
 use crate::ast::{self as ast, HasName};
 
 pub(in super::super) trait ImplBuilder: HasName {
@@ -66,6 +68,7 @@ impl ast::Union {
         let entity = entity_name(self.name());
         self.items()
             .iter()
+            .filter(|item| self.is_unique_item_type(item))
             .map(|item| {
                 let item_name = entity_name(item.typ().name());
                 quote!(
@@ -209,17 +212,36 @@ impl ImplBuilder for ast::Struct {
     }
 }
 
+/// Items of a collection are sorted by their keys and deduplicated, the last one wins, both the
+/// expected length and the written items are computed from the prepared items.
+fn prepare_items(collection: Option<ast::Collection>) -> (m4::TokenStream, m4::TokenStream) {
+    if let Some(collection) = collection {
+        let key = collection_key(collection);
+        let prepare = quote!(
+            let mut items = self.0.iter().rev().collect::<Vec<_>>();
+            items.sort_by(|a, b| a.as_reader()#key.as_slice().cmp(b.as_reader()#key.as_slice()));
+            items.dedup_by(|a, b| a.as_reader()#key.as_slice() == b.as_reader()#key.as_slice());
+        );
+        (prepare, quote!(items))
+    } else {
+        (quote!(), quote!(self.0))
+    }
+}
+
 impl ImplBuilder for ast::FixVec {
     fn impl_builder_internal(&self) -> m4::TokenStream {
-        let write_inners = quote!(for inner in &self.0[..] {
+        let (prepare_items, items) = prepare_items(self.collection());
+        let write_inners = quote!(for inner in &#items[..] {
             writer.write_all(inner.as_slice())?;
         });
         quote!(
             fn expected_length(&self) -> usize {
-                molecule::NUMBER_SIZE + Self::ITEM_SIZE * self.0.len()
+                #prepare_items
+                molecule::NUMBER_SIZE + Self::ITEM_SIZE * #items.len()
             }
             fn write<W: molecule::io::Write>(&self, writer: &mut W) -> molecule::io::Result<()> {
-                writer.write_all(&molecule::pack_number(self.0.len() as molecule::Number))?;
+                #prepare_items
+                writer.write_all(&molecule::pack_number(#items.len() as molecule::Number))?;
                 #write_inners
                 Ok(())
             }
@@ -229,23 +251,25 @@ impl ImplBuilder for ast::FixVec {
 
 impl ImplBuilder for ast::DynVec {
     fn impl_builder_internal(&self) -> m4::TokenStream {
+        let (prepare_items, items) = prepare_items(self.collection());
         quote!(
             fn expected_length(&self) -> usize {
-                molecule::NUMBER_SIZE * (self.0.len() + 1)
-                    + self
-                        .0
+                #prepare_items
+                molecule::NUMBER_SIZE * (#items.len() + 1)
+                    + #items
                         .iter()
                         .map(|inner| inner.as_slice().len())
                         .sum::<usize>()
             }
             fn write<W: molecule::io::Write>(&self, writer: &mut W) -> molecule::io::Result<()> {
-                let item_count = self.0.len();
+                #prepare_items
+                let item_count = #items.len();
                 if item_count == 0 {
                     writer.write_all(&molecule::pack_number(
                         molecule::NUMBER_SIZE as molecule::Number,
                     ))?;
                 } else {
-                    let (total_size, offsets) = self.0.iter().fold(
+                    let (total_size, offsets) = #items.iter().fold(
                         (
                             molecule::NUMBER_SIZE * (item_count + 1),
                             Vec::with_capacity(item_count),
@@ -259,7 +283,7 @@ impl ImplBuilder for ast::DynVec {
                     for offset in offsets.into_iter() {
                         writer.write_all(&molecule::pack_number(offset as molecule::Number))?;
                     }
-                    for inner in self.0.iter() {
+                    for inner in #items.iter() {
                         writer.write_all(inner.as_slice())?;
                     }
                 }
@@ -309,7 +333,29 @@ fn gen_from_iter(name: &str, item_name: &str) -> m4::TokenStream {
 
 impl ast::FixVec {
     pub(crate) fn gen_from_iter(&self) -> m4::TokenStream {
-        gen_from_iter(self.name(), self.item().typ().name())
+        let from_iter = gen_from_iter(self.name(), self.item().typ().name());
+        if self.utf8() {
+            let entity = entity_name(self.name());
+            let reader = reader_name(self.name());
+            quote!(
+                #from_iter
+
+                impl From<&str> for #entity {
+                    fn from(v: &str) -> Self {
+                        Self::new_builder().set_str(v).build()
+                    }
+                }
+
+                impl<'r> ::core::convert::TryFrom<#reader<'r>> for &'r str {
+                    type Error = ::core::str::Utf8Error;
+                    fn try_from(value: #reader<'r>) -> Result<Self, Self::Error> {
+                        value.as_str()
+                    }
+                }
+            )
+        } else {
+            from_iter
+        }
     }
 }
 
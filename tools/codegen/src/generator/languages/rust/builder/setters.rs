@@ -81,7 +81,18 @@ impl ImplSetters for ast::Struct {
 
 impl ImplSetters for ast::FixVec {
     fn impl_setters(&self) -> m4::TokenStream {
-        impl_setters_for_vector(self.item().typ().name())
+        let setters = impl_setters_for_vector(self.item().typ().name());
+        if self.utf8() {
+            quote!(
+                #setters
+                pub fn set_str(mut self, v: &str) -> Self {
+                    self.0 = v.bytes().map(Into::into).collect();
+                    self
+                }
+            )
+        } else {
+            setters
+        }
     }
 }
 
@@ -100,6 +111,7 @@ impl ImplSetters for ast::Table {
 fn impl_setters_for_struct_or_table(inner: &[ast::FieldDecl]) -> m4::TokenStream {
     let each_setter = inner
         .iter()
+        .filter(|f| !f.reserved())
         .map(|f| {
             let field_name = field_name(f.name());
             let field_type = entity_name(f.typ().name());
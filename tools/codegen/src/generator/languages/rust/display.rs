@@ -115,7 +115,12 @@ impl ImplDisplay for ast::DynVec {
 
 impl ImplDisplay for ast::Table {
     fn impl_display(&self) -> m4::TokenStream {
-        let display_fields = self.fields().iter().enumerate().map(|(i, f)| {
+        let fields = self
+            .fields()
+            .iter()
+            .filter(|f| !f.reserved())
+            .collect::<Vec<_>>();
+        let display_fields = fields.iter().enumerate().map(|(i, f)| {
             let field = f.name().to_owned();
             let func = func_name(f.name());
             if i == 0 {
@@ -124,7 +129,7 @@ impl ImplDisplay for ast::Table {
                 quote!(write!(f, ", {}: {}", #field, self.#func())?;)
             }
         });
-        let display_unresolved = if self.fields().is_empty() {
+        let display_unresolved = if fields.is_empty() {
             quote!(write!(f, ".. ({} fields)", extra_count)?;)
         } else {
             quote!(write!(f, ", .. ({} fields)", extra_count)?;)
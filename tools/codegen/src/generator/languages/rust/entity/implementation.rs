@@ -109,7 +109,11 @@ impl ImplEntity for ast::DynVec {
 
 impl ImplEntity for ast::Table {
     fn impl_entity_internal(&self) -> m4::TokenStream {
-        let fields = self.fields().iter().map(|f| field_name(f.name()));
+        let fields = self
+            .fields()
+            .iter()
+            .filter(|f| !f.reserved())
+            .map(|f| field_name(f.name()));
         let fields_func = fields.clone();
         quote!(
             fn as_builder(self) -> Self::Builder {
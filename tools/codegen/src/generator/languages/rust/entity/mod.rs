@@ -2,39 +2,54 @@ use proc_macro2 as m4;
 use quote::quote;
 
 use super::utilities::{entity_name, reader_name, usize_lit};
-use crate::ast::{DefaultContent, HasName};
+use crate::{
+    ast::{DefaultContent, HasName, SchemaHash, SCHEMA_HASH_SIZE},
+    generator::languages::GeneratorOptions,
+};
 
 mod implementation;
 
 pub(super) trait GenEntity {
-    fn gen_entity(&self) -> m4::TokenStream;
+    fn gen_entity(&self, options: &GeneratorOptions) -> m4::TokenStream;
 }
 
 impl<T> GenEntity for T
 where
     T: HasName
         + DefaultContent
+        + SchemaHash
         + super::display::ImplDisplay
         + super::constants::DefConstants
         + super::properties::DefProperties
         + super::getters::ImplGetters
         + implementation::ImplEntity,
 {
-    fn gen_entity(&self) -> m4::TokenStream {
+    fn gen_entity(&self, options: &GeneratorOptions) -> m4::TokenStream {
         let entity = entity_name(self.name());
+        let derives = options.entity_derives().iter().map(|derive| {
+            derive
+                .parse::<m4::TokenStream>()
+                .expect("the derive is checked")
+        });
         let reader = reader_name(self.name());
         let default_size = usize_lit(self.default_content().len());
         let default_content = self
             .default_content()
             .into_iter()
             .map(|b| usize_lit(b as usize));
+        let schema_hash_size = usize_lit(SCHEMA_HASH_SIZE);
+        let schema_hash = self
+            .schema_hash()
+            .iter()
+            .map(|b| usize_lit(*b as usize))
+            .collect::<Vec<_>>();
         let display_stmts = self.impl_display();
         let constants = self.def_constants();
         let properties = self.def_properties();
         let getters = self.impl_getters_for_entity();
         let implementation = self.impl_entity();
         quote!(
-            #[derive(Clone)]
+            #[derive(Clone #(, #derives)*)]
             pub struct #entity(molecule::bytes::Bytes);
 
             impl ::core::fmt::LowerHex for #entity {
@@ -68,6 +83,7 @@ where
 
             impl #entity {
                 const DEFAULT_VALUE: [u8; #default_size] = [#( #default_content, )*];
+                pub const SCHEMA_HASH: [u8; #schema_hash_size] = [#( #schema_hash, )*];
                 #constants
                 #properties
                 #getters
@@ -47,7 +47,7 @@ impl GenEnumerator for ast::Union {
                     let inner_name = item.typ().name();
                     let entity_name = entity_name(inner_name);
                     let reader_name = reader_name(inner_name);
-                    let item_name = union_item_name(inner_name);
+                    let item_name = union_item_name(item.name());
                     let item_id = usize_lit(item.id());
                     let entity_union_item_path = quote!(#entity_union::#item_name);
                     let reader_union_item_path = quote!(#reader_union::#item_name);
@@ -72,9 +72,18 @@ impl GenEnumerator for ast::Union {
             .iter()
             .map(|x| x.to_string())
             .collect::<Vec<_>>();
+        // The items which share a type are told apart by their variant names.
+        let display_formats = &self
+            .items()
+            .iter()
+            .map(|item| match item.explicit_name() {
+                Some(name) => format!("{}({{}})", name),
+                None => "{}".to_owned(),
+            })
+            .collect::<Vec<_>>();
         let entity_default = {
             let inner = &self.items()[0];
-            let item_name = union_item_name(inner.typ().name());
+            let item_name = union_item_name(inner.name());
             quote!(#item_name(::core::default::Default::default()))
         };
         let code_union_definitions_and_impl_traits = quote!(
@@ -98,7 +107,7 @@ impl GenEnumerator for ast::Union {
                     match self {
                         #(
                             #entity_union_item_paths(ref item) => {
-                                write!(f, "{}::{}({})", Self::NAME, #union_items::NAME, item)
+                                write!(f, "{}::{}({})", Self::NAME, #union_items_string, item)
                             }
                         )*
                     }
@@ -109,7 +118,7 @@ impl GenEnumerator for ast::Union {
                     match self {
                         #(
                             #reader_union_item_paths(ref item) => {
-                                write!(f, "{}::{}({})", Self::NAME, #union_items::NAME, item)
+                                write!(f, "{}::{}({})", Self::NAME, #union_items_string, item)
                             }
                         )*
                     }
@@ -119,22 +128,30 @@ impl GenEnumerator for ast::Union {
             impl #entity_union {
                 pub(crate) fn display_inner(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                     match self {
-                        #( #entity_union_item_paths(ref item) => write!(f, "{}", item), )*
+                        #( #entity_union_item_paths(ref item) => write!(f, #display_formats, item), )*
                     }
                 }
             }
             impl<'r> #reader_union<'r> {
                 pub(crate) fn display_inner(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                     match self {
-                        #( #reader_union_item_paths(ref item) => write!(f, "{}", item), )*
+                        #( #reader_union_item_paths(ref item) => write!(f, #display_formats, item), )*
                     }
                 }
             }
         );
+        // Only the types which appear once in the union could be converted into it directly.
+        let unique_items = self
+            .items()
+            .iter()
+            .map(|item| self.is_unique_item_type(item))
+            .collect::<Vec<_>>();
         let code_entity_item_into_union = union_items
             .iter()
             .zip(entity_inners.iter())
-            .map(|(item_name, entity_name)| {
+            .zip(unique_items.iter())
+            .filter(|(_, is_unique)| **is_unique)
+            .map(|((item_name, entity_name), _)| {
                 quote!(
                     impl ::core::convert::From<#entity_name> for #entity_union {
                         fn from(item: #entity_name) -> Self {
@@ -147,7 +164,9 @@ impl GenEnumerator for ast::Union {
         let code_reader_item_into_union = union_items
             .iter()
             .zip(reader_inners.iter())
-            .map(|(item_name, reader_name)| {
+            .zip(unique_items.iter())
+            .filter(|(_, is_unique)| **is_unique)
+            .map(|((item_name, reader_name), _)| {
                 quote!(
                     impl<'r> ::core::convert::From<#reader_name<'r>> for #reader_union<'r> {
                         fn from(item: #reader_name<'r>) -> Self {
@@ -183,7 +202,7 @@ impl GenEnumerator for ast::Union {
                     }
                     pub fn as_reader<'r>(&'r self) -> #reader_union<'r> {
                         match self {
-                            #( #entity_union_item_paths(item) => item.as_reader().into(), )*
+                            #( #entity_union_item_paths(item) => #reader_union_item_paths(item.as_reader()), )*
                         }
                     }
                 }
@@ -1,82 +1,127 @@
-use std::io;
+use proc_macro2 as m4;
 
 use super::{
     builder::GenBuilder, entity::GenEntity, enumerator::GenEnumerator, iterator::GenIterator,
     reader::GenReader,
 };
-use crate::ast;
+use crate::{ast, generator::languages::GeneratorOptions};
+
+/// The kinds of the generated code, the module of each kind when the code is split by kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Kind {
+    /// The entities, the unions and the iterators.
+    Entity,
+    Reader,
+    /// The builders and the conversions which are implemented by the builders.
+    Builder,
+}
 
 pub(super) trait Generator {
-    fn generate<W: io::Write>(&self, writer: &mut W) -> io::Result<()>;
+    /// The code of the declaration, in order.
+    fn generate(&self, options: &GeneratorOptions) -> Vec<(Kind, m4::TokenStream)>;
+}
+
+impl Kind {
+    pub(super) const ALL: [Self; 3] = [Self::Entity, Self::Reader, Self::Builder];
+
+    pub(super) fn module_name(self) -> &'static str {
+        match self {
+            Self::Entity => "entities",
+            Self::Reader => "readers",
+            Self::Builder => "builders",
+        }
+    }
+}
+
+impl Generator for ast::TopDecl {
+    fn generate(&self, options: &GeneratorOptions) -> Vec<(Kind, m4::TokenStream)> {
+        match self {
+            Self::Option_(ref i) => i.generate(options),
+            Self::Union(ref i) => i.generate(options),
+            Self::Array(ref i) => i.generate(options),
+            Self::Struct(ref i) => i.generate(options),
+            Self::FixVec(ref i) => i.generate(options),
+            Self::DynVec(ref i) => i.generate(options),
+            Self::Table(ref i) => i.generate(options),
+            Self::Primitive(_) => unreachable!(),
+        }
+    }
 }
 
 impl Generator for ast::Option_ {
-    fn generate<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
-        writeln!(writer, "{}", self.gen_entity())?;
-        writeln!(writer, "{}", self.gen_reader())?;
-        writeln!(writer, "{}", self.gen_builder())?;
-        writeln!(writer, "{}", self.gen_from())?;
-        Ok(())
+    fn generate(&self, options: &GeneratorOptions) -> Vec<(Kind, m4::TokenStream)> {
+        vec![
+            (Kind::Entity, self.gen_entity(options)),
+            (Kind::Reader, self.gen_reader()),
+            (Kind::Builder, self.gen_builder()),
+            (Kind::Builder, self.gen_from()),
+        ]
     }
 }
 
 impl Generator for ast::Union {
-    fn generate<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
-        writeln!(writer, "{}", self.gen_entity())?;
-        writeln!(writer, "{}", self.gen_reader())?;
-        writeln!(writer, "{}", self.gen_builder())?;
-        writeln!(writer, "{}", self.gen_enumerator())?;
-        writeln!(writer, "{}", self.gen_from())?;
-        Ok(())
+    fn generate(&self, options: &GeneratorOptions) -> Vec<(Kind, m4::TokenStream)> {
+        vec![
+            (Kind::Entity, self.gen_entity(options)),
+            (Kind::Reader, self.gen_reader()),
+            (Kind::Builder, self.gen_builder()),
+            (Kind::Entity, self.gen_enumerator()),
+            (Kind::Builder, self.gen_from()),
+        ]
     }
 }
 
 impl Generator for ast::Array {
-    fn generate<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
-        writeln!(writer, "{}", self.gen_entity())?;
-        writeln!(writer, "{}", self.gen_reader())?;
-        writeln!(writer, "{}", self.gen_builder())?;
-        writeln!(writer, "{}", self.gen_from())?;
-        Ok(())
+    fn generate(&self, options: &GeneratorOptions) -> Vec<(Kind, m4::TokenStream)> {
+        vec![
+            (Kind::Entity, self.gen_entity(options)),
+            (Kind::Reader, self.gen_reader()),
+            (Kind::Builder, self.gen_builder()),
+            (Kind::Builder, self.gen_from()),
+        ]
     }
 }
 
 impl Generator for ast::Struct {
-    fn generate<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
-        writeln!(writer, "{}", self.gen_entity())?;
-        writeln!(writer, "{}", self.gen_reader())?;
-        writeln!(writer, "{}", self.gen_builder())?;
-        Ok(())
+    fn generate(&self, options: &GeneratorOptions) -> Vec<(Kind, m4::TokenStream)> {
+        vec![
+            (Kind::Entity, self.gen_entity(options)),
+            (Kind::Reader, self.gen_reader()),
+            (Kind::Builder, self.gen_builder()),
+        ]
     }
 }
 
 impl Generator for ast::FixVec {
-    fn generate<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
-        writeln!(writer, "{}", self.gen_entity())?;
-        writeln!(writer, "{}", self.gen_reader())?;
-        writeln!(writer, "{}", self.gen_builder())?;
-        writeln!(writer, "{}", self.gen_iterator())?;
-        writeln!(writer, "{}", self.gen_from_iter())?;
-        Ok(())
+    fn generate(&self, options: &GeneratorOptions) -> Vec<(Kind, m4::TokenStream)> {
+        vec![
+            (Kind::Entity, self.gen_entity(options)),
+            (Kind::Reader, self.gen_reader()),
+            (Kind::Builder, self.gen_builder()),
+            (Kind::Entity, self.gen_iterator()),
+            (Kind::Builder, self.gen_from_iter()),
+        ]
     }
 }
 
 impl Generator for ast::DynVec {
-    fn generate<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
-        writeln!(writer, "{}", self.gen_entity())?;
-        writeln!(writer, "{}", self.gen_reader())?;
-        writeln!(writer, "{}", self.gen_builder())?;
-        writeln!(writer, "{}", self.gen_iterator())?;
-        writeln!(writer, "{}", self.gen_from_iter())?;
-        Ok(())
+    fn generate(&self, options: &GeneratorOptions) -> Vec<(Kind, m4::TokenStream)> {
+        vec![
+            (Kind::Entity, self.gen_entity(options)),
+            (Kind::Reader, self.gen_reader()),
+            (Kind::Builder, self.gen_builder()),
+            (Kind::Entity, self.gen_iterator()),
+            (Kind::Builder, self.gen_from_iter()),
+        ]
     }
 }
 
 impl Generator for ast::Table {
-    fn generate<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
-        writeln!(writer, "{}", self.gen_entity())?;
-        writeln!(writer, "{}", self.gen_reader())?;
-        writeln!(writer, "{}", self.gen_builder())?;
-        Ok(())
+    fn generate(&self, options: &GeneratorOptions) -> Vec<(Kind, m4::TokenStream)> {
+        vec![
+            (Kind::Entity, self.gen_entity(options)),
+            (Kind::Reader, self.gen_reader()),
+            (Kind::Builder, self.gen_builder()),
+        ]
     }
 }
@@ -2,7 +2,8 @@ use proc_macro2 as m4;
 use quote::quote;
 
 use super::utilities::{
-    entity_name, entity_union_name, func_name, reader_name, reader_union_name, usize_lit,
+    collection_key, entity_name, entity_union_name, func_name, reader_name, reader_union_name,
+    union_item_name, usize_lit,
 };
 use crate::ast::{self as ast, HasName};
 
@@ -52,25 +53,26 @@ impl ImplGetters for ast::Option_ {
 
 impl ImplGetters for ast::Union {
     fn impl_getters_internal(&self, is_entity: bool) -> m4::TokenStream {
-        let (getter_ret, getter_stmt) = if is_entity {
+        let (union, getter_ret, getter_stmt) = if is_entity {
             let union = entity_union_name(self.name());
             let getter_ret = quote!(#union);
             let getter_stmt = quote!(self.0.slice(molecule::NUMBER_SIZE..));
-            (getter_ret, getter_stmt)
+            (union, getter_ret, getter_stmt)
         } else {
             let union = reader_union_name(self.name());
             let getter_ret = quote!(#union<'r>);
             let getter_stmt = quote!(&self.as_slice()[molecule::NUMBER_SIZE..]);
-            (getter_ret, getter_stmt)
+            (union, getter_ret, getter_stmt)
         };
         let match_stmts = self.items().iter().map(|item| {
             let item_id = usize_lit(item.id());
+            let item_name = union_item_name(item.name());
             let inner = if is_entity {
                 entity_name(item.typ().name())
             } else {
                 reader_name(item.typ().name())
             };
-            quote!(#item_id => #inner::new_unchecked(inner).into(),)
+            quote!(#item_id => #union::#item_name(#inner::new_unchecked(inner)),)
         });
         quote!(
             pub fn to_enum(&self) -> #getter_ret {
@@ -169,6 +171,86 @@ impl ImplGetters for ast::Struct {
     }
 }
 
+fn index_getter(collection: Option<ast::Collection>) -> m4::Ident {
+    if collection == Some(ast::Collection::Map) {
+        func_name("get_entry")
+    } else {
+        func_name("get")
+    }
+}
+
+fn collection_lookups(
+    collection: Option<ast::Collection>,
+    item: &ast::TopDecl,
+    is_entity: bool,
+) -> m4::TokenStream {
+    let collection = if let Some(collection) = collection {
+        collection
+    } else {
+        return quote!();
+    };
+    let key = collection_key(collection);
+    let binary_search = if is_entity {
+        quote!(
+            pub fn binary_search(&self, key: &[u8]) -> Result<usize, usize> {
+                self.as_reader().binary_search(key)
+            }
+        )
+    } else {
+        quote!(
+            pub fn binary_search(&self, key: &[u8]) -> Result<usize, usize> {
+                let (mut low, mut high) = (0, self.len());
+                while low < high {
+                    let mid = low + (high - low) / 2;
+                    match self.get_unchecked(mid)#key.as_slice().cmp(key) {
+                        ::core::cmp::Ordering::Less => low = mid + 1,
+                        ::core::cmp::Ordering::Greater => high = mid,
+                        ::core::cmp::Ordering::Equal => return Ok(mid),
+                    }
+                }
+                Err(low)
+            }
+        )
+    };
+    let lookups = match collection {
+        ast::Collection::Set => {
+            let item_reader = reader_name(item.name());
+            quote!(
+                pub fn contains(&self, item: #item_reader<'_>) -> bool {
+                    self.binary_search(item.as_slice()).is_ok()
+                }
+            )
+        }
+        ast::Collection::Map => {
+            let (key_field, value_field) = item
+                .entry_fields()
+                .expect("the item of a map should be an entry");
+            let key_reader = reader_name(key_field.typ().name());
+            let value_ret = if is_entity {
+                let value = entity_name(value_field.typ().name());
+                quote!(#value)
+            } else {
+                let value = reader_name(value_field.typ().name());
+                quote!(#value<'r>)
+            };
+            quote!(
+                pub fn get(&self, key: #key_reader<'_>) -> Option<#value_ret> {
+                    self.binary_search(key.as_slice())
+                        .ok()
+                        .map(|idx| self.get_unchecked(idx).value())
+                }
+                pub fn contains_key(&self, key: #key_reader<'_>) -> bool {
+                    self.binary_search(key.as_slice()).is_ok()
+                }
+            )
+        }
+    };
+    quote!(
+        #binary_search
+        #lookups
+    )
+}
+
 impl ImplGetters for ast::FixVec {
     fn impl_getters_internal(&self, is_entity: bool) -> m4::TokenStream {
         let (inner, getter_ret, getter_stmt, getter_ret_byte, getter_stmt_byte) = if is_entity {
@@ -198,8 +280,10 @@ impl ImplGetters for ast::FixVec {
                 getter_stmt_byte,
             )
         };
+        let getter = index_getter(self.collection());
+        let lookups = collection_lookups(self.collection(), self.item().typ(), is_entity);
         let common_part = quote!(
-            pub fn get(&self, idx: usize) -> Option<#getter_ret> {
+            pub fn #getter(&self, idx: usize) -> Option<#getter_ret> {
                 if idx >= self.len() {
                     None
                 } else {
@@ -211,8 +295,24 @@ impl ImplGetters for ast::FixVec {
                 let end = start + Self::ITEM_SIZE;
                 #inner::new_unchecked(#getter_stmt)
             }
+            #lookups
         );
-        if self.item().typ().is_byte() {
+        if self.utf8() {
+            let getter_ret_str = if is_entity {
+                quote!(&str)
+            } else {
+                quote!(&'r str)
+            };
+            quote!(
+                #common_part
+                pub fn raw_data(&self) -> #getter_ret_byte {
+                    #getter_stmt_byte
+                }
+                pub fn as_str(&self) -> Result<#getter_ret_str, ::core::str::Utf8Error> {
+                    ::core::str::from_utf8(&self.as_slice()[molecule::NUMBER_SIZE..])
+                }
+            )
+        } else if self.item().typ().is_byte() {
             quote!(
                 #common_part
                 pub fn raw_data(&self) -> #getter_ret_byte {
@@ -240,8 +340,10 @@ impl ImplGetters for ast::DynVec {
             let getter_stmt = quote!(&self.as_slice()[start..end]);
             (inner, getter_ret, getter_stmt_last, getter_stmt)
         };
+        let getter = index_getter(self.collection());
+        let lookups = collection_lookups(self.collection(), self.item().typ(), is_entity);
         quote!(
-            pub fn get(&self, idx: usize) -> Option<#getter_ret> {
+            pub fn #getter(&self, idx: usize) -> Option<#getter_ret> {
                 if idx >= self.len() {
                     None
                 } else {
@@ -260,6 +362,7 @@ impl ImplGetters for ast::DynVec {
                     #inner::new_unchecked(#getter_stmt)
                 }
             }
+            #lookups
         )
     }
 }
@@ -279,6 +382,7 @@ impl ImplGetters for ast::Table {
             .fields()
             .iter()
             .enumerate()
+            .filter(|(_, f)| !f.reserved())
             .map(|(i, f)| {
                 let func = func_name(f.name());
                 let (inner, getter_ret) = if is_entity {
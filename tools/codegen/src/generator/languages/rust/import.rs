@@ -1,24 +1,52 @@
+use std::io;
+
 use proc_macro2 as m4;
 use quote::quote;
 
 use super::utilities::ident_new;
-use crate::ast;
+use crate::{ast, generator::GeneratorOptions};
 
-pub(super) trait GenImport {
-    fn import_crate(&self) -> m4::TokenStream;
+pub(crate) trait GenImport {
+    /// Imports all items of the module of the imported schema file.
+    ///
+    /// The modules of the schema files are in the module of the option `module-path`, which is
+    /// `super` by default, each `../` of the import goes to its parent module.
+    fn import_crate(&self, options: &GeneratorOptions) -> io::Result<m4::TokenStream>;
 }
 
 impl GenImport for ast::ImportStmt {
-    fn import_crate(&self) -> m4::TokenStream {
-        let mut stmt = quote!(use super::);
+    fn import_crate(&self, options: &GeneratorOptions) -> io::Result<m4::TokenStream> {
+        let module_path = options.module_path_or_default();
+        let mut segments = module_path.split("::").collect::<Vec<_>>();
         for _ in 0..self.path_supers() {
-            stmt = quote!(#stmt super::);
+            // `super` can only be followed by `super`
+            if segments.iter().all(|segment| *segment == "super") {
+                segments.push("super");
+            } else if segments.len() > 1 {
+                segments.pop();
+            } else {
+                let message = format!(
+                    "the module path `{}` has no parent module for the import `{}`",
+                    module_path,
+                    self.name()
+                );
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+            }
+        }
+        let mut stmt = quote!(use);
+        for (index, segment) in segments.into_iter().enumerate() {
+            let segment = ident_new(segment);
+            if index == 0 {
+                stmt = quote!(#stmt #segment);
+            } else {
+                stmt = quote!(#stmt :: #segment);
+            }
         }
         for part in self.paths() {
             let part = ident_new(part);
-            stmt = quote!(#stmt #part::);
+            stmt = quote!(#stmt :: #part);
         }
         let name = ident_new(self.name());
-        quote!(#stmt #name::*;)
+        Ok(quote!(#stmt :: #name::*;))
     }
 }
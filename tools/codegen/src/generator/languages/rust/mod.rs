@@ -1,8 +1,13 @@
-use std::io;
+use std::{collections::HashSet, io, io::Write as _};
 
+use proc_macro2 as m4;
 use quote::quote;
 
-use crate::{ast, VERSION};
+use super::{GeneratorOptions, Split};
+use crate::{
+    ast::{self, HasName as _},
+    VERSION,
+};
 
 pub(crate) mod utilities;
 
@@ -28,43 +33,218 @@ mod iterator;
 /// Enumerator for `{ Vector } x { Entity, Reader }`
 mod enumerator;
 
-mod import;
+pub(super) mod import;
 use import::GenImport as _;
 
 mod generator;
-use generator::Generator as _;
+use generator::{Generator as _, Kind};
 
 pub(crate) struct Generator;
 
-impl super::LanguageGenerator for Generator {
-    fn generate<W: io::Write>(writer: &mut W, ast: &ast::Ast) -> io::Result<()> {
+impl Generator {
+    /// Generates a module which declares all modules.
+    pub(super) fn umbrella<W: io::Write>(writer: &mut W, modules: &[&str]) -> io::Result<()> {
         writeln!(writer, "// Generated by Molecule {}", VERSION)?;
         writeln!(writer)?;
-        let code = quote!(
-            use molecule::prelude::*;
-        );
-        write!(writer, "{}", code)?;
-        let imports = ast.imports();
-        if !imports.is_empty() {
-            writeln!(writer)?;
-            for import in imports {
-                let code = import.import_crate();
-                write!(writer, "{}", code)?;
-            }
+        for module in modules {
+            writeln!(writer, "pub mod {};", module)?;
         }
-        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+impl super::LanguageGenerator for Generator {
+    fn generate<W: io::Write>(
+        writer: &mut W,
+        ast: &ast::Ast,
+        options: &GeneratorOptions,
+    ) -> io::Result<()> {
+        if options.split.is_some() || options.feature_gates {
+            let message = "the split code should be generated into a directory";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+        let crate_path = utilities::crate_path(options);
+        let crate_path = crate_path.as_ref();
+        write_prelude(writer, ast, options, crate_path)?;
         for decl in ast.major_decls() {
-            match decl.as_ref() {
-                ast::TopDecl::Option_(ref i) => i.generate(writer)?,
-                ast::TopDecl::Union(ref i) => i.generate(writer)?,
-                ast::TopDecl::Array(ref i) => i.generate(writer)?,
-                ast::TopDecl::Struct(ref i) => i.generate(writer)?,
-                ast::TopDecl::FixVec(ref i) => i.generate(writer)?,
-                ast::TopDecl::DynVec(ref i) => i.generate(writer)?,
-                ast::TopDecl::Table(ref i) => i.generate(writer)?,
-                ast::TopDecl::Primitive(_) => unreachable!(),
-            };
+            for (_, code) in decl.generate(options) {
+                write_code(writer, code, crate_path)?;
+            }
         }
         Ok(())
     }
 }
+
+impl Generator {
+    /// Generates the modules of the split code into the directory of the module `name`.
+    pub(super) fn generate_files(
+        name: &str,
+        ast: &ast::Ast,
+        options: &GeneratorOptions,
+    ) -> io::Result<Vec<(String, Vec<u8>)>> {
+        let invalid_input = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+        let crate_path = utilities::crate_path(options);
+        let crate_path = crate_path.as_ref();
+        let mut root = Vec::new();
+        write_prelude(&mut root, ast, options, crate_path)?;
+        let mut files = Vec::new();
+        match options.split {
+            Some(Split::Type) => {
+                let mut features = Vec::new();
+                writeln!(features, "# Generated by Molecule {}", VERSION)?;
+                writeln!(features, "#")?;
+                writeln!(
+                    features,
+                    "# The features of the declarations, which should be added into `[features]`."
+                )?;
+                let major_decls = ast.major_decls();
+                let mut module_names = HashSet::new();
+                for decl in &major_decls {
+                    let module = utilities::func_name(decl.name()).to_string();
+                    if !module_names.insert(module.clone()) {
+                        let message = format!("more than one declaration is named `{}`", module);
+                        return Err(invalid_input(message));
+                    }
+                    writeln!(root)?;
+                    if options.feature_gates {
+                        let feature = feature_name(name, decl.name());
+                        let dependencies = decl
+                            .dependencies()
+                            .into_iter()
+                            .map(|dep| {
+                                let module = if major_decls.iter().any(|d| d.name() == dep.name()) {
+                                    name
+                                } else {
+                                    imported_module(ast, dep.name())?
+                                };
+                                Ok(format!("\"{}\"", feature_name(module, dep.name())))
+                            })
+                            .collect::<io::Result<Vec<_>>>()?;
+                        writeln!(features, "{} = [{}]", feature, dependencies.join(", "))?;
+                        writeln!(root, "#[cfg(feature = \"{}\")]", feature)?;
+                        writeln!(root, "mod {};", module)?;
+                        writeln!(root, "#[cfg(feature = \"{}\")]", feature)?;
+                        writeln!(root, "pub use {}::*;", module)?;
+                    } else {
+                        writeln!(root, "mod {};", module)?;
+                        writeln!(root, "pub use {}::*;", module)?;
+                    }
+                    let mut content = Vec::new();
+                    write_submodule_prelude(&mut content)?;
+                    for (_, code) in decl.generate(options) {
+                        write_code(&mut content, code, crate_path)?;
+                    }
+                    files.push((format!("{}/{}.rs", name, module), content));
+                }
+                if options.feature_gates {
+                    files.push((format!("{}/features.toml", name), features));
+                }
+            }
+            Some(Split::Kind) if !options.feature_gates => {
+                let decls = ast.major_decls();
+                let codes = decls
+                    .iter()
+                    .map(|decl| decl.generate(options))
+                    .collect::<Vec<_>>();
+                for kind in &Kind::ALL {
+                    let module = kind.module_name();
+                    writeln!(root)?;
+                    writeln!(root, "mod {};", module)?;
+                    writeln!(root, "pub use {}::*;", module)?;
+                    let mut content = Vec::new();
+                    write_submodule_prelude(&mut content)?;
+                    for (_, code) in codes.iter().flatten().filter(|(k, _)| k == kind) {
+                        write_code(&mut content, code.clone(), crate_path)?;
+                    }
+                    files.push((format!("{}/{}.rs", name, module), content));
+                }
+            }
+            _ => {
+                let message = "the feature gates require to split the code by type".to_owned();
+                return Err(invalid_input(message));
+            }
+        }
+        files.insert(0, (format!("{}/mod.rs", name), root));
+        Ok(files)
+    }
+}
+
+/// The feature of a declaration, which is prefixed by the module of its schema file, since the
+/// same type could be declared in several schema files.
+///
+/// The separator is `-`, which is not allowed in the names of the modules and the types.
+fn feature_name(module: &str, decl: &str) -> String {
+    format!("{}-{}", module, utilities::func_name(decl))
+}
+
+/// The module of the schema file which declares an imported type.
+///
+/// The declarations of an imported file include the files which are imported by it, so the
+/// file which declares the type is the import with the fewest declarations.
+fn imported_module<'a>(ast: &'a ast::Ast, decl: &str) -> io::Result<&'a str> {
+    ast.imports()
+        .iter()
+        .filter(|import| import.decls().iter().any(|name| name == decl))
+        .min_by_key(|import| import.decls().len())
+        .map(|import| import.name())
+        .ok_or_else(|| {
+            let message = format!("the schema file of the imported type `{}` is unknown", decl);
+            io::Error::new(io::ErrorKind::InvalidInput, message)
+        })
+}
+
+/// Writes the header, the prelude and the imports of the module of a schema file.
+fn write_prelude<W: io::Write>(
+    writer: &mut W,
+    ast: &ast::Ast,
+    options: &GeneratorOptions,
+    crate_path: Option<&m4::TokenStream>,
+) -> io::Result<()> {
+    writeln!(writer, "// Generated by Molecule {}", VERSION)?;
+    writeln!(writer)?;
+    // the imports are unused when all features are disabled, the attribute is not an inner
+    // attribute, so the module could be included by `include!`
+    let allow = if options.feature_gates {
+        quote!(#[allow(unused_imports)])
+    } else {
+        quote!()
+    };
+    let code = quote!(
+        #allow
+        use molecule::prelude::*;
+    );
+    write!(
+        writer,
+        "{}",
+        utilities::replace_crate_path(code, crate_path)
+    )?;
+    let imports = ast.imports();
+    if !imports.is_empty() {
+        writeln!(writer)?;
+        for import in imports {
+            let code = import.import_crate(options)?;
+            write!(writer, "{}", quote!(#allow #code))?;
+        }
+    }
+    writeln!(writer)
+}
+
+/// Writes the header of a module of the split code, which uses all items of its parent.
+fn write_submodule_prelude<W: io::Write>(writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "// Generated by Molecule {}", VERSION)?;
+    writeln!(writer)?;
+    writeln!(writer, "use super::*;")?;
+    writeln!(writer)
+}
+
+fn write_code<W: io::Write>(
+    writer: &mut W,
+    code: m4::TokenStream,
+    crate_path: Option<&m4::TokenStream>,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "{}",
+        utilities::replace_crate_path(code, crate_path)
+    )
+}
@@ -1,7 +1,7 @@
 use proc_macro2 as m4;
 use quote::quote;
 
-use super::super::utilities::{entity_name, reader_name, usize_lit};
+use super::super::utilities::{collection_key, entity_name, reader_name, usize_lit};
 use crate::ast::{self as ast, HasName};
 
 pub(in super::super) trait ImplReader: HasName {
@@ -103,8 +103,35 @@ impl ImplReader for ast::Struct {
     }
 }
 
+fn verify_ordering(name: &str, collection: Option<ast::Collection>) -> m4::TokenStream {
+    if let Some(collection) = collection {
+        let reader = reader_name(name);
+        let key = collection_key(collection);
+        quote!(
+            let reader = #reader::new_unchecked(slice);
+            for idx in 1..reader.len() {
+                if reader.get_unchecked(idx - 1)#key.as_slice() >= reader.get_unchecked(idx)#key.as_slice() {
+                    return ve!(Self, ItemsNotSorted, idx);
+                }
+            }
+        )
+    } else {
+        quote!()
+    }
+}
+
 impl ImplReader for ast::FixVec {
     fn impl_reader_internal(&self) -> m4::TokenStream {
+        let verify_ordering = verify_ordering(self.name(), self.collection());
+        let verify_utf8 = if self.utf8() {
+            quote!(
+                if let Err(err) = ::core::str::from_utf8(&slice[molecule::NUMBER_SIZE..]) {
+                    return ve!(Self, InvalidUtf8, err.valid_up_to());
+                }
+            )
+        } else {
+            quote!()
+        };
         quote!(
             fn verify(slice: &[u8], _compatible: bool) -> molecule::error::VerificationResult<()> {
                 use molecule::verification_error as ve;
@@ -123,6 +150,8 @@ impl ImplReader for ast::FixVec {
                 if slice_len != total_size {
                     return ve!(Self, TotalSizeNotMatch, total_size, slice_len);
                 }
+                #verify_utf8
+                #verify_ordering
                 Ok(())
             }
         )
@@ -132,6 +161,7 @@ impl ImplReader for ast::FixVec {
 impl ImplReader for ast::DynVec {
     fn impl_reader_internal(&self) -> m4::TokenStream {
         let inner = reader_name(self.item().typ().name());
+        let verify_ordering = verify_ordering(self.name(), self.collection());
         quote!(
             fn verify(slice: &[u8], compatible: bool) -> molecule::error::VerificationResult<()> {
                 use molecule::verification_error as ve;
@@ -169,6 +199,7 @@ impl ImplReader for ast::DynVec {
                     let end =  pair[1];
                     #inner::verify(&slice[start..end], compatible)?;
                 }
+                #verify_ordering
                 Ok(())
             }
         )
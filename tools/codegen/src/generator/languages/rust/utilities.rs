@@ -1,8 +1,11 @@
 use case::CaseExt;
 use proc_macro2 as m4;
+use quote::quote;
 
 use std::{collections::HashSet, sync::OnceLock};
 
+use crate::{ast, generator::GeneratorOptions};
+
 static RUST_KEYWORDS: OnceLock<HashSet<&'static str>> = OnceLock::new();
 
 fn rust_keyword() -> &'static HashSet<&'static str> {
@@ -138,3 +141,82 @@ pub(super) fn entity_iterator_name(name: &str) -> m4::Ident {
 pub(super) fn reader_iterator_name(name: &str) -> m4::Ident {
     ident_name(name, "ReaderIterator")
 }
+
+/// The accessor of the sort key of an item in a collection.
+pub(super) fn collection_key(collection: ast::Collection) -> m4::TokenStream {
+    match collection {
+        ast::Collection::Set => quote!(),
+        ast::Collection::Map => quote!(.key()),
+    }
+}
+
+/// The path of the molecule crate, or `None` if it's the default `molecule`.
+pub(crate) fn crate_path(options: &GeneratorOptions) -> Option<m4::TokenStream> {
+    let path = options.crate_path_or_default();
+    if path == "molecule" {
+        None
+    } else {
+        Some(path.parse().expect("the crate path is checked"))
+    }
+}
+
+/// Replaces `molecule` at the start of the paths by the path of the molecule crate.
+///
+/// The generated code always refers the molecule crate as `molecule`, the fields and the modules
+/// which are named `molecule` are not at the start of the paths.
+pub(crate) fn replace_crate_path(
+    code: m4::TokenStream,
+    crate_path: Option<&m4::TokenStream>,
+) -> m4::TokenStream {
+    let crate_path = if let Some(crate_path) = crate_path {
+        crate_path
+    } else {
+        return code;
+    };
+    let mut output = m4::TokenStream::new();
+    // the previous token is `::` or `.`
+    let mut in_path = false;
+    // the previous token is the first `:` of `::`
+    let mut joint_colon = false;
+    let mut tokens = code.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let next_is_path_sep = matches!(
+            tokens.peek(),
+            Some(m4::TokenTree::Punct(ref punct))
+                if punct.as_char() == ':' && punct.spacing() == m4::Spacing::Joint
+        );
+        match token {
+            m4::TokenTree::Group(ref group) => {
+                let stream = replace_crate_path(group.stream(), Some(crate_path));
+                let mut new_group = m4::Group::new(group.delimiter(), stream);
+                new_group.set_span(group.span());
+                output.extend(Some(m4::TokenTree::Group(new_group)));
+                in_path = false;
+                joint_colon = false;
+            }
+            m4::TokenTree::Ident(ref ident)
+                if ident == "molecule" && !in_path && next_is_path_sep =>
+            {
+                output.extend(crate_path.clone());
+                in_path = false;
+                joint_colon = false;
+            }
+            m4::TokenTree::Punct(ref punct) if punct.as_char() == ':' => {
+                in_path = joint_colon;
+                joint_colon = !joint_colon && punct.spacing() == m4::Spacing::Joint;
+                output.extend(Some(token));
+            }
+            m4::TokenTree::Punct(ref punct) => {
+                in_path = punct.as_char() == '.';
+                joint_colon = false;
+                output.extend(Some(token));
+            }
+            _ => {
+                in_path = false;
+                joint_colon = false;
+                output.extend(Some(token));
+            }
+        }
+    }
+    output
+}
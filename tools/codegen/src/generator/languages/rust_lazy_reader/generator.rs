@@ -1,14 +1,14 @@
 use super::LazyReaderGenerator;
 use crate::ast::{self, HasName, *};
 use crate::generator::{field_name, ident_name, ident_new};
-use proc_macro2::{Literal, TokenStream};
+use proc_macro2::{Literal, TokenStream, TokenTree};
 use quote::quote;
 use std::io;
 
 impl ast::Union {
-    fn get_item_name(typ: &TopDecl) -> TokenStream {
-        let item_name = ident_name(typ.name(), "");
-        let item_type_name = Self::get_type_name(typ);
+    fn get_item_name(item: &UnionItemDecl) -> TokenStream {
+        let item_name = ident_name(item.name(), "");
+        let item_type_name = Self::get_type_name(item.typ());
 
         quote! {
             #item_name(#item_type_name),
@@ -60,7 +60,7 @@ impl LazyReaderGenerator for ast::Union {
 
         // generate enum:
         let q = self.items().iter().map(|item| {
-            let item_name = Self::get_item_name(item.typ().as_ref());
+            let item_name = Self::get_item_name(item);
             quote! {
                 #item_name
             }
@@ -78,8 +78,7 @@ impl LazyReaderGenerator for ast::Union {
         // generate enum try_from
         let q = self.items().iter().map(|item| {
             let item_id = item.id();
-            let item_name_str = item.typ().name();
-            let item_name = ident_name(item_name_str, "");
+            let item_name = ident_name(item.name(), "");
             let item_type = Self::get_type_name(item.typ());
 
             let q = match item.typ().as_ref() {
@@ -159,7 +158,7 @@ impl LazyReaderGenerator for ast::Union {
 
         // generate verify
         let verify_items = self.items().iter().map(|item| {
-            let item_name = ident_name(item.typ().name(), "");
+            let item_name = ident_name(item.name(), "");
             match item.typ().as_ref() {
                 TopDecl::Primitive(_) => {
                     quote!( Self::#item_name(_v) =>  Ok(()), )
@@ -237,16 +236,12 @@ impl LazyReaderGenerator for ast::Array {
             }
         };
 
-        let val_compatible = if verify_sub.is_empty() {
-            quote!(_compatible)
-        } else {
-            quote!(compatible)
-        };
+        let val_compatible = compatible_param(&verify_sub);
         let q = quote! {
             impl #name {
                 pub fn verify(&self, #val_compatible: bool) -> Result<(), Error> {
                     self.cursor.verify_fixed_size(#total_size)?;
-                    #verify_sub;
+                    #verify_sub
                     Ok(())
                 }
             }
@@ -263,13 +258,18 @@ impl LazyReaderGenerator for ast::Struct {
         let name = ident_name(self.name(), "");
         let total_size: usize = self.field_sizes().iter().sum();
 
-        let verify_fields = self.fields().iter().map(verify_filed);
+        let verify_fields = self
+            .fields()
+            .iter()
+            .map(verify_filed)
+            .collect::<TokenStream>();
+        let val_compatible = compatible_param(&verify_fields);
 
         let q = quote! {
             impl #name {
-                pub fn verify(&self, compatible: bool) -> Result<(), Error> {
+                pub fn verify(&self, #val_compatible: bool) -> Result<(), Error> {
                     self.cursor.verify_fixed_size(#total_size)?;
-                    #( #verify_fields )*
+                    #verify_fields
                     Ok(())
                 }
             }
@@ -279,6 +279,61 @@ impl LazyReaderGenerator for ast::Struct {
     }
 }
 
+// the parameter `compatible` of `verify`, which is unused if the inner items don't verify with it,
+// e.g. the bytes and the strings.
+fn compatible_param(verify: &TokenStream) -> TokenStream {
+    fn uses_compatible(stream: TokenStream) -> bool {
+        stream.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => ident == "compatible",
+            TokenTree::Group(group) => uses_compatible(group.stream()),
+            _ => false,
+        })
+    }
+    if uses_compatible(verify.clone()) {
+        quote!(compatible)
+    } else {
+        quote!(_compatible)
+    }
+}
+
+// the items of a set, or the keys of a map, should be strictly increasing.
+fn verify_ordering(
+    item: &TopDecl,
+    collection: Option<ast::Collection>,
+    item_cursor: TokenStream,
+) -> TokenStream {
+    let key_cursor = match (collection, item) {
+        (None, _) => return quote!(),
+        (Some(ast::Collection::Set), _) => item_cursor,
+        (Some(ast::Collection::Map), TopDecl::Struct(entry)) => {
+            let key_size = entry.field_sizes()[0];
+            quote!(#item_cursor.slice_by_offset(0, #key_size)?)
+        }
+        (Some(ast::Collection::Map), _) => quote!(#item_cursor.table_slice_by_index(0)?),
+    };
+    quote! {
+        let mut prev_key: Option<Vec<u8>> = None;
+        for i in 0..self.len()? {
+            let key: Vec<u8> = #key_cursor.try_into()?;
+            if let Some(prev_key) = prev_key {
+                if prev_key >= key {
+                    return Err(Error::Verify);
+                }
+            }
+            prev_key = Some(key);
+        }
+    }
+}
+
+fn verify_utf8(raw_bytes: TokenStream) -> TokenStream {
+    quote! {
+        let data: Vec<u8> = #raw_bytes.try_into()?;
+        if core::str::from_utf8(&data).is_err() {
+            return Err(Error::Verify);
+        }
+    }
+}
+
 // in FixVec, all item size is same and known already, the count is unknown.
 impl LazyReaderGenerator for ast::FixVec {
     fn gen_rust<W: io::Write>(&self, output: &mut W) -> io::Result<()> {
@@ -295,11 +350,24 @@ impl LazyReaderGenerator for ast::FixVec {
 
         let name = ident_name(self.name(), "");
         let item_size = self.item_size();
+        let verify_ordering = verify_ordering(
+            self.item().typ(),
+            self.collection(),
+            quote!(self.cursor.fixvec_slice_by_index(#item_size, i)?),
+        );
+
+        let verify_utf8 = if self.utf8() {
+            verify_utf8(quote!(self.cursor.convert_to_rawbytes()?))
+        } else {
+            quote!()
+        };
 
         let q = quote! {
             impl #name {
                 pub fn verify(&self, _compatible: bool) -> Result<(), Error> {
                     self.cursor.verify_fixvec(#item_size)?;
+                    #verify_utf8
+                    #verify_ordering
                     Ok(())
                 }
             }
@@ -332,18 +400,21 @@ impl LazyReaderGenerator for ast::DynVec {
                 })
             }
         };
-        let val_compatible = if verify_sub.is_empty() {
-            quote!(_compatible)
-        } else {
-            quote!(compatible)
-        };
+        let val_compatible = compatible_param(&verify_sub);
+
+        let verify_ordering = verify_ordering(
+            self.item().typ(),
+            self.collection(),
+            quote!(self.cursor.dynvec_slice_by_index(i)?),
+        );
 
         let name = ident_name(self.name(), "");
         let q = quote! {
             impl #name {
                 pub fn verify(&self, #val_compatible: bool) -> Result<(), Error> {
                     self.cursor.verify_dynvec()?;
-                    #verify_sub;
+                    #verify_sub
+                    #verify_ordering
                     Ok(())
                 }
             }
@@ -562,9 +633,15 @@ fn generate_rust_common_table_impl<W: io::Write>(
     let convert_code = tc.gen_convert_code();
     let name = ident_name(plain_name, "");
     let field_name = field_name(field.name());
+    // reserved fields are only read for verification
+    let vis = if field.reserved() {
+        quote!()
+    } else {
+        quote!(pub)
+    };
     let q = quote! {
         impl #name {
-            pub fn #field_name(&self) -> Result<#transformed_name, Error> {
+            #vis fn #field_name(&self) -> Result<#transformed_name, Error> {
                 let cur = self.cursor.#slice_by?;
                 #convert_code
              }
@@ -816,8 +893,13 @@ fn verify_typ(typ: &TopDecl, q_val: TokenStream) -> TokenStream {
                 #q_val.verify(compatible)?;
             )
         }
-        TopDecl::FixVec(_) => {
-            quote!()
+        TopDecl::FixVec(v) => {
+            if v.utf8() {
+                // the getters return the raw bytes of strings
+                verify_utf8(q_val)
+            } else {
+                quote!()
+            }
         }
         TopDecl::DynVec(_) => {
             quote!(
@@ -1,6 +1,7 @@
+use super::rust::import::GenImport as _;
 use crate::ast;
 use crate::ast::HasName;
-use crate::generator::ident_name;
+use crate::generator::{ident_name, GeneratorOptions};
 use quote::quote;
 use std::io;
 
@@ -9,17 +10,28 @@ mod generator;
 pub(crate) struct Generator;
 
 impl super::LanguageGenerator for Generator {
-    fn generate<W: io::Write>(output: &mut W, ast: &ast::Ast) -> io::Result<()> {
+    fn generate<W: io::Write>(
+        output: &mut W,
+        ast: &ast::Ast,
+        options: &GeneratorOptions,
+    ) -> io::Result<()> {
+        // only the unions use `NUMBER_SIZE`
+        let has_union = ast
+            .major_decls()
+            .iter()
+            .any(|decl| matches!(decl.as_ref(), ast::TopDecl::Union(_)));
         writeln!(
             output,
             r#"
         extern crate alloc;
-        use molecule::lazy_reader::{{Cursor, Error, NUMBER_SIZE}};
-        use core::convert::TryInto;"#
+        use {}::lazy_reader::{{Cursor, Error{}}};
+        use core::convert::TryInto;"#,
+            options.crate_path_or_default(),
+            if has_union { ", NUMBER_SIZE" } else { "" }
         )?;
 
         for import in ast.imports() {
-            writeln!(output, "use super::{}::*; ", &import.name())?;
+            writeln!(output, "{}", import.import_crate(options)?)?;
         }
 
         for decl in ast.major_decls() {
@@ -0,0 +1,295 @@
+use std::{collections::HashSet, io, rc::Rc};
+
+use crate::{
+    ast::{self, HasName as _},
+    VERSION,
+};
+
+const INDENT: &str = "    ";
+
+/// Writes the canonical schema source of an AST.
+///
+/// The anonymous types are written as declarations, the imports are sorted by their paths,
+/// and the ids of union items are only written if they are not the next of the previous ids.
+/// The output is already formatted by the [`Formatter`](crate::Formatter).
+pub(crate) struct Generator;
+
+impl super::LanguageGenerator for Generator {
+    fn generate<W: io::Write>(
+        writer: &mut W,
+        ast: &ast::Ast,
+        _options: &super::GeneratorOptions,
+    ) -> io::Result<()> {
+        write_schema(
+            writer,
+            ast.syntax_version(),
+            ast.imports(),
+            &ast.major_decls(),
+        )
+    }
+}
+
+/// Writes the schema source which has the imports and the declarations.
+pub(crate) fn write_schema<W: io::Write>(
+    writer: &mut W,
+    syntax_version: &ast::SyntaxVersion,
+    imports: &[ast::ImportStmt],
+    decls: &[Rc<ast::TopDecl>],
+) -> io::Result<()> {
+    writeln!(writer, "// Generated by Molecule {}", VERSION)?;
+    writeln!(writer)?;
+    writeln!(writer, "syntax = {};", syntax_version.version())?;
+
+    let mut imports = imports.iter().map(import_path).collect::<Vec<_>>();
+    if !imports.is_empty() {
+        imports.sort();
+        writeln!(writer)?;
+        for path in imports {
+            writeln!(writer, "import {};", path)?;
+        }
+    }
+
+    // the entry types of maps are declared by the maps
+    let entries = decls
+        .iter()
+        .filter_map(|decl| map_entry(decl))
+        .map(|entry| entry.name().to_owned())
+        .collect::<HashSet<_>>();
+    let decls = decls
+        .iter()
+        .filter(|decl| !entries.contains(decl.name()))
+        .collect::<Vec<_>>();
+    if !decls.is_empty() {
+        writeln!(writer)?;
+        for decl in decls {
+            gen_decl(writer, decl)?;
+        }
+    }
+    Ok(())
+}
+
+fn import_path(import: &ast::ImportStmt) -> String {
+    let mut path = "../".repeat(import.path_supers());
+    for p in import.paths() {
+        path.push_str(p);
+        path.push('/');
+    }
+    path.push_str(import.name());
+    path
+}
+
+fn map_entry(decl: &ast::TopDecl) -> Option<&ast::TopDecl> {
+    match decl {
+        ast::TopDecl::FixVec(inner) if inner.collection() == Some(ast::Collection::Map) => {
+            Some(inner.item().typ())
+        }
+        ast::TopDecl::DynVec(inner) if inner.collection() == Some(ast::Collection::Map) => {
+            Some(inner.item().typ())
+        }
+        _ => None,
+    }
+}
+
+fn gen_decl<W: io::Write>(writer: &mut W, decl: &ast::TopDecl) -> io::Result<()> {
+    match decl {
+        ast::TopDecl::Primitive(_) => unreachable!(),
+        ast::TopDecl::Option_(inner) => writeln!(
+            writer,
+            "option {} ({});",
+            inner.name(),
+            inner.item().typ().name()
+        ),
+        ast::TopDecl::Union(inner) => gen_union(writer, inner),
+        ast::TopDecl::Array(inner) => writeln!(
+            writer,
+            "array {} [{}; {}];",
+            inner.name(),
+            inner.item().typ().name(),
+            inner.item_count()
+        ),
+        ast::TopDecl::Struct(inner) => gen_fields(writer, "struct", inner.name(), inner.fields()),
+        ast::TopDecl::FixVec(inner) if inner.utf8() => writeln!(writer, "string {};", inner.name()),
+        ast::TopDecl::FixVec(inner) => {
+            gen_vector(writer, decl, inner.name(), inner.item(), inner.collection())
+        }
+        ast::TopDecl::DynVec(inner) => {
+            gen_vector(writer, decl, inner.name(), inner.item(), inner.collection())
+        }
+        ast::TopDecl::Table(inner) => gen_fields(writer, "table", inner.name(), inner.fields()),
+    }
+}
+
+fn gen_vector<W: io::Write>(
+    writer: &mut W,
+    decl: &ast::TopDecl,
+    name: &str,
+    item: &ast::ItemDecl,
+    collection: Option<ast::Collection>,
+) -> io::Result<()> {
+    match collection {
+        None => writeln!(writer, "vector {} <{}>;", name, item.typ().name()),
+        Some(ast::Collection::Set) => writeln!(writer, "set {} <{}>;", name, item.typ().name()),
+        Some(ast::Collection::Map) => {
+            let (key, value) = map_entry(decl)
+                .and_then(ast::TopDecl::entry_fields)
+                .unwrap();
+            writeln!(
+                writer,
+                "map {} <{}, {}>;",
+                name,
+                key.typ().name(),
+                value.typ().name()
+            )
+        }
+    }
+}
+
+fn gen_fields<W: io::Write>(
+    writer: &mut W,
+    keyword: &str,
+    name: &str,
+    fields: &[ast::FieldDecl],
+) -> io::Result<()> {
+    if fields.is_empty() {
+        return writeln!(writer, "{} {} {{}}", keyword, name);
+    }
+    writeln!(writer, "{} {} {{", keyword, name)?;
+    // the colons are aligned, as the formatter does
+    let width = fields.iter().map(|field| field.name().len()).max().unwrap();
+    for field in fields {
+        write!(
+            writer,
+            "{}{:width$}: {}",
+            INDENT,
+            field.name(),
+            field.typ().name()
+        )?;
+        if let Some(default) = field.default() {
+            write!(writer, " = 0x")?;
+            for byte in default {
+                write!(writer, "{:02x}", byte)?;
+            }
+        }
+        writeln!(writer, ",")?;
+    }
+    writeln!(writer, "}}")
+}
+
+fn gen_union<W: io::Write>(writer: &mut W, union: &ast::Union) -> io::Result<()> {
+    writeln!(writer, "union {} {{", union.name())?;
+    // the items, which are split by the colons
+    let mut items = Vec::with_capacity(union.items().len());
+    let mut next_id = 0;
+    for item in union.items() {
+        let typ = item.typ().name();
+        let id = item.id();
+        items.push(match (item.explicit_name(), id == next_id) {
+            (None, true) => (typ, None),
+            (None, false) => (typ, Some(id.to_string())),
+            (Some(name), true) => (name.as_str(), Some(typ.to_owned())),
+            (Some(name), false) => (name.as_str(), Some(format!("{} = {}", typ, id))),
+        });
+        next_id = id + 1;
+    }
+    let width = items
+        .iter()
+        .filter(|(_, rest)| rest.is_some())
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or_default();
+    for (label, rest) in items {
+        if let Some(rest) = rest {
+            writeln!(writer, "{}{:width$}: {},", INDENT, label, rest)?;
+        } else {
+            writeln!(writer, "{}{},", INDENT, label)?;
+        }
+    }
+    if !union.reserved_ids().is_empty() {
+        let ids = union
+            .reserved_ids()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        writeln!(writer, "{}reserved {};", INDENT, ids.join(", "))?;
+    }
+    writeln!(writer, "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write as _};
+
+    use super::super::LanguageGenerator as _;
+    use super::Generator;
+    use crate::{ast::SchemaHash as _, Formatter, Parser};
+
+    #[test]
+    fn test_generate_schema() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        fs::create_dir_all(dir.join("common")).unwrap();
+        fs::write(
+            dir.join("common").join("types.mol"),
+            "syntax = 2;\narray Uint32 [byte; 4];\n",
+        )
+        .unwrap();
+        let source = "syntax = 2;
+import common/types;
+struct Point { x: Uint32 = 7, y: Uint32, }
+table Shape { _reserved: Bytes, points: vector<Point>, tag: option<[byte; 2]>, }
+union Either { Point, Shape: 4, Named: Point = 6, Other: Shape, reserved 5, 9; }
+string Name;
+vector Bytes <byte>;
+set Names <Name>;
+map Points <Name, Point>;
+map Sizes <Uint32, Uint32>;
+";
+        let path = dir.join("shapes.mol");
+        fs::write(&path, source).unwrap();
+        let ast = Parser::parse(&path);
+        let mut output = Vec::new();
+        Generator::generate(&mut output, &ast, &Default::default()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let decls = output.split("import common/types;\n\n").nth(1).unwrap();
+        assert_eq!(
+            decls,
+            "vector PointVec <Point>;
+array Byte2 [byte; 2];
+option Byte2Opt (Byte2);
+struct Point {
+    x: Uint32 = 0x07000000,
+    y: Uint32,
+}
+table Shape {
+    _reserved: Bytes,
+    points   : PointVec,
+    tag      : Byte2Opt,
+}
+union Either {
+    Point,
+    Shape: 4,
+    Named: Point = 6,
+    Other: Shape,
+    reserved 5, 9;
+}
+string Name;
+vector Bytes <byte>;
+set Names <Name>;
+map Points <Name, Point>;
+map Sizes <Uint32, Uint32>;
+"
+        );
+        assert!(Formatter::check(&output).unwrap());
+
+        let emitted = dir.join("emitted.mol");
+        fs::File::create(&emitted)
+            .unwrap()
+            .write_all(output.as_bytes())
+            .unwrap();
+        let recovered = Parser::parse(&emitted);
+        for (decl, recovered_decl) in ast.decls().iter().zip(recovered.decls()) {
+            assert_eq!(decl.schema_hash(), recovered_decl.schema_hash());
+        }
+        assert_eq!(ast.decls().len(), recovered.decls().len());
+    }
+}
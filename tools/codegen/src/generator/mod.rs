@@ -8,40 +8,367 @@ use crate::ir::{self, ToIntermediate as _};
 mod languages;
 
 pub(crate) use languages::rust::utilities::{field_name, ident_name, ident_new};
-pub use languages::Language;
+pub(crate) use languages::schema::write_schema;
+pub use languages::{GeneratorOptions, Language, Split};
+
+/// A code generator, which could be implemented outside this crate to generate the code for
+/// other languages, and used by [`Compiler::generate_with`](crate::Compiler::generate_with).
+pub trait Generator {
+    /// The extension of the generated file, without the leading dot.
+    fn extension(&self) -> &str;
+
+    fn generate(&self, writer: &mut dyn io::Write, ast: &ast::Ast) -> io::Result<()>;
+}
+
+impl Generator for Language {
+    fn extension(&self) -> &str {
+        Language::extension(*self)
+    }
+
+    fn generate(&self, mut writer: &mut dyn io::Write, ast: &ast::Ast) -> io::Result<()> {
+        Language::generate(*self, &mut writer, ast)
+    }
+}
 
-#[derive(Debug, Clone, Copy)]
 pub(crate) enum Target {
     Language(Language),
     #[cfg(feature = "compiler-plugin")]
     Intermediate(ir::Format),
+    Custom(Box<dyn Generator>),
 }
 
+/// Emits the output of a target for an AST.
 #[derive(Debug)]
-pub(crate) struct Generator {
+pub(crate) struct Emitter {
     ast: ast::Ast,
+    options: GeneratorOptions,
+    #[cfg(feature = "compiler-plugin")]
+    intermediate_format_version: usize,
+    #[cfg(feature = "compiler-plugin")]
+    intermediate_layouts: bool,
 }
 
 impl Target {
-    pub(crate) fn extension(self) -> &'static str {
+    pub(crate) fn extension(&self) -> &str {
         match self {
             Self::Language(lang) => lang.extension(),
             #[cfg(feature = "compiler-plugin")]
             Self::Intermediate(format) => format.extension(),
+            Self::Custom(generator) => generator.extension(),
         }
     }
 }
 
-impl Generator {
+impl Emitter {
     pub(crate) fn new(ast: ast::Ast) -> Self {
-        Self { ast }
+        Self {
+            ast,
+            options: GeneratorOptions::default(),
+            #[cfg(feature = "compiler-plugin")]
+            intermediate_format_version: 1,
+            #[cfg(feature = "compiler-plugin")]
+            intermediate_layouts: false,
+        }
+    }
+
+    pub(crate) fn generator_options(mut self, options: GeneratorOptions) -> Self {
+        self.options = options;
+        self
     }
 
-    pub(crate) fn generate<W: io::Write>(&self, target: Target, writer: &mut W) -> io::Result<()> {
+    /// The version of the intermediate format, the latest version is used if it's newer.
+    #[cfg(feature = "compiler-plugin")]
+    pub(crate) fn intermediate_format_version(mut self, version: usize) -> Self {
+        self.intermediate_format_version = version;
+        self
+    }
+
+    /// Includes the layouts and the dependency order in the intermediate data, which requires
+    /// the latest version of the intermediate format.
+    #[cfg(feature = "compiler-plugin")]
+    pub(crate) fn intermediate_layouts(mut self, enabled: bool) -> Self {
+        self.intermediate_layouts = enabled;
+        self
+    }
+
+    /// Generates the files of the module `name`, see [`Language::generate_files`].
+    pub(crate) fn generate_files(
+        &self,
+        target: &Target,
+        name: &str,
+    ) -> io::Result<Vec<(String, Vec<u8>)>> {
+        if let Target::Language(lang) = target {
+            return lang.generate_files(name, &self.ast, &self.options);
+        }
+        let mut content = Vec::new();
+        self.generate(target, &mut content)?;
+        Ok(vec![(format!("{}.{}", name, target.extension()), content)])
+    }
+
+    pub(crate) fn generate<W: io::Write>(&self, target: &Target, writer: &mut W) -> io::Result<()> {
         match target {
-            Target::Language(lang) => lang.generate(writer, &self.ast),
+            Target::Language(lang) => lang.generate_with_options(writer, &self.ast, &self.options),
+            Target::Custom(generator) => generator.generate(writer, &self.ast),
             #[cfg(feature = "compiler-plugin")]
-            Target::Intermediate(format) => format.generate(writer, &self.ast.to_ir()),
+            Target::Intermediate(ref format) => {
+                let ir = if self.intermediate_layouts {
+                    ir::to_ir_with_layouts(&self.ast)
+                } else if self.intermediate_format_version > 1 {
+                    ir::to_latest_ir(&self.ast)
+                } else {
+                    self.ast.to_ir()
+                };
+                format.generate(writer, &ir)
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io};
+
+    use super::Generator;
+    use crate::{
+        ast::{self, HasName as _},
+        Compiler, GeneratorOptions, Language, Split, VERSION,
+    };
+
+    struct NamesGenerator;
+
+    impl Generator for NamesGenerator {
+        fn extension(&self) -> &str {
+            "txt"
+        }
+
+        fn generate(&self, writer: &mut dyn io::Write, ast: &ast::Ast) -> io::Result<()> {
+            for decl in ast.major_decls() {
+                writeln!(writer, "{}", decl.name())?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_generate_with() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = dir.path().join("types.mol");
+        fs::write(
+            &schema,
+            "array Uint32 [byte; 4];\nvector Uint32Vec <Uint32>;\n",
+        )
+        .unwrap();
+        Compiler::new()
+            .input_schema_file(&schema)
+            .generate_with(Box::new(NamesGenerator))
+            .output_dir(dir.path())
+            .run()
+            .unwrap();
+        let output = fs::read_to_string(dir.path().join("types.txt")).unwrap();
+        assert_eq!(output, "Uint32\nUint32Vec\n");
+    }
+
+    #[test]
+    fn test_generate_umbrella() {
+        let mut output = Vec::new();
+        Language::Rust
+            .generate_umbrella(&mut output, "mod", &["blockchain", "types"])
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "// Generated by Molecule {}\n\npub mod blockchain;\npub mod types;\n",
+                VERSION
+            )
+        );
+
+        let mut output = Vec::new();
+        Language::C
+            .generate_umbrella(&mut output, "schemas", &["types"])
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("#ifndef SCHEMAS_H\n#define SCHEMAS_H\n"));
+        assert!(output.contains("#include \"types.h\"\n"));
+
+        assert!(Language::Schema
+            .generate_umbrella(&mut Vec::new(), "all", &["types"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_generator_options() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("common")).unwrap();
+        fs::write(
+            dir.path().join("common").join("basic.mol"),
+            "array Uint32 [byte; 4];\n",
+        )
+        .unwrap();
+        let schema = dir.path().join("types").join("types.mol");
+        fs::create_dir_all(schema.parent().unwrap()).unwrap();
+        fs::write(
+            &schema,
+            "import ../common/basic;\ntable Pair { molecule: Uint32, }\n",
+        )
+        .unwrap();
+        let ast = crate::Parser::parse(&schema);
+        let mut options = GeneratorOptions::new();
+        options
+            .crate_path("ckb_types::molecule")
+            .module_path("crate::generated::types");
+
+        let mut output = Vec::new();
+        Language::Rust
+            .generate_with_options(&mut output, &ast, &options)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("use ckb_types :: molecule :: prelude :: * ;"));
+        assert!(output.contains("use crate :: generated :: common :: basic :: * ;"));
+        assert!(
+            output.contains("fn new_unchecked (data : ckb_types :: molecule :: bytes :: Bytes)")
+        );
+        assert!(output.contains("pub (crate) molecule : Uint32"));
+        assert!(!output
+            .replace("ckb_types :: molecule ::", "")
+            .contains("molecule ::"));
+
+        let mut output = Vec::new();
+        Language::RustLazyReader
+            .generate_with_options(&mut output, &ast, &options)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("use ckb_types::molecule::lazy_reader::"));
+        assert!(output.contains("use crate :: generated :: common :: basic :: * ;"));
+
+        let mut output = Vec::new();
+        Language::Rust.generate(&mut output, &ast).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("use molecule :: prelude :: * ;"));
+        assert!(output.contains("use super :: super :: common :: basic :: * ;"));
+
+        options.module_path("crate");
+        assert!(Language::Rust
+            .generate_with_options(&mut Vec::new(), &ast, &options)
+            .is_err());
+        assert!(GeneratorOptions::new().set("crate-path", "a::1b").is_err());
+        assert!(GeneratorOptions::new().set("no-such-option", "a").is_err());
+    }
+
+    #[test]
+    fn test_derives_and_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = dir.path().join("types.mol");
+        fs::write(
+            &schema,
+            "array Uint32 [byte; 4];\ntable MolPair { first: Uint32, second: Uint32, }\n",
+        )
+        .unwrap();
+        let ast = crate::Parser::parse(&schema);
+        let mut options = GeneratorOptions::new();
+        options
+            .set("derives", "PartialEq, Eq,Hash")
+            .unwrap()
+            .set("prefix", "Ckb")
+            .unwrap();
+
+        let mut output = Vec::new();
+        Language::Rust
+            .generate_with_options(&mut output, &ast, &options)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(
+            "# [derive (Clone , PartialEq , Eq , Hash)] pub struct Uint32 (molecule :: bytes :: Bytes) ;"
+        ));
+        assert!(output.contains("# [derive (Clone , Copy)] pub struct Uint32Reader"));
+
+        let mut output = Vec::new();
+        Language::C
+            .generate_with_options(&mut output, &ast, &options)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("CkbReader_MolPair_verify"));
+        assert!(output.contains("CkbBuilder_MolPair_build"));
+        assert!(output.contains("CkbDefault_Uint32"));
+        assert!(output.contains("CkbSchemaHash_MolPair"));
+        assert!(output.contains("mol_builder_t"));
+        assert!(output.contains("MOLECULE_API_DECORATOR"));
+        assert!(!output.contains("MolReader_"));
+
+        assert!(GeneratorOptions::new().set("derives", "Debug").is_err());
+        assert!(GeneratorOptions::new()
+            .set("derives", "Partial Eq")
+            .is_err());
+        assert!(GeneratorOptions::new().set("prefix", "1Mol").is_err());
+    }
+
+    #[test]
+    fn test_generate_split() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = dir.path().join("types.mol");
+        fs::write(dir.path().join("common.mol"), "array Uint32 [byte; 4];\n").unwrap();
+        fs::write(
+            &schema,
+            "import common;\narray Uint64 [byte; 8];\n\
+             table Header { number: Uint32, timestamp: Uint64, }\nvector Headers <Header>;\n",
+        )
+        .unwrap();
+        let generate = |options: &GeneratorOptions| {
+            let out_dir = tempfile::tempdir().unwrap();
+            Compiler::new()
+                .input_schema_file(&schema)
+                .generate_code(Language::Rust)
+                .generator_options(options.clone())
+                .output_dir(out_dir.path())
+                .run()
+                .map(|_| out_dir)
+        };
+
+        let mut options = GeneratorOptions::new();
+        options.split(Split::Type);
+        let out_dir = generate(&options).unwrap();
+        let module = out_dir.path().join("types");
+        for name in &["mod.rs", "uint64.rs", "header.rs", "headers.rs"] {
+            assert!(module.join(name).is_file());
+        }
+        assert!(!module.join("features.toml").exists());
+        let root = fs::read_to_string(module.join("mod.rs")).unwrap();
+        assert!(root.contains("\nmod header;\npub use header::*;\n"));
+        let header = fs::read_to_string(module.join("header.rs")).unwrap();
+        assert!(header.contains("\nuse super::*;\n"));
+        assert!(header.contains("pub struct HeaderBuilder"));
+        assert!(!header.contains("pub struct Headers"));
+
+        options.feature_gates(true);
+        let out_dir = generate(&options).unwrap();
+        let module = out_dir.path().join("types");
+        let root = fs::read_to_string(module.join("mod.rs")).unwrap();
+        assert!(root.contains("#[cfg(feature = \"types-headers\")]\nmod headers;\n"));
+        let features = fs::read_to_string(module.join("features.toml")).unwrap();
+        assert!(features.ends_with(
+            "types-uint64 = []\n\
+             types-header = [\"common-uint32\", \"types-uint64\"]\n\
+             types-headers = [\"types-header\"]\n"
+        ));
+
+        options.split(Split::Kind);
+        assert!(generate(&options).is_err());
+        options.feature_gates(false);
+        let out_dir = generate(&options).unwrap();
+        let module = out_dir.path().join("types");
+        let readers = fs::read_to_string(module.join("readers.rs")).unwrap();
+        assert!(readers.contains("pub struct HeaderReader"));
+        assert!(!readers.contains("pub struct HeaderBuilder"));
+        let builders = fs::read_to_string(module.join("builders.rs")).unwrap();
+        assert!(builders.contains("pub struct HeadersBuilder"));
+
+        let ast = crate::Parser::parse(&schema);
+        assert!(Language::Rust
+            .generate_with_options(&mut Vec::new(), &ast, &options)
+            .is_err());
+        let files = Language::Rust
+            .generate_files("types", &ast, &GeneratorOptions::new())
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, "types.rs");
+    }
+}
@@ -2,12 +2,17 @@
 
 use std::{convert::TryFrom, fmt, io, str};
 
+use serde::{Deserialize, Serialize};
+
 use crate::ast;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Format {
     JSON,
     YAML,
+    /// Encoded in molecule, the schema is `docs/ir.mol`.
+    Molecule,
 }
 
 impl fmt::Display for Format {
@@ -15,6 +20,7 @@ impl fmt::Display for Format {
         match *self {
             Self::JSON => write!(f, "JSON"),
             Self::YAML => write!(f, "YAML"),
+            Self::Molecule => write!(f, "Molecule"),
         }
     }
 }
@@ -25,6 +31,7 @@ impl TryFrom<&str> for Format {
         match value.to_uppercase().as_str() {
             "JSON" => Ok(Self::JSON),
             "YAML" => Ok(Self::YAML),
+            "MOLECULE" => Ok(Self::Molecule),
             format => Err(format!("unsupported format: [{}]", format)),
         }
     }
@@ -35,6 +42,7 @@ impl Format {
         match self {
             Self::JSON => "json",
             Self::YAML => "yaml",
+            Self::Molecule => "bin",
         }
     }
 
@@ -54,6 +62,8 @@ impl Format {
                     s
                 })
                 .map(String::into_bytes),
+            Self::Molecule => super::molecule::serialize(ir)
+                .map_err(|err| format!("failed to serialize {}: {}", self, err)),
         }
     }
 
@@ -67,8 +77,11 @@ impl Format {
                         .map_err(|err| format!("failed to deserialize {}: {}", self, err)),
                     Self::YAML => serde_yaml::from_str(s)
                         .map_err(|err| format!("failed to deserialize {}: {}", self, err)),
+                    Self::Molecule => unreachable!(),
                 }
             }
+            Self::Molecule => super::molecule::deserialize(bytes)
+                .map_err(|err| format!("failed to deserialize {}: {}", self, err)),
         }
     }
 
@@ -78,6 +91,106 @@ impl Format {
     }
 
     pub fn recover(self, bytes: &[u8]) -> Result<ast::Ast, String> {
-        self.deserialize(bytes).map(ast::Ast::recover)
+        let ir = self.deserialize(bytes)?;
+        if ir.format_version() > super::Ir::FORMAT_VERSION {
+            return Err(format!(
+                "unsupported intermediate format version: {} (the latest supported is {})",
+                ir.format_version(),
+                super::Ir::FORMAT_VERSION
+            ));
+        }
+        Ok(ast::Ast::recover(ir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write as _, path};
+
+    use super::Format;
+    use crate::{
+        ast::HasName as _,
+        ir::{self, ToIntermediate as _},
+        Parser,
+    };
+
+    #[test]
+    fn test_intermediate_layouts() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(
+            b"table Shape { center: Point, name: Bytes, }
+struct Point { x: Uint32, y: byte, }
+array Uint32 [byte; 4];
+vector Bytes <byte>;
+",
+        )
+        .unwrap();
+        let ast = Parser::parse(&file.path());
+
+        let ir = ir::to_ir_with_layouts(&ast);
+        assert_eq!(ir.format_version(), ir::Ir::FORMAT_VERSION);
+        assert_eq!(ir.dependency_order(), ["Uint32", "Point", "Bytes", "Shape"]);
+        let point = ir.decls().iter().find(|decl| decl.name() == "Point");
+        let layout = point.and_then(ir::TopDecl::layout).unwrap();
+        assert_eq!(layout.total_size(), Some(5));
+        let offsets = layout
+            .fields()
+            .iter()
+            .map(|field| field.offset())
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, [Some(0), Some(4)]);
+
+        let data = Format::JSON.serialize(&ir).unwrap();
+        let recovered = Format::JSON.recover(&data).unwrap();
+        let names = recovered
+            .decls()
+            .iter()
+            .map(|decl| decl.name().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(names.len(), ast.decls().len());
+
+        // the layouts and the fields of version 2 are not included by default
+        let data = String::from_utf8(Format::JSON.serialize(&ast.to_ir()).unwrap()).unwrap();
+        assert!(!data.contains("\"layout\""));
+        assert!(!data.contains("\"dependency_order\""));
+        assert!(!data.contains("\"format_version\""));
+        assert!(!data.contains("\"schema_hash\""));
+        let ir = Format::JSON.deserialize(data.as_bytes()).unwrap();
+        assert_eq!(ir.format_version(), 1);
+
+        let data = String::from_utf8(Format::JSON.serialize(&ir::to_latest_ir(&ast)).unwrap());
+        let data = data.unwrap();
+        assert!(!data.contains("\"layout\""));
+        assert!(data.contains("\"format_version\": 2"));
+        assert!(data.contains("\"schema_hash\""));
+        assert!(Format::JSON.recover(data.as_bytes()).is_ok());
+
+        let data = data.replacen('{', "{\"format_version\": 99,", 1);
+        assert!(Format::JSON.recover(data.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_json_schema_is_up_to_date() {
+        let path = path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../docs/ir.schema.json");
+        let committed = fs::read_to_string(&path).unwrap();
+        assert!(
+            committed == ir::Ir::json_schema(),
+            "the JSON Schema of the intermediate data is out of date, \
+             update it by `moleculec --ir-schema > docs/ir.schema.json`"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the layout of `Point` doesn't match its structure")]
+    fn test_intermediate_layouts_mismatch() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"struct Point { x: byte, y: byte, }\nstruct Pair { a: byte, b: byte, }\n")
+            .unwrap();
+        let ast = Parser::parse(&file.path());
+        let mut ir = ir::to_ir_with_layouts(&ast);
+        let layout = ir.decls()[1].layout().unwrap().clone();
+        ir.decls[0].set_layout(layout);
+        let data = Format::JSON.serialize(&ir).unwrap();
+        let _ = Format::JSON.recover(&data);
     }
 }
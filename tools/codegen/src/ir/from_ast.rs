@@ -1,22 +1,55 @@
-use crate::ast::{self, HasName as _};
+use crate::ast::{self, HasName as _, SchemaHash};
 
 pub(crate) trait ToIntermediate {
     type Ir;
     fn to_ir(&self) -> Self::Ir;
 }
 
+pub(crate) fn schema_hash_hex<T: SchemaHash>(decl: &T) -> String {
+    format!("0x{}", molecule::hex_string(&decl.schema_hash()))
+}
+
 impl ToIntermediate for ast::Ast {
     type Ir = super::Ir;
     fn to_ir(&self) -> Self::Ir {
         Self::Ir {
+            format_version: 1,
             syntax_version: self.syntax_version().to_owned(),
             namespace: self.namespace().to_owned(),
             imports: self.imports().iter().map(ToIntermediate::to_ir).collect(),
             decls: self.decls().iter().map(|decl| decl.to_ir()).collect(),
+            dependency_order: Vec::new(),
         }
     }
 }
 
+/// Converts the AST in the latest version of the intermediate format, with the schema hashes.
+///
+/// [`ToIntermediate::to_ir`] converts the AST in the first version, which could be read by
+/// the plugins which are built with the old versions of this crate.
+pub(crate) fn to_latest_ir(ast: &ast::Ast) -> super::Ir {
+    let mut ir = ast.to_ir();
+    ir.format_version = super::Ir::FORMAT_VERSION;
+    for (decl_ir, decl) in ir.decls.iter_mut().zip(ast.decls()) {
+        decl_ir.set_schema_hash(schema_hash_hex(decl.as_ref()));
+    }
+    ir
+}
+
+/// Converts the AST in the latest version, with the computed layouts and the dependency order.
+pub(crate) fn to_ir_with_layouts(ast: &ast::Ast) -> super::Ir {
+    let mut ir = to_latest_ir(ast);
+    for (decl_ir, decl) in ir.decls.iter_mut().zip(ast.decls()) {
+        decl_ir.set_layout(decl.layout());
+    }
+    ir.dependency_order = ast
+        .dependency_order()
+        .iter()
+        .map(|decl| decl.name().to_owned())
+        .collect();
+    ir
+}
+
 impl ToIntermediate for ast::ImportStmt {
     type Ir = super::ImportStmt;
     fn to_ir(&self) -> Self::Ir {
@@ -51,6 +84,8 @@ impl ToIntermediate for ast::Option_ {
             name: self.name().to_owned(),
             item: self.item().to_ir(),
             imported_depth: self.imported_depth(),
+            schema_hash: None,
+            layout: None,
         }
     }
 }
@@ -61,7 +96,10 @@ impl ToIntermediate for ast::Union {
         Self::Ir {
             name: self.name().to_owned(),
             items: self.items().iter().map(ToIntermediate::to_ir).collect(),
+            reserved_ids: self.reserved_ids().to_owned(),
             imported_depth: self.imported_depth(),
+            schema_hash: None,
+            layout: None,
         }
     }
 }
@@ -74,6 +112,8 @@ impl ToIntermediate for ast::Array {
             item: self.item().to_ir(),
             item_count: self.item_count(),
             imported_depth: self.imported_depth(),
+            schema_hash: None,
+            layout: None,
         }
     }
 }
@@ -85,6 +125,8 @@ impl ToIntermediate for ast::Struct {
             name: self.name().to_owned(),
             fields: self.fields().iter().map(ToIntermediate::to_ir).collect(),
             imported_depth: self.imported_depth(),
+            schema_hash: None,
+            layout: None,
         }
     }
 }
@@ -96,6 +138,10 @@ impl ToIntermediate for ast::FixVec {
             name: self.name().to_owned(),
             item: self.item().to_ir(),
             imported_depth: self.imported_depth(),
+            schema_hash: None,
+            layout: None,
+            collection: self.collection(),
+            utf8: self.utf8(),
         }
     }
 }
@@ -107,6 +153,9 @@ impl ToIntermediate for ast::DynVec {
             name: self.name().to_owned(),
             item: self.item().to_ir(),
             imported_depth: self.imported_depth(),
+            schema_hash: None,
+            layout: None,
+            collection: self.collection(),
         }
     }
 }
@@ -118,6 +167,8 @@ impl ToIntermediate for ast::Table {
             name: self.name().to_owned(),
             fields: self.fields().iter().map(ToIntermediate::to_ir).collect(),
             imported_depth: self.imported_depth(),
+            schema_hash: None,
+            layout: None,
         }
     }
 }
@@ -134,6 +185,7 @@ impl ToIntermediate for ast::UnionItemDecl {
     type Ir = super::UnionItemDecl;
     fn to_ir(&self) -> Self::Ir {
         Self::Ir {
+            name: self.explicit_name().cloned(),
             typ: self.typ().name().to_owned(),
             id: self.id().to_owned(),
         }
@@ -146,6 +198,10 @@ impl ToIntermediate for ast::FieldDecl {
         Self::Ir {
             name: self.name().to_owned(),
             typ: self.typ().name().to_owned(),
+            reserved: self.reserved(),
+            default: self
+                .default()
+                .map(|value| format!("0x{}", molecule::hex_string(value))),
         }
     }
 }
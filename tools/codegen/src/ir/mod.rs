@@ -1,28 +1,62 @@
 mod format;
 mod from_ast;
+mod molecule;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer, Serialize};
 
 use property::Property;
 
 pub use format::Format;
-pub(crate) use from_ast::ToIntermediate;
+pub(crate) use from_ast::{schema_hash_hex, to_ir_with_layouts, to_latest_ir, ToIntermediate};
 
-pub use crate::ast::SyntaxVersion;
+pub use crate::ast::{Collection, Feature, Layout, SyntaxVersion};
 
 /// Intermediate file.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Ir {
+    /// The version of the intermediate format, the files without it are in version 1.
+    #[serde(
+        default = "first_format_version",
+        skip_serializing_if = "is_first_format_version"
+    )]
+    pub format_version: usize,
     #[serde(default)]
     pub syntax_version: SyntaxVersion,
     pub namespace: String,
     pub imports: Vec<ImportStmt>,
     #[serde(rename = "declarations")]
     pub decls: Vec<TopDecl>,
+    /// The names of all declarations, each declaration is after all its dependencies.
+    ///
+    /// It's only included when the layouts are included.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependency_order: Vec<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize)]
+impl Ir {
+    /// The version of the intermediate format which is generated.
+    ///
+    /// - Version 1: the declarations and the imports.
+    /// - Version 2: the format version, the schema hashes, the optional layouts and the
+    ///   dependency order.
+    ///
+    /// The fields of version 2 are rejected by the plugins which are built with the old
+    /// versions of this crate, so version 2 is only generated for the plugins which declare it
+    /// in their [capabilities](crate::plugin::Capabilities), or when the layouts are included.
+    pub const FORMAT_VERSION: usize = 2;
+
+    /// The JSON Schema of the intermediate data, which is also valid for the YAML format.
+    pub fn json_schema() -> String {
+        let schema = schemars::schema_for!(Ir);
+        let mut json = serde_json::to_string_pretty(&schema).unwrap();
+        json.push('\n');
+        json
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct ImportStmt {
     pub name: String,
@@ -30,7 +64,7 @@ pub struct ImportStmt {
     pub path_supers: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields, tag = "type", rename_all = "lowercase")]
 pub enum TopDecl {
     #[serde(rename = "option")]
@@ -43,27 +77,40 @@ pub enum TopDecl {
     Table(Table),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Option_ {
     pub name: String,
     pub item: ItemDecl,
     #[serde(default = "zero", skip_serializing_if = "is_zero")]
     pub imported_depth: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_hash: Option<String>,
+    /// The computed layout, which is only included when it's required.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Union {
     pub name: String,
 
     #[serde(deserialize_with = "deserialize_union_items")]
+    #[schemars(with = "UnionItemsForCompatibility")]
     pub items: Vec<UnionItemDecl>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reserved_ids: Vec<usize>,
     #[serde(default = "zero", skip_serializing_if = "is_zero")]
     pub imported_depth: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_hash: Option<String>,
+    /// The computed layout, which is only included when it's required.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Array {
     pub name: String,
@@ -71,58 +118,93 @@ pub struct Array {
     pub item_count: usize,
     #[serde(default = "zero", skip_serializing_if = "is_zero")]
     pub imported_depth: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_hash: Option<String>,
+    /// The computed layout, which is only included when it's required.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Struct {
     pub name: String,
     pub fields: Vec<FieldDecl>,
     #[serde(default = "zero", skip_serializing_if = "is_zero")]
     pub imported_depth: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_hash: Option<String>,
+    /// The computed layout, which is only included when it's required.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct FixVec {
     pub name: String,
     pub item: ItemDecl,
     #[serde(default = "zero", skip_serializing_if = "is_zero")]
     pub imported_depth: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_hash: Option<String>,
+    /// The computed layout, which is only included when it's required.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection: Option<Collection>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub utf8: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct DynVec {
     pub name: String,
     pub item: ItemDecl,
     #[serde(default = "zero", skip_serializing_if = "is_zero")]
     pub imported_depth: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_hash: Option<String>,
+    /// The computed layout, which is only included when it's required.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection: Option<Collection>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Table {
     pub name: String,
     pub fields: Vec<FieldDecl>,
     #[serde(default = "zero", skip_serializing_if = "is_zero")]
     pub imported_depth: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_hash: Option<String>,
+    /// The computed layout, which is only included when it's required.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields, transparent)]
 pub struct ItemDecl {
     pub typ: String,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct UnionItemDecl {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     pub typ: String,
     pub id: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The items of a union, the items in the legacy format are only the types, and their ids are
+/// their indexes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum UnionItemsForCompatibility {
     ItemsForCompatibility(Vec<ItemDecl>),
@@ -139,6 +221,7 @@ where
             .iter()
             .enumerate()
             .map(|(id, item)| UnionItemDecl {
+                name: None,
                 typ: item.typ.clone(),
                 id,
             })
@@ -147,12 +230,25 @@ where
     })
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Property, Deserialize, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct FieldDecl {
     pub name: String,
     #[serde(rename = "type")]
     pub typ: String,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub reserved: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+const fn first_format_version() -> usize {
+    1
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_first_format_version(value: &usize) -> bool {
+    *value == first_format_version()
 }
 
 const fn zero() -> usize {
@@ -164,6 +260,11 @@ const fn is_zero(value: &usize) -> bool {
     *value == 0
 }
 
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_false(value: &bool) -> bool {
+    !*value
+}
+
 impl TopDecl {
     pub(crate) fn name(&self) -> &str {
         match self {
@@ -176,4 +277,54 @@ impl TopDecl {
             Self::Table(inner) => inner.name(),
         }
     }
+
+    pub(crate) fn schema_hash(&self) -> Option<&String> {
+        match self {
+            Self::Option_(inner) => inner.schema_hash(),
+            Self::Union(inner) => inner.schema_hash(),
+            Self::Array(inner) => inner.schema_hash(),
+            Self::Struct(inner) => inner.schema_hash(),
+            Self::FixVec(inner) => inner.schema_hash(),
+            Self::DynVec(inner) => inner.schema_hash(),
+            Self::Table(inner) => inner.schema_hash(),
+        }
+    }
+
+    pub(crate) fn layout(&self) -> Option<&Layout> {
+        match self {
+            Self::Option_(inner) => inner.layout(),
+            Self::Union(inner) => inner.layout(),
+            Self::Array(inner) => inner.layout(),
+            Self::Struct(inner) => inner.layout(),
+            Self::FixVec(inner) => inner.layout(),
+            Self::DynVec(inner) => inner.layout(),
+            Self::Table(inner) => inner.layout(),
+        }
+    }
+
+    pub(crate) fn set_schema_hash(&mut self, schema_hash: String) {
+        let field = match self {
+            Self::Option_(inner) => &mut inner.schema_hash,
+            Self::Union(inner) => &mut inner.schema_hash,
+            Self::Array(inner) => &mut inner.schema_hash,
+            Self::Struct(inner) => &mut inner.schema_hash,
+            Self::FixVec(inner) => &mut inner.schema_hash,
+            Self::DynVec(inner) => &mut inner.schema_hash,
+            Self::Table(inner) => &mut inner.schema_hash,
+        };
+        field.replace(schema_hash);
+    }
+
+    pub(crate) fn set_layout(&mut self, layout: Layout) {
+        let field = match self {
+            Self::Option_(inner) => &mut inner.layout,
+            Self::Union(inner) => &mut inner.layout,
+            Self::Array(inner) => &mut inner.layout,
+            Self::Struct(inner) => &mut inner.layout,
+            Self::FixVec(inner) => &mut inner.layout,
+            Self::DynVec(inner) => &mut inner.layout,
+            Self::Table(inner) => &mut inner.layout,
+        };
+        field.replace(layout);
+    }
 }
@@ -0,0 +1,605 @@
+//! The intermediate data which is encoded in molecule.
+//!
+//! The schema is `docs/ir.mol`, and the code in `generated.rs` is generated from it.
+
+use std::convert::TryFrom;
+
+use molecule::prelude::*;
+
+use crate::ast::{Collection, Encoding, FieldLayout, HeaderLayout, ItemLayout, UnionItemLayout};
+
+mod packed {
+    #![allow(clippy::all, dead_code)]
+    include!("generated.rs");
+}
+
+/// The encodings in the order of their bytes.
+const ENCODINGS: [Encoding; 8] = [
+    Encoding::Byte,
+    Encoding::Option,
+    Encoding::Union,
+    Encoding::Array,
+    Encoding::Struct,
+    Encoding::FixVec,
+    Encoding::DynVec,
+    Encoding::Table,
+];
+
+pub(crate) fn serialize(ir: &super::Ir) -> Result<Vec<u8>, String> {
+    let imports = ir
+        .imports()
+        .iter()
+        .map(|import| {
+            Ok(packed::ImportStmt::new_builder()
+                .name(pack_str(import.name()))
+                .paths(pack_strs(import.paths()))
+                .path_supers(pack_number(import.path_supers())?)
+                .build())
+        })
+        .collect::<Result<_, String>>()?;
+    let decls = ir.decls().iter().map(pack_decl).collect::<Result<_, _>>()?;
+    let ir = packed::Ir::new_builder()
+        .format_version(pack_number(ir.format_version())?)
+        .syntax_version(pack_number(ir.syntax_version().version())?)
+        .namespace(pack_str(ir.namespace()))
+        .imports(packed::ImportStmtVec::new_builder().set(imports).build())
+        .declarations(packed::TopDeclVec::new_builder().set(decls).build())
+        .dependency_order(pack_strs(ir.dependency_order()))
+        .build();
+    Ok(ir.as_slice().to_owned())
+}
+
+pub(crate) fn deserialize(bytes: &[u8]) -> Result<super::Ir, String> {
+    let ir = packed::IrReader::from_compatible_slice(bytes)
+        .map_err(|err| format!("failed to verify the data: {}", err))?;
+    let imports = ir
+        .imports()
+        .iter()
+        .map(|import| {
+            Ok(super::ImportStmt {
+                name: unpack_str(import.name())?,
+                paths: unpack_strs(import.paths())?,
+                path_supers: unpack_number(import.path_supers()),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    let syntax_version = unpack_number(ir.syntax_version());
+    if syntax_version == 0 || syntax_version > super::SyntaxVersion::LATEST {
+        return Err(format!("unknown syntax version {}", syntax_version));
+    }
+    let decls = ir
+        .declarations()
+        .iter()
+        .map(unpack_decl)
+        .collect::<Result<_, _>>()?;
+    Ok(super::Ir {
+        format_version: unpack_number(ir.format_version()),
+        syntax_version: super::SyntaxVersion::new(syntax_version),
+        namespace: unpack_str(ir.namespace())?,
+        imports,
+        decls,
+        dependency_order: unpack_strs(ir.dependency_order())?,
+    })
+}
+
+fn pack_decl(decl: &super::TopDecl) -> Result<packed::TopDecl, String> {
+    let decl: packed::TopDeclUnion = match decl {
+        super::TopDecl::Option_(inner) => packed::OptionDecl::new_builder()
+            .name(pack_str(inner.name()))
+            .item(pack_str(inner.item().typ()))
+            .imported_depth(pack_number(inner.imported_depth())?)
+            .schema_hash(pack_schema_hash(inner.schema_hash())?)
+            .layout(pack_layout(inner.layout())?)
+            .build()
+            .into(),
+        super::TopDecl::Union(inner) => {
+            let items = inner
+                .items()
+                .iter()
+                .map(|item| {
+                    Ok(packed::UnionItemDecl::new_builder()
+                        .name(pack_opt_str(item.name()))
+                        .typ(pack_str(item.typ()))
+                        .id(pack_number(item.id())?)
+                        .build())
+                })
+                .collect::<Result<_, String>>()?;
+            let reserved_ids = inner
+                .reserved_ids()
+                .iter()
+                .map(|id| pack_number(*id))
+                .collect::<Result<_, _>>()?;
+            packed::UnionDecl::new_builder()
+                .name(pack_str(inner.name()))
+                .items(packed::UnionItemDeclVec::new_builder().set(items).build())
+                .reserved_ids(packed::Uint32Vec::new_builder().set(reserved_ids).build())
+                .imported_depth(pack_number(inner.imported_depth())?)
+                .schema_hash(pack_schema_hash(inner.schema_hash())?)
+                .layout(pack_layout(inner.layout())?)
+                .build()
+                .into()
+        }
+        super::TopDecl::Array(inner) => packed::ArrayDecl::new_builder()
+            .name(pack_str(inner.name()))
+            .item(pack_str(inner.item().typ()))
+            .item_count(pack_number(inner.item_count())?)
+            .imported_depth(pack_number(inner.imported_depth())?)
+            .schema_hash(pack_schema_hash(inner.schema_hash())?)
+            .layout(pack_layout(inner.layout())?)
+            .build()
+            .into(),
+        super::TopDecl::Struct(inner) => packed::StructDecl::new_builder()
+            .name(pack_str(inner.name()))
+            .fields(pack_fields(inner.fields())?)
+            .imported_depth(pack_number(inner.imported_depth())?)
+            .schema_hash(pack_schema_hash(inner.schema_hash())?)
+            .layout(pack_layout(inner.layout())?)
+            .build()
+            .into(),
+        super::TopDecl::FixVec(inner) => packed::FixVecDecl::new_builder()
+            .name(pack_str(inner.name()))
+            .item(pack_str(inner.item().typ()))
+            .imported_depth(pack_number(inner.imported_depth())?)
+            .schema_hash(pack_schema_hash(inner.schema_hash())?)
+            .layout(pack_layout(inner.layout())?)
+            .collection(pack_collection(inner.collection().copied()))
+            .utf8(Byte::new(u8::from(inner.utf8())))
+            .build()
+            .into(),
+        super::TopDecl::DynVec(inner) => packed::DynVecDecl::new_builder()
+            .name(pack_str(inner.name()))
+            .item(pack_str(inner.item().typ()))
+            .imported_depth(pack_number(inner.imported_depth())?)
+            .schema_hash(pack_schema_hash(inner.schema_hash())?)
+            .layout(pack_layout(inner.layout())?)
+            .collection(pack_collection(inner.collection().copied()))
+            .build()
+            .into(),
+        super::TopDecl::Table(inner) => packed::TableDecl::new_builder()
+            .name(pack_str(inner.name()))
+            .fields(pack_fields(inner.fields())?)
+            .imported_depth(pack_number(inner.imported_depth())?)
+            .schema_hash(pack_schema_hash(inner.schema_hash())?)
+            .layout(pack_layout(inner.layout())?)
+            .build()
+            .into(),
+    };
+    Ok(packed::TopDecl::new_builder().set(decl).build())
+}
+
+fn unpack_decl(decl: packed::TopDeclReader) -> Result<super::TopDecl, String> {
+    let decl = match decl.to_enum() {
+        packed::TopDeclUnionReader::OptionDecl(inner) => super::TopDecl::Option_(super::Option_ {
+            name: unpack_str(inner.name())?,
+            item: super::ItemDecl {
+                typ: unpack_str(inner.item())?,
+            },
+            imported_depth: unpack_number(inner.imported_depth()),
+            schema_hash: unpack_schema_hash(inner.schema_hash()),
+            layout: unpack_layout(inner.layout())?,
+        }),
+        packed::TopDeclUnionReader::UnionDecl(inner) => super::TopDecl::Union(super::Union {
+            name: unpack_str(inner.name())?,
+            items: inner
+                .items()
+                .iter()
+                .map(|item| {
+                    Ok(super::UnionItemDecl {
+                        name: unpack_opt_str(item.name())?,
+                        typ: unpack_str(item.typ())?,
+                        id: unpack_number(item.id()),
+                    })
+                })
+                .collect::<Result<_, String>>()?,
+            reserved_ids: inner.reserved_ids().iter().map(unpack_number).collect(),
+            imported_depth: unpack_number(inner.imported_depth()),
+            schema_hash: unpack_schema_hash(inner.schema_hash()),
+            layout: unpack_layout(inner.layout())?,
+        }),
+        packed::TopDeclUnionReader::ArrayDecl(inner) => super::TopDecl::Array(super::Array {
+            name: unpack_str(inner.name())?,
+            item: super::ItemDecl {
+                typ: unpack_str(inner.item())?,
+            },
+            item_count: unpack_number(inner.item_count()),
+            imported_depth: unpack_number(inner.imported_depth()),
+            schema_hash: unpack_schema_hash(inner.schema_hash()),
+            layout: unpack_layout(inner.layout())?,
+        }),
+        packed::TopDeclUnionReader::StructDecl(inner) => super::TopDecl::Struct(super::Struct {
+            name: unpack_str(inner.name())?,
+            fields: unpack_fields(inner.fields())?,
+            imported_depth: unpack_number(inner.imported_depth()),
+            schema_hash: unpack_schema_hash(inner.schema_hash()),
+            layout: unpack_layout(inner.layout())?,
+        }),
+        packed::TopDeclUnionReader::FixVecDecl(inner) => super::TopDecl::FixVec(super::FixVec {
+            name: unpack_str(inner.name())?,
+            item: super::ItemDecl {
+                typ: unpack_str(inner.item())?,
+            },
+            imported_depth: unpack_number(inner.imported_depth()),
+            schema_hash: unpack_schema_hash(inner.schema_hash()),
+            layout: unpack_layout(inner.layout())?,
+            collection: unpack_collection(inner.collection())?,
+            utf8: unpack_bool(inner.utf8())?,
+        }),
+        packed::TopDeclUnionReader::DynVecDecl(inner) => super::TopDecl::DynVec(super::DynVec {
+            name: unpack_str(inner.name())?,
+            item: super::ItemDecl {
+                typ: unpack_str(inner.item())?,
+            },
+            imported_depth: unpack_number(inner.imported_depth()),
+            schema_hash: unpack_schema_hash(inner.schema_hash()),
+            layout: unpack_layout(inner.layout())?,
+            collection: unpack_collection(inner.collection())?,
+        }),
+        packed::TopDeclUnionReader::TableDecl(inner) => super::TopDecl::Table(super::Table {
+            name: unpack_str(inner.name())?,
+            fields: unpack_fields(inner.fields())?,
+            imported_depth: unpack_number(inner.imported_depth()),
+            schema_hash: unpack_schema_hash(inner.schema_hash()),
+            layout: unpack_layout(inner.layout())?,
+        }),
+    };
+    Ok(decl)
+}
+
+fn pack_fields(fields: &[super::FieldDecl]) -> Result<packed::FieldDeclVec, String> {
+    let fields = fields
+        .iter()
+        .map(|field| {
+            let default = field.default().map(|value| decode_hex(value)).transpose()?;
+            let default = packed::BytesOpt::new_builder()
+                .set(default.as_deref().map(pack_bytes))
+                .build();
+            Ok(packed::FieldDecl::new_builder()
+                .name(pack_str(field.name()))
+                .typ(pack_str(field.typ()))
+                .reserved(Byte::new(u8::from(field.reserved())))
+                .default(default)
+                .build())
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(packed::FieldDeclVec::new_builder().set(fields).build())
+}
+
+fn unpack_fields(fields: packed::FieldDeclVecReader) -> Result<Vec<super::FieldDecl>, String> {
+    fields
+        .iter()
+        .map(|field| {
+            Ok(super::FieldDecl {
+                name: unpack_str(field.name())?,
+                typ: unpack_str(field.typ())?,
+                reserved: unpack_bool(field.reserved())?,
+                default: field
+                    .default()
+                    .to_opt()
+                    .map(|value| format!("0x{}", molecule::hex_string(value.raw_data()))),
+            })
+        })
+        .collect()
+}
+
+fn pack_layout(layout: Option<&super::Layout>) -> Result<packed::LayoutOpt, String> {
+    let layout = layout
+        .map(|layout| {
+            let encoding = ENCODINGS
+                .iter()
+                .position(|encoding| *encoding == layout.encoding())
+                .unwrap();
+            let header = layout
+                .header()
+                .map(|header| {
+                    Ok::<_, String>(
+                        packed::HeaderLayout::new_builder()
+                            .size(pack_number(header.size())?)
+                            .size_per_item(pack_number(header.size_per_item())?)
+                            .build(),
+                    )
+                })
+                .transpose()?;
+            let item = layout
+                .item()
+                .map(|item| {
+                    Ok::<_, String>(
+                        packed::ItemLayout::new_builder()
+                            .typ(pack_str(item.typ()))
+                            .size(pack_opt_number(item.size())?)
+                            .count(pack_opt_number(item.count())?)
+                            .build(),
+                    )
+                })
+                .transpose()?;
+            let fields = layout
+                .fields()
+                .iter()
+                .map(|field| {
+                    Ok(packed::FieldLayout::new_builder()
+                        .name(pack_str(field.name()))
+                        .typ(pack_str(field.typ()))
+                        .offset(pack_opt_number(field.offset())?)
+                        .size(pack_opt_number(field.size())?)
+                        .build())
+                })
+                .collect::<Result<_, String>>()?;
+            let items = layout
+                .items()
+                .iter()
+                .map(|item| {
+                    Ok(packed::UnionItemLayout::new_builder()
+                        .id(pack_number(item.id())?)
+                        .name(pack_str(item.name()))
+                        .typ(pack_str(item.typ()))
+                        .size(pack_opt_number(item.size())?)
+                        .build())
+                })
+                .collect::<Result<_, String>>()?;
+            Ok::<_, String>(
+                packed::Layout::new_builder()
+                    .name(pack_str(layout.name()))
+                    .encoding(Byte::new(encoding as u8))
+                    .fixed_size(pack_opt_number(layout.total_size())?)
+                    .header(packed::HeaderLayoutOpt::new_builder().set(header).build())
+                    .item(packed::ItemLayoutOpt::new_builder().set(item).build())
+                    .fields(packed::FieldLayoutVec::new_builder().set(fields).build())
+                    .items(packed::UnionItemLayoutVec::new_builder().set(items).build())
+                    .build(),
+            )
+        })
+        .transpose()?;
+    Ok(packed::LayoutOpt::new_builder().set(layout).build())
+}
+
+fn unpack_layout(layout: packed::LayoutOptReader) -> Result<Option<super::Layout>, String> {
+    let layout = if let Some(layout) = layout.to_opt() {
+        layout
+    } else {
+        return Ok(None);
+    };
+    let encoding = ENCODINGS
+        .get(usize::from(u8::from(layout.encoding())))
+        .copied()
+        .ok_or_else(|| format!("unknown encoding: {}", layout.encoding()))?;
+    let item = layout
+        .item()
+        .to_opt()
+        .map(|item| {
+            Ok::<_, String>(ItemLayout {
+                typ: unpack_str(item.typ())?,
+                size: unpack_opt_number(item.size()),
+                count: unpack_opt_number(item.count()),
+            })
+        })
+        .transpose()?;
+    let fields = layout
+        .fields()
+        .iter()
+        .map(|field| {
+            Ok(FieldLayout {
+                name: unpack_str(field.name())?,
+                typ: unpack_str(field.typ())?,
+                offset: unpack_opt_number(field.offset()),
+                size: unpack_opt_number(field.size()),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    let items = layout
+        .items()
+        .iter()
+        .map(|item| {
+            Ok(UnionItemLayout {
+                id: unpack_number(item.id()),
+                name: unpack_str(item.name())?,
+                typ: unpack_str(item.typ())?,
+                size: unpack_opt_number(item.size()),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(Some(super::Layout {
+        name: unpack_str(layout.name())?,
+        encoding,
+        total_size: unpack_opt_number(layout.fixed_size()),
+        header: layout.header().to_opt().map(|header| HeaderLayout {
+            size: unpack_number(header.size()),
+            size_per_item: unpack_number(header.size_per_item()),
+        }),
+        item,
+        fields,
+        items,
+    }))
+}
+
+fn pack_collection(collection: Option<Collection>) -> Byte {
+    match collection {
+        None => Byte::new(0),
+        Some(Collection::Set) => Byte::new(1),
+        Some(Collection::Map) => Byte::new(2),
+    }
+}
+
+fn unpack_collection(collection: ByteReader) -> Result<Option<Collection>, String> {
+    match u8::from(collection) {
+        0 => Ok(None),
+        1 => Ok(Some(Collection::Set)),
+        2 => Ok(Some(Collection::Map)),
+        value => Err(format!("unknown collection: {}", value)),
+    }
+}
+
+fn unpack_bool(value: ByteReader) -> Result<bool, String> {
+    match u8::from(value) {
+        0 => Ok(false),
+        1 => Ok(true),
+        value => Err(format!("invalid boolean: {}", value)),
+    }
+}
+
+fn pack_schema_hash(schema_hash: Option<&String>) -> Result<packed::HashOpt, String> {
+    let schema_hash = schema_hash
+        .map(|hash| {
+            let hash = decode_hex(hash)?;
+            <[u8; 32]>::try_from(hash.as_slice())
+                .map(|hash| packed::Hash::new_builder().set(hash.map(Byte::new)).build())
+                .map_err(|_| format!("invalid schema hash: {}", molecule::hex_string(&hash)))
+        })
+        .transpose()?;
+    Ok(packed::HashOpt::new_builder().set(schema_hash).build())
+}
+
+fn unpack_schema_hash(schema_hash: packed::HashOptReader) -> Option<String> {
+    schema_hash
+        .to_opt()
+        .map(|hash| format!("0x{}", molecule::hex_string(hash.raw_data())))
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>, String> {
+    let digits = value
+        .strip_prefix("0x")
+        .filter(|digits| digits.len() % 2 == 0 && digits.is_ascii())
+        .ok_or_else(|| format!("invalid hex string: {}", value))?;
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("invalid hex string: {}", value))
+        })
+        .collect()
+}
+
+fn pack_number(value: usize) -> Result<packed::Uint32, String> {
+    let bytes = u32::try_from(value)
+        .map_err(|_| format!("the number is too large: {}", value))?
+        .to_le_bytes();
+    Ok(packed::Uint32::new_builder()
+        .set(bytes.map(Byte::new))
+        .build())
+}
+
+fn unpack_number(value: packed::Uint32Reader) -> usize {
+    molecule::unpack_number(value.raw_data()) as usize
+}
+
+fn pack_opt_number(value: Option<usize>) -> Result<packed::Uint32Opt, String> {
+    let value = value.map(pack_number).transpose()?;
+    Ok(packed::Uint32Opt::new_builder().set(value).build())
+}
+
+fn unpack_opt_number(value: packed::Uint32OptReader) -> Option<usize> {
+    value.to_opt().map(unpack_number)
+}
+
+fn pack_bytes(value: &[u8]) -> packed::Bytes {
+    packed::Bytes::new_builder()
+        .set(value.iter().copied().map(Byte::new).collect())
+        .build()
+}
+
+fn pack_str(value: &str) -> packed::Bytes {
+    pack_bytes(value.as_bytes())
+}
+
+fn unpack_str(value: packed::BytesReader) -> Result<String, String> {
+    String::from_utf8(value.raw_data().to_owned())
+        .map_err(|err| format!("invalid UTF-8 string: {}", err))
+}
+
+fn pack_opt_str(value: Option<&String>) -> packed::BytesOpt {
+    packed::BytesOpt::new_builder()
+        .set(value.map(|value| pack_str(value)))
+        .build()
+}
+
+fn unpack_opt_str(value: packed::BytesOptReader) -> Result<Option<String>, String> {
+    value.to_opt().map(unpack_str).transpose()
+}
+
+fn pack_strs(values: &[String]) -> packed::BytesVec {
+    packed::BytesVec::new_builder()
+        .set(values.iter().map(|value| pack_str(value)).collect())
+        .build()
+}
+
+fn unpack_strs(values: packed::BytesVecReader) -> Result<Vec<String>, String> {
+    values.iter().map(unpack_str).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write as _, path, process};
+
+    use crate::{
+        ir::{self, ToIntermediate as _},
+        Language, Parser,
+    };
+
+    #[test]
+    fn test_generated_code_is_up_to_date() {
+        let dir = path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let ast = Parser::parse(&dir.join("../../docs/ir.mol"));
+        let mut rustfmt = process::Command::new("rustfmt")
+            .args(["--edition", "2018"])
+            .stdin(process::Stdio::piped())
+            .stdout(process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        Language::Rust
+            .generate(rustfmt.stdin.as_mut().unwrap(), &ast)
+            .unwrap();
+        drop(rustfmt.stdin.take());
+        let output = rustfmt.wait_with_output().unwrap();
+        assert!(output.status.success());
+        let committed = fs::read(dir.join("src/ir/molecule/generated.rs")).unwrap();
+        assert!(
+            committed == output.stdout,
+            "the generated code of the intermediate data is out of date, \
+             update it by `moleculec --language rust --schema-file docs/ir.mol \
+             | rustfmt --edition 2018 > tools/codegen/src/ir/molecule/generated.rs`"
+        );
+    }
+
+    #[test]
+    fn test_molecule_format() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(
+            b"syntax = 2;
+array Uint32 [byte; 4];
+struct Point { x: Uint32 = 7, y: byte, }
+table Shape { _reserved: Points, center: Point, }
+vector Points <Point>;
+union Either { Point, Named: Shape = 3, reserved 2; }
+string Name;
+map Names <Uint32, Name>;
+",
+        )
+        .unwrap();
+        let ast = Parser::parse(&file.path());
+        let format = ir::Format::Molecule;
+        for ir in [
+            ir::to_ir_with_layouts(&ast),
+            ir::to_latest_ir(&ast),
+            ast.to_ir(),
+        ] {
+            let data = format.serialize(&ir).unwrap();
+            assert_eq!(format.deserialize(&data).unwrap(), ir);
+            let recovered = format.recover(&data).unwrap();
+            assert_eq!(recovered.to_ir(), ast.to_ir());
+        }
+
+        assert!(format.deserialize(b"invalid").is_err());
+    }
+
+    #[test]
+    fn test_molecule_format_number_is_too_large() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"array Uint32 [byte; 4];\nunion Either { Uint32: 4294967296, }\n")
+            .unwrap();
+        let ast = Parser::parse(&file.path());
+        let err = ir::Format::Molecule.serialize(&ast.to_ir()).unwrap_err();
+        assert!(
+            err.contains("the number is too large: 4294967296"),
+            "{}",
+            err
+        );
+    }
+}
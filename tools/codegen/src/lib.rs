@@ -1,20 +1,36 @@
 #![recursion_limit = "256"]
 
 pub mod ast;
+pub(crate) mod bundler;
 pub(crate) mod compiler;
+pub(crate) mod formatter;
 pub(crate) mod generator;
+pub mod linter;
+pub mod outline;
 pub(crate) mod parser;
+#[cfg(feature = "project")]
+pub mod project;
+#[cfg(feature = "templates")]
+pub(crate) mod template;
 pub(crate) mod utils;
 
 #[cfg(feature = "compiler-plugin")]
 pub mod ir;
+#[cfg(feature = "compiler-plugin")]
+pub mod plugin;
 
+pub use bundler::Bundler;
 pub use compiler::Compiler;
-pub use generator::Language;
-pub use parser::Parser;
+pub use formatter::Formatter;
+pub use generator::{Generator, GeneratorOptions, Language, Split};
+pub use linter::Linter;
+pub use outline::Outline;
+pub use parser::{ParseError, Parser, Span};
 
 #[cfg(feature = "compiler-plugin")]
 pub use ir::Format as IntermediateFormat;
+#[cfg(feature = "templates")]
+pub use template::TemplateGenerator;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const C_API_VERSION_MIN: &str = "0.7.0";
@@ -0,0 +1,405 @@
+use std::{collections::HashMap, collections::HashSet, convert::TryFrom, fmt, rc::Rc};
+
+use property::Property;
+
+use crate::ast::{self, HasName as _};
+
+/// The rules which are checked by the linter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintRule {
+    /// Type names and union variant names should be `UpperCamelCase`, and field names should
+    /// be `snake_case`.
+    Naming,
+    /// An imported declaration is not used by the declarations in the schema file.
+    UnusedDeclaration,
+    /// No declaration in the imported file is used by the declarations in the schema file.
+    UnusedImport,
+    /// A struct is so large that it should probably be a table.
+    LargeStruct,
+    /// A table has no fields.
+    EmptyTable,
+    /// The item ids of a union, with the reserved ids, are not `0..n`.
+    NonContiguousUnionIds,
+    /// An option of an option.
+    NestedOption,
+}
+
+/// The level of a lint rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// A problem which is found by the linter.
+#[derive(Debug, Clone, Property)]
+#[property(get(public))]
+pub struct Lint {
+    #[property(get(type = "copy"))]
+    rule: LintRule,
+    #[property(get(type = "copy"))]
+    level: LintLevel,
+    decl: String,
+    message: String,
+}
+
+/// The linter for the verified AST.
+///
+/// Each rule could be allowed, warned or denied, all rules are warned by default.
+pub struct Linter {
+    levels: HashMap<LintRule, LintLevel>,
+    large_struct_size: usize,
+}
+
+impl LintRule {
+    pub const ALL: &'static [Self] = &[
+        Self::Naming,
+        Self::UnusedDeclaration,
+        Self::UnusedImport,
+        Self::LargeStruct,
+        Self::EmptyTable,
+        Self::NonContiguousUnionIds,
+        Self::NestedOption,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Naming => "naming",
+            Self::UnusedDeclaration => "unused-declaration",
+            Self::UnusedImport => "unused-import",
+            Self::LargeStruct => "large-struct",
+            Self::EmptyTable => "empty-table",
+            Self::NonContiguousUnionIds => "non-contiguous-union-ids",
+            Self::NestedOption => "nested-option",
+        }
+    }
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl TryFrom<&str> for LintRule {
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|rule| rule.name() == value)
+            .ok_or_else(|| format!("no such lint rule: {}", value))
+    }
+}
+
+impl fmt::Display for LintLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Allow => write!(f, "allow"),
+            Self::Warn => write!(f, "warning"),
+            Self::Deny => write!(f, "error"),
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: `{}`: {}",
+            self.level, self.rule, self.decl, self.message
+        )
+    }
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Linter {
+    pub const DEFAULT_LARGE_STRUCT_SIZE: usize = 256;
+
+    pub fn new() -> Self {
+        Self {
+            levels: HashMap::new(),
+            large_struct_size: Self::DEFAULT_LARGE_STRUCT_SIZE,
+        }
+    }
+
+    pub fn set_level(&mut self, rule: LintRule, level: LintLevel) -> &mut Self {
+        self.levels.insert(rule, level);
+        self
+    }
+
+    pub fn level(&self, rule: LintRule) -> LintLevel {
+        self.levels.get(&rule).copied().unwrap_or(LintLevel::Warn)
+    }
+
+    /// Structs which are larger than this size, in bytes, are reported by `large-struct`.
+    pub fn set_large_struct_size(&mut self, size: usize) -> &mut Self {
+        self.large_struct_size = size;
+        self
+    }
+
+    pub fn lint(&self, ast: &ast::Ast) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        let mut report = |rule: LintRule, decl: &str, message: String| {
+            let level = self.level(rule);
+            if level != LintLevel::Allow {
+                lints.push(Lint {
+                    rule,
+                    level,
+                    decl: decl.to_owned(),
+                    message,
+                });
+            }
+        };
+
+        let major_decls = ast.major_decls();
+        for decl in &major_decls {
+            self.check_decl(decl, &mut report);
+        }
+
+        let used = used_decls(&major_decls);
+        let majors = major_decls
+            .iter()
+            .map(|decl| decl.name())
+            .collect::<HashSet<_>>();
+        for decl in ast.decls() {
+            let name = decl.name();
+            if !decl.is_byte() && !majors.contains(name) && !used.contains(name) {
+                report(
+                    LintRule::UnusedDeclaration,
+                    name,
+                    "it's imported but not used".to_owned(),
+                );
+            }
+        }
+        for stmt in ast.imports() {
+            if !stmt.decls().is_empty() && !stmt.decls().iter().any(|d| used.contains(d.as_str())) {
+                let mut path = "../".repeat(stmt.path_supers());
+                for p in stmt.paths() {
+                    path.push_str(p);
+                    path.push('/');
+                }
+                path.push_str(stmt.name());
+                report(
+                    LintRule::UnusedImport,
+                    &path,
+                    "no declaration in it is used".to_owned(),
+                );
+            }
+        }
+
+        lints
+    }
+
+    fn check_decl<F>(&self, decl: &ast::TopDecl, report: &mut F)
+    where
+        F: FnMut(LintRule, &str, String),
+    {
+        let name = decl.name();
+        if !is_upper_camel_case(name) {
+            report(
+                LintRule::Naming,
+                name,
+                "the type name should be UpperCamelCase".to_owned(),
+            );
+        }
+        match decl {
+            ast::TopDecl::Option_(inner) => {
+                if let ast::TopDecl::Option_(_) = inner.item().typ().as_ref() {
+                    report(
+                        LintRule::NestedOption,
+                        name,
+                        "the item is also an option".to_owned(),
+                    );
+                }
+            }
+            ast::TopDecl::Union(inner) => {
+                for item in inner.items() {
+                    if let Some(variant) = item.explicit_name() {
+                        if !is_upper_camel_case(variant) {
+                            report(
+                                LintRule::Naming,
+                                name,
+                                format!("the variant name `{}` should be UpperCamelCase", variant),
+                            );
+                        }
+                    }
+                }
+                let mut ids = inner
+                    .items()
+                    .iter()
+                    .map(|item| item.id())
+                    .chain(inner.reserved_ids().iter().copied())
+                    .collect::<Vec<_>>();
+                ids.sort_unstable();
+                if ids.iter().enumerate().any(|(index, id)| index != *id) {
+                    report(
+                        LintRule::NonContiguousUnionIds,
+                        name,
+                        format!("the ids {:?} are not contiguous from 0", ids),
+                    );
+                }
+            }
+            ast::TopDecl::Struct(inner) => {
+                self.check_fields(name, inner.fields(), report);
+                if inner.total_size() > self.large_struct_size {
+                    report(
+                        LintRule::LargeStruct,
+                        name,
+                        format!(
+                            "the size is {} bytes, which is larger than {} bytes",
+                            inner.total_size(),
+                            self.large_struct_size
+                        ),
+                    );
+                }
+            }
+            ast::TopDecl::Table(inner) => {
+                self.check_fields(name, inner.fields(), report);
+                if inner.fields().is_empty() {
+                    report(LintRule::EmptyTable, name, "it has no fields".to_owned());
+                }
+            }
+            ast::TopDecl::Primitive(_)
+            | ast::TopDecl::Array(_)
+            | ast::TopDecl::FixVec(_)
+            | ast::TopDecl::DynVec(_) => {}
+        }
+    }
+
+    fn check_fields<F>(&self, name: &str, fields: &[ast::FieldDecl], report: &mut F)
+    where
+        F: FnMut(LintRule, &str, String),
+    {
+        for field in fields {
+            if !field.reserved() && !is_snake_case(field.name()) {
+                report(
+                    LintRule::Naming,
+                    name,
+                    format!("the field name `{}` should be snake_case", field.name()),
+                );
+            }
+        }
+    }
+}
+
+fn is_upper_camel_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase()) && !name.contains('_')
+}
+
+fn is_snake_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Returns the names of the declarations which are used by the major declarations.
+fn used_decls(major_decls: &[Rc<ast::TopDecl>]) -> HashSet<&str> {
+    fn visit<'a>(decl: &'a ast::TopDecl, used: &mut HashSet<&'a str>) {
+        let items: Vec<&'a Rc<ast::TopDecl>> = match decl {
+            ast::TopDecl::Primitive(_) => Vec::new(),
+            ast::TopDecl::Option_(inner) => vec![inner.item().typ()],
+            ast::TopDecl::Union(inner) => inner.items().iter().map(|item| item.typ()).collect(),
+            ast::TopDecl::Array(inner) => vec![inner.item().typ()],
+            ast::TopDecl::Struct(inner) => inner.fields().iter().map(|f| f.typ()).collect(),
+            ast::TopDecl::FixVec(inner) => vec![inner.item().typ()],
+            ast::TopDecl::DynVec(inner) => vec![inner.item().typ()],
+            ast::TopDecl::Table(inner) => inner.fields().iter().map(|f| f.typ()).collect(),
+        };
+        for item in items {
+            if used.insert(item.name()) {
+                visit(item, used);
+            }
+        }
+    }
+
+    let mut used = HashSet::new();
+    for decl in major_decls {
+        visit(decl, &mut used);
+    }
+    used
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use super::{LintLevel, LintRule, Linter};
+    use crate::Parser;
+
+    #[test]
+    fn test_lint() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut library = std::fs::File::create(dir.path().join("library.mol")).unwrap();
+        library
+            .write_all(b"syntax = 2;\narray Uint32 [byte; 4];\narray Uint64 [byte; 8];\n")
+            .unwrap();
+        let mut unused = std::fs::File::create(dir.path().join("unused.mol")).unwrap();
+        unused
+            .write_all(b"syntax = 2;\narray Hash [byte; 32];\n")
+            .unwrap();
+        let schema_path = dir.path().join("schema.mol");
+        let mut schema = std::fs::File::create(&schema_path).unwrap();
+        schema
+            .write_all(
+                b"
+syntax = 2;
+import library;
+import unused;
+
+array Large [byte; 300];
+struct point { X: Uint32, y: Uint32, }
+table Empty {}
+option Nonce (Uint32);
+option NonceOpt (Nonce);
+union Message { Uint32: 1, reserved 2; bad_name: Large = 4, }
+",
+            )
+            .unwrap();
+        schema.flush().unwrap();
+
+        let ast = Parser::parse(&schema_path);
+        let lints = Linter::new()
+            .set_level(LintRule::LargeStruct, LintLevel::Deny)
+            .set_large_struct_size(4)
+            .lint(&ast)
+            .into_iter()
+            .map(|lint| lint.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lints,
+            vec![
+                "warning[naming]: `point`: the type name should be UpperCamelCase",
+                "warning[naming]: `point`: the field name `X` should be snake_case",
+                "error[large-struct]: `point`: the size is 8 bytes, which is larger than 4 bytes",
+                "warning[empty-table]: `Empty`: it has no fields",
+                "warning[nested-option]: `NonceOpt`: the item is also an option",
+                "warning[naming]: `Message`: the variant name `bad_name` should be UpperCamelCase",
+                "warning[non-contiguous-union-ids]: `Message`: the ids [1, 2, 4] are not contiguous from 0",
+                "warning[unused-declaration]: `Uint64`: it's imported but not used",
+                "warning[unused-declaration]: `Hash`: it's imported but not used",
+                "warning[unused-import]: `unused`: no declaration in it is used",
+            ]
+        );
+
+        let lints = Linter::new()
+            .set_level(LintRule::Naming, LintLevel::Allow)
+            .set_level(LintRule::UnusedDeclaration, LintLevel::Allow)
+            .lint(&ast);
+        assert!(lints.iter().all(|lint| lint.rule() != LintRule::Naming));
+        assert_eq!(
+            lints
+                .iter()
+                .filter(|lint| lint.level() == LintLevel::Deny)
+                .count(),
+            0
+        );
+    }
+}
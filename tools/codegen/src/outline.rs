@@ -0,0 +1,261 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use pest::{error::InputLocation, iterators::Pair, Parser as _};
+use property::Property;
+
+use crate::parser::{InnerParser, Rule};
+
+/// The declarations, the type references and the imports in a schema source, with their byte
+/// ranges, for the editor tooling.
+///
+/// Only the source is parsed, the imported files are not read.
+#[derive(Debug, Clone, Default, Property)]
+#[property(get(public))]
+pub struct Outline {
+    decls: Vec<Declaration>,
+    references: Vec<Reference>,
+    imports: Vec<Import>,
+}
+
+#[derive(Debug, Clone, Property)]
+#[property(get(public))]
+pub struct Declaration {
+    name: String,
+    keyword: String,
+    name_range: Range<usize>,
+    range: Range<usize>,
+}
+
+/// A type which is used by a declaration.
+#[derive(Debug, Clone, Property)]
+#[property(get(public))]
+pub struct Reference {
+    name: String,
+    range: Range<usize>,
+}
+
+#[derive(Debug, Clone, Property)]
+#[property(get(public))]
+pub struct Import {
+    name: String,
+    paths: Vec<String>,
+    #[property(get(type = "copy"))]
+    path_supers: usize,
+    range: Range<usize>,
+}
+
+#[derive(Debug, Clone, Property)]
+#[property(get(public))]
+pub struct SyntaxError {
+    message: String,
+    range: Range<usize>,
+}
+
+impl Outline {
+    pub fn parse(source: &str) -> Result<Self, SyntaxError> {
+        let grammar = InnerParser::parse(Rule::grammar, source)
+            .map_err(|err| {
+                let range = match err.location {
+                    InputLocation::Pos(pos) => pos..pos,
+                    InputLocation::Span((start, end)) => start..end,
+                };
+                SyntaxError {
+                    message: err.variant.message().into_owned(),
+                    range,
+                }
+            })?
+            .next()
+            .unwrap();
+        let mut outline = Self::default();
+        for pair in grammar.into_inner() {
+            match pair.as_rule() {
+                Rule::syntax_version_stmt | Rule::EOI => {}
+                Rule::import_stmt => {
+                    let path = pair.into_inner().next().unwrap();
+                    let range = range_of(&path);
+                    let mut path_supers = 0;
+                    let mut paths = Vec::new();
+                    for inner in path.into_inner() {
+                        if inner.as_rule() == Rule::path_super {
+                            path_supers += 1;
+                        } else {
+                            paths.push(inner.as_str().to_owned());
+                        }
+                    }
+                    outline.imports.push(Import {
+                        name: paths.pop().unwrap(),
+                        paths,
+                        path_supers,
+                        range,
+                    });
+                }
+                _ => outline.add_decl(pair),
+            }
+        }
+        Ok(outline)
+    }
+
+    /// Returns the name of the declaration or the reference at the offset.
+    pub fn symbol_at(&self, offset: usize) -> Option<&str> {
+        self.decls
+            .iter()
+            .map(|decl| (decl.name(), decl.name_range()))
+            .chain(
+                self.references
+                    .iter()
+                    .map(|reference| (reference.name(), reference.range())),
+            )
+            .find(|(_, range)| range.start <= offset && offset <= range.end)
+            .map(|(name, _)| name)
+    }
+
+    pub fn decl(&self, name: &str) -> Option<&Declaration> {
+        self.decls.iter().find(|decl| decl.name() == name)
+    }
+
+    fn add_decl(&mut self, pair: Pair<Rule>) {
+        let keyword = match pair.as_rule() {
+            Rule::option_decl => "option",
+            Rule::union_decl => "union",
+            Rule::array_decl => "array",
+            Rule::struct_decl => "struct",
+            Rule::vector_decl => "vector",
+            Rule::table_decl => "table",
+            Rule::string_decl => "string",
+            Rule::set_decl => "set",
+            Rule::map_decl => "map",
+            _ => unreachable!(),
+        };
+        let range = range_of(&pair);
+        let mut inner = pair.into_inner();
+        let name = inner.next().unwrap();
+        self.decls.push(Declaration {
+            name: name.as_str().to_owned(),
+            keyword: keyword.to_owned(),
+            name_range: range_of(&name),
+            range,
+        });
+        for pair in inner {
+            match pair.as_rule() {
+                Rule::field_decl => {
+                    let typ = pair.into_inner().nth(1).unwrap();
+                    self.add_reference(typ);
+                }
+                Rule::item_decl | Rule::custom_union_item_decl => {
+                    self.add_reference(pair.into_inner().next().unwrap());
+                }
+                Rule::named_union_item_decl => {
+                    self.add_reference(pair.into_inner().nth(1).unwrap());
+                }
+                Rule::identifier => self.add_reference(pair),
+                _ => {}
+            }
+        }
+    }
+
+    fn add_reference(&mut self, pair: Pair<Rule>) {
+        match pair.as_rule() {
+            Rule::identifier => {
+                if pair.as_str() != "byte" {
+                    self.references.push(Reference {
+                        name: pair.as_str().to_owned(),
+                        range: range_of(&pair),
+                    });
+                }
+            }
+            Rule::vector_type | Rule::option_type | Rule::array_type => {
+                self.add_reference(pair.into_inner().next().unwrap());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Import {
+    /// Returns the path of the imported file, `base` is the path of the importing file.
+    pub fn file_path<P: AsRef<Path>>(&self, base: &P) -> PathBuf {
+        let mut path_buf = base.as_ref().to_path_buf();
+        path_buf.pop();
+        for _ in 0..self.path_supers {
+            path_buf.push("..");
+        }
+        for p in &self.paths {
+            path_buf.push(p);
+        }
+        path_buf.push(&self.name);
+        path_buf.set_extension("mol");
+        path_buf
+    }
+}
+
+fn range_of(pair: &Pair<Rule>) -> Range<usize> {
+    let span = pair.as_span();
+    span.start()..span.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Outline;
+
+    #[test]
+    fn test_outline() {
+        let source = "syntax = 2;
+import ../common/types;
+array Uint32 [byte; 4];
+struct Point { x: Uint32, y: Uint32, }
+table Shape { points: vector<Point>, tag: option<[Tag; 2]>, }
+union Either { Point, Named: Shape = 3, Uint32: 4, }
+";
+        let outline = Outline::parse(source).unwrap();
+        assert_eq!(
+            outline
+                .decls()
+                .iter()
+                .map(|decl| (decl.keyword(), decl.name()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("array", "Uint32"),
+                ("struct", "Point"),
+                ("table", "Shape"),
+                ("union", "Either")
+            ]
+        );
+        assert_eq!(
+            outline
+                .references()
+                .iter()
+                .map(|reference| reference.name())
+                .collect::<Vec<_>>(),
+            vec!["Uint32", "Uint32", "Point", "Tag", "Point", "Shape", "Uint32"]
+        );
+        let import = &outline.imports()[0];
+        assert_eq!(
+            (import.path_supers(), import.paths(), import.name()),
+            (1, &["common".to_owned()][..], "types")
+        );
+        assert_eq!(
+            import.file_path(&"schemas/shapes.mol"),
+            std::path::PathBuf::from("schemas/../common/types.mol")
+        );
+
+        let offset = source.find("Tag").unwrap();
+        assert_eq!(outline.symbol_at(offset + 1), Some("Tag"));
+        let offset = source.find("struct Point").unwrap();
+        assert_eq!(outline.symbol_at(offset), None);
+        assert_eq!(outline.symbol_at(offset + 8), Some("Point"));
+        assert_eq!(
+            outline.decl("Point").unwrap().name_range(),
+            &(offset + 7..offset + 12)
+        );
+    }
+
+    #[test]
+    fn test_outline_syntax_error() {
+        let source = "array Uint32 [byte; 4];\nstruct Point { x Uint32, }\n";
+        let error = Outline::parse(source).unwrap_err();
+        assert_eq!(error.range().start, source.find("x Uint32").unwrap());
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    error, fmt,
+    ops::Range,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use property::Property;
+
+thread_local! {
+    /// The span of the statement or the declaration which is being parsed or completed.
+    static CURRENT_SPAN: RefCell<Option<Span>> = const { RefCell::new(None) };
+}
+
+/// A range of bytes in a schema file.
+#[derive(Debug, Clone, PartialEq, Eq, Property)]
+#[property(get(public))]
+pub struct Span {
+    file: PathBuf,
+    range: Range<usize>,
+}
+
+/// An error of the schema files, which is reported by a panic of the parser.
+#[derive(Debug, Clone, Property)]
+#[property(get(public))]
+pub struct ParseError {
+    message: String,
+    /// Where the error is found, if it's known.
+    span: Option<Span>,
+}
+
+/// Keeps the current span while it's alive.
+///
+/// When the scope is dropped by a panic, the span is kept, so the panic can be located.
+pub(crate) struct SpanScope {
+    previous: Option<Span>,
+}
+
+impl Span {
+    pub(crate) fn new<P: AsRef<Path>>(file: &P, range: Range<usize>) -> Self {
+        Self {
+            file: file.as_ref().to_path_buf(),
+            range,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref span) = self.span {
+            write!(
+                f,
+                "{} (in {} at {}..{})",
+                self.message,
+                span.file.display(),
+                span.range.start,
+                span.range.end
+            )
+        } else {
+            write!(f, "{}", self.message)
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+impl ParseError {
+    /// Takes the span of the panic, it should be called just after the panic is caught.
+    pub(crate) fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else if let Some(message) = payload.downcast_ref::<&str>() {
+            (*message).to_owned()
+        } else {
+            "failed to parse the schema".to_owned()
+        };
+        let span = CURRENT_SPAN.with(|current| current.borrow_mut().take());
+        Self { message, span }
+    }
+}
+
+impl SpanScope {
+    pub(crate) fn enter(span: Span) -> Self {
+        let previous = CURRENT_SPAN.with(|current| current.replace(Some(span)));
+        Self { previous }
+    }
+}
+
+impl Drop for SpanScope {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            let previous = self.previous.take();
+            CURRENT_SPAN.with(|current| *current.borrow_mut() = previous);
+        }
+    }
+}
+
+/// Sets the current span without a scope, for the errors which are returned instead of panics.
+pub(crate) fn set_span(span: Option<Span>) {
+    CURRENT_SPAN.with(|current| *current.borrow_mut() = span);
+}
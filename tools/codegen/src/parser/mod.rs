@@ -1,17 +1,79 @@
-use std::path::Path;
+use std::{
+    panic,
+    path::{Path, PathBuf},
+};
 
 use crate::{ast, utils::ParserUtils as _};
 
+mod error;
 mod inner;
+pub(crate) use error::{set_span, SpanScope};
+pub use error::{ParseError, Span};
 pub(crate) use inner::{Parser as InnerParser, Rule};
 
 pub struct Parser;
 
 impl Parser {
     pub fn parse<P: AsRef<Path>>(path: &P) -> ast::Ast {
-        let ast_raw = Self::preprocess(path).unwrap();
+        let ast_raw = Self::preprocess(path).unwrap_or_else(|err| panic!("{}", err));
         ast::Ast::complete(ast_raw)
     }
+
+    /// Parses a schema file, the imported files which are not found relative to the importing
+    /// file are searched in the import paths, in order.
+    pub fn parse_with_import_paths<P: AsRef<Path>>(path: &P, import_paths: &[PathBuf]) -> ast::Ast {
+        let ast_raw = Self::preprocess_source(path, &|_| None, import_paths)
+            .unwrap_or_else(|err| panic!("{}", err));
+        ast::Ast::complete(ast_raw)
+    }
+
+    /// Parses a schema file whose content is `source`, for example, an unsaved file in an
+    /// editor; the imported files are read from the disk.
+    pub fn parse_source<P: AsRef<Path>>(path: &P, source: &str) -> ast::Ast {
+        let sources = |file: &Path| (file == path.as_ref()).then(|| source.to_owned());
+        Self::parse_with_sources(path, &sources)
+    }
+
+    /// Parses a schema file, the content of each file, including the imported files, is
+    /// provided by `sources` if it returns `Some`, for example, the unsaved files in an editor;
+    /// otherwise the file is read from the disk.
+    pub fn parse_with_sources<P: AsRef<Path>>(
+        path: &P,
+        sources: &dyn Fn(&Path) -> Option<String>,
+    ) -> ast::Ast {
+        let ast_raw =
+            Self::preprocess_source(path, sources, &[]).unwrap_or_else(|err| panic!("{}", err));
+        ast::Ast::complete(ast_raw)
+    }
+
+    /// Same as [`parse_with_sources`](Self::parse_with_sources), but the errors are returned
+    /// with their spans, instead of panics.
+    ///
+    /// The errors are still caught from panics, so the panic hook is called; set a silent hook
+    /// to hide the messages.
+    pub fn try_parse_with_sources<P: AsRef<Path>>(
+        path: &P,
+        sources: &dyn Fn(&Path) -> Option<String>,
+    ) -> Result<ast::Ast, ParseError> {
+        Self::catch(|| Self::parse_with_sources(path, sources))
+    }
+
+    /// Same as [`parse_with_import_paths`](Self::parse_with_import_paths), but the errors are
+    /// returned with their spans, instead of panics.
+    ///
+    /// The errors are still caught from panics, so the panic hook is called; set a silent hook
+    /// to hide the messages.
+    pub fn try_parse_with_import_paths<P: AsRef<Path>>(
+        path: &P,
+        import_paths: &[PathBuf],
+    ) -> Result<ast::Ast, ParseError> {
+        Self::catch(|| Self::parse_with_import_paths(path, import_paths))
+    }
+
+    fn catch<F: FnOnce() -> ast::Ast>(parse: F) -> Result<ast::Ast, ParseError> {
+        set_span(None);
+        panic::catch_unwind(panic::AssertUnwindSafe(parse)).map_err(ParseError::from_panic)
+    }
 }
 
 #[cfg(test)]
@@ -315,4 +377,796 @@ union UninoWithPartialDuplicateCustomIdInReverseOrder {
 
         let _should_panic = Parser::parse(&schema_file.into_temp_path());
     }
+
+    #[test]
+    fn test_parse_named_union_items() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+array Empty [byte; 1];
+array Nonce [byte; 4];
+union Message {
+    Ping: Empty = 1,
+    Pong: Empty = 2,
+    Nonce,
+    Retry: Nonce,
+}
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let ast = Parser::parse(&schema_file.into_temp_path());
+        match ast.decls()[2].as_ref() {
+            TopDecl::Union(union) => {
+                let items = union
+                    .items()
+                    .iter()
+                    .map(|item| (item.name(), item.typ().name(), item.id()))
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    items,
+                    vec![
+                        ("Ping", "Empty", 1),
+                        ("Pong", "Empty", 2),
+                        ("Nonce", "Nonce", 3),
+                        ("Retry", "Nonce", 4),
+                    ]
+                );
+                assert!(!union.is_unique_item_type(&union.items()[0]));
+                assert_eq!(union.items()[2].explicit_name(), None);
+            }
+            _ => panic!("Message should be a union"),
+        }
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_bad_duplicate_union_variant_names() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+array Empty [byte; 1];
+array Nonce [byte; 4];
+union Message {
+    Nonce,
+    Nonce: Empty,
+}
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let _should_panic = Parser::parse(&schema_file.into_temp_path());
+    }
+
+    #[test]
+    fn test_parse_reserved() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+array Uint32 [byte; 4];
+vector Bytes <byte>;
+union Message {
+    Uint32 : 1,
+    reserved 5, 3;
+    Bytes : 4,
+}
+table Config {
+    version: Uint32,
+    _reserved: Bytes,
+    _reserved_2: Uint32,
+}
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let ast = Parser::parse(&schema_file.into_temp_path());
+        match ast.decls()[2].as_ref() {
+            TopDecl::Union(union) => {
+                assert_eq!(union.reserved_ids(), &[3, 5]);
+                assert_eq!(union.items().len(), 2);
+            }
+            _ => panic!("Message should be a union"),
+        }
+        match ast.decls()[3].as_ref() {
+            TopDecl::Table(table) => {
+                let reserved = table
+                    .fields()
+                    .iter()
+                    .map(|field| field.reserved())
+                    .collect::<Vec<_>>();
+                assert_eq!(reserved, vec![false, true, true]);
+            }
+            _ => panic!("Config should be a table"),
+        }
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_bad_reserved_union_id_is_used() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+array a0 [byte; 1];
+array a1 [byte; 1];
+union Message {
+    reserved 1;
+    a0,
+    a1,
+}
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let _should_panic = Parser::parse(&schema_file.into_temp_path());
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_bad_reserved_field_in_struct() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+array Uint32 [byte; 4];
+struct Point {
+    x: Uint32,
+    _reserved: Uint32,
+}
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let _should_panic = Parser::parse(&schema_file.into_temp_path());
+    }
+
+    #[test]
+    fn test_parse_field_defaults() {
+        use crate::ast::DefaultContent as _;
+
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+array Uint16 [byte; 2];
+array Uint32 [byte; 4];
+struct Version {
+    major: byte = 1,
+    minor: Uint16 = 0x0203,
+}
+table Config {
+    version: Version,
+    timeout: Uint32 = 10,
+    retries: Uint16,
+}
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let ast = Parser::parse(&schema_file.into_temp_path());
+        match ast.decls()[2].as_ref() {
+            TopDecl::Struct(inner) => {
+                assert_eq!(inner.fields()[1].default(), Some(&vec![2, 3]));
+                assert_eq!(inner.default_content(), vec![1, 2, 3]);
+            }
+            _ => panic!("Version should be a struct"),
+        }
+        match ast.decls()[3].as_ref() {
+            TopDecl::Table(inner) => {
+                assert_eq!(inner.fields()[0].default(), None);
+                assert_eq!(inner.fields()[1].default(), Some(&vec![10, 0, 0, 0]));
+                assert_eq!(
+                    inner.default_content(),
+                    vec![
+                        25, 0, 0, 0, 16, 0, 0, 0, 19, 0, 0, 0, 23, 0, 0, 0, 1, 2, 3, 10, 0, 0, 0,
+                        0, 0
+                    ]
+                );
+            }
+            _ => panic!("Config should be a table"),
+        }
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_bad_field_default_does_not_fit() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+array Uint16 [byte; 2];
+struct Point {
+    x: Uint16 = 65536,
+}
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let _should_panic = Parser::parse(&schema_file.into_temp_path());
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_bad_field_default_for_dynamic_size() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+vector Bytes <byte>;
+table Config {
+    name: Bytes = 0x00000000,
+}
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let _should_panic = Parser::parse(&schema_file.into_temp_path());
+    }
+
+    #[test]
+    fn test_syntax_version_features() {
+        use crate::ast::{Feature, SyntaxVersion};
+
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+string Name;
+array Uint32 [byte; 4];
+table Person {
+    name: Name,
+    age: Uint32 = 18,
+}
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let ast = Parser::parse(&schema_file.into_temp_path());
+        assert_eq!(ast.syntax_version().version(), SyntaxVersion::LATEST);
+        assert_eq!(ast.syntax_version().features(), Feature::ALL);
+        assert_eq!(
+            ast.used_features(),
+            vec![Feature::Strings, Feature::FieldDefaults]
+        );
+    }
+
+    #[test]
+    fn test_schema_hash() {
+        use crate::ast::SchemaHash as _;
+
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+array Uint32 [byte; 4];
+array Word [byte; 4];
+vector Bytes <byte>;
+struct Point { x: Uint32, y: Uint32, }
+struct Coord { x: Word, y: Word = 1, }
+struct Swapped { y: Uint32, x: Uint32, }
+table Header { point: Point, data: Bytes, }
+table Header2 { point: Coord, data: Bytes, }
+union U1 { Uint32, Bytes, }
+union U2 { Word: Word, Data: Bytes, reserved 8; }
+union U3 { Uint32: 0, Bytes: 2, }
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let ast = Parser::parse(&schema_file.into_temp_path());
+        let hash = |name: &str| {
+            ast.decls()
+                .iter()
+                .find(|decl| decl.name() == name)
+                .unwrap()
+                .schema_hash()
+        };
+        assert_eq!(hash("Uint32"), hash("Word"));
+        assert_eq!(hash("Point"), hash("Coord"));
+        assert_ne!(hash("Point"), hash("Swapped"));
+        assert_eq!(hash("Header"), hash("Header2"));
+        assert_eq!(hash("U1"), hash("U2"));
+        assert_ne!(hash("U1"), hash("U3"));
+        assert_ne!(hash("Uint32"), hash("Bytes"));
+    }
+
+    #[test]
+    fn test_schema_hash_of_shared_types() {
+        use crate::ast::SchemaHash as _;
+
+        // each type is used twice by the next one, it takes forever if the hashes of the
+        // dependencies are computed again and again
+        let mut source = "table T0 { a: byte, }\n".to_owned();
+        for i in 1..64 {
+            source += &format!("table T{} {{ a: T{}, b: T{}, }}\n", i, i - 1, i - 1);
+        }
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file.write_all(source.as_bytes()).unwrap();
+        schema_file.flush().unwrap();
+
+        let ast = Parser::parse(&schema_file.into_temp_path());
+        let hashes = ast
+            .decls()
+            .iter()
+            .map(|decl| decl.schema_hash())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(hashes.len(), ast.decls().len());
+    }
+
+    #[test]
+    fn test_layouts() {
+        use crate::ast::Encoding;
+
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+array Uint32 [byte; 4];
+vector Bytes <byte>;
+vector BytesVec <Bytes>;
+struct Point { x: Uint32, y: byte, z: Uint32, }
+table Header { point: Point, data: Bytes, }
+union Either { Point, Bytes: 3, }
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let ast = Parser::parse(&schema_file.into_temp_path());
+        let layouts = ast.layouts();
+        assert_eq!(
+            layouts.iter().map(|l| l.name()).collect::<Vec<_>>(),
+            vec!["Uint32", "Bytes", "BytesVec", "Point", "Header", "Either"]
+        );
+
+        let uint32 = &layouts[0];
+        assert_eq!(uint32.encoding(), Encoding::Array);
+        assert_eq!(uint32.total_size(), Some(4));
+        let item = uint32.item().unwrap();
+        assert_eq!((item.size(), item.count()), (Some(1), Some(4)));
+
+        let bytes_vec = &layouts[2];
+        assert_eq!(bytes_vec.total_size(), None);
+        let header = bytes_vec.header().unwrap();
+        assert_eq!((header.size(), header.size_per_item()), (4, 4));
+
+        let point = &layouts[3];
+        assert_eq!(point.total_size(), Some(9));
+        assert_eq!(
+            point
+                .fields()
+                .iter()
+                .map(|f| (f.offset(), f.size()))
+                .collect::<Vec<_>>(),
+            vec![(Some(0), Some(4)), (Some(4), Some(1)), (Some(5), Some(4))]
+        );
+
+        let header = &layouts[4];
+        assert_eq!(header.header().unwrap().size(), 12);
+        assert_eq!(
+            header
+                .fields()
+                .iter()
+                .map(|f| (f.offset(), f.size()))
+                .collect::<Vec<_>>(),
+            vec![(None, Some(9)), (None, None)]
+        );
+
+        let either = &layouts[5];
+        assert_eq!(
+            either
+                .items()
+                .iter()
+                .map(|item| (item.id(), item.name()))
+                .collect::<Vec<_>>(),
+            vec![(0, "Point"), (3, "Bytes")]
+        );
+        assert_eq!(
+            either.to_string(),
+            "union Either\n    size: dynamic\n    header: 4 bytes\n    id 0 => Point: Point, 9 bytes\n    id 3 => Bytes: Bytes, dynamic\n"
+        );
+    }
+
+    #[should_panic(expected = "requires syntax version 2")]
+    #[test]
+    fn test_bad_feature_above_syntax_version() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+string Name;
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let _should_panic = Parser::parse(&schema_file.into_temp_path());
+    }
+
+    #[test]
+    fn test_parse_set_and_map() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+array Uint32 [byte; 4];
+vector Bytes <byte>;
+set Uint32Set <Uint32>;
+set BytesSet <Bytes>;
+map Uint32Map <Uint32, Uint32>;
+map BytesMap <Bytes, Uint32>;
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let ast = Parser::parse(&schema_file.into_temp_path());
+        let names = ast.decls().iter().map(|d| d.name()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "Uint32",
+                "Bytes",
+                "Uint32Set",
+                "BytesSet",
+                "Uint32MapEntry",
+                "Uint32Map",
+                "BytesMapEntry",
+                "BytesMap"
+            ]
+        );
+        let decls = ast.decls();
+        match decls[2].as_ref() {
+            TopDecl::FixVec(set) => {
+                assert_eq!(set.collection(), Some(ast::Collection::Set));
+                assert_eq!(set.item_size(), 4);
+            }
+            _ => panic!("Uint32Set should be a fixvec"),
+        }
+        match decls[3].as_ref() {
+            TopDecl::DynVec(set) => assert_eq!(set.collection(), Some(ast::Collection::Set)),
+            _ => panic!("BytesSet should be a dynvec"),
+        }
+        match (decls[4].as_ref(), decls[5].as_ref()) {
+            (TopDecl::Struct(entry), TopDecl::FixVec(map)) => {
+                assert_eq!(map.collection(), Some(ast::Collection::Map));
+                assert_eq!(map.item_size(), 8);
+                assert_eq!(entry.field_sizes(), &[4, 4]);
+                assert!(map.item().typ().entry_fields().is_some());
+            }
+            _ => panic!("Uint32Map should be a fixvec of structs"),
+        }
+        match (decls[6].as_ref(), decls[7].as_ref()) {
+            (TopDecl::Table(entry), TopDecl::DynVec(map)) => {
+                assert_eq!(map.collection(), Some(ast::Collection::Map));
+                assert_eq!(entry.fields().len(), 2);
+            }
+            _ => panic!("BytesMap should be a dynvec of tables"),
+        }
+    }
+
+    #[test]
+    fn test_parse_string() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+string Name;
+table Person {
+    name: Name,
+}
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let ast = Parser::parse(&schema_file.into_temp_path());
+        match ast.decls()[0].as_ref() {
+            TopDecl::FixVec(string) => {
+                assert_eq!(string.name(), "Name");
+                assert!(string.utf8());
+                assert!(string.item().typ().is_byte());
+            }
+            _ => panic!("Name should be a fixvec"),
+        }
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_bad_map_entry_name_is_used() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+array Uint32 [byte; 4];
+struct Uint32MapEntry {
+    key: Uint32,
+}
+map Uint32Map <Uint32, Uint32>;
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let _should_panic = Parser::parse(&schema_file.into_temp_path());
+    }
+
+    #[test]
+    fn test_parse_anonymous_types() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+array Uint32 [byte; 4];
+vector Bytes <byte>;
+struct Point {
+    x: Uint32,
+    y: Uint32,
+    tag: [byte; 20],
+}
+table Shape {
+    points: vector<Point>,
+    name: vector<byte>,
+    labels: vector<vector<byte>>,
+    center: option<Point>,
+    corners: [Point; 4],
+    tag: [byte; 20],
+}
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let ast = Parser::parse(&schema_file.into_temp_path());
+        let names = ast.decls().iter().map(|d| d.name()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "Byte20",
+                "PointVec",
+                "BytesVec",
+                "PointOpt",
+                "PointArray4",
+                "Uint32",
+                "Bytes",
+                "Point",
+                "Shape"
+            ]
+        );
+        match ast.decls()[4].as_ref() {
+            TopDecl::Array(array) => {
+                assert_eq!(array.item().typ().name(), "Point");
+                assert_eq!(array.item_count(), 4);
+            }
+            _ => panic!("PointArray4 should be an array"),
+        }
+        match ast.decls()[2].as_ref() {
+            TopDecl::DynVec(vector) => assert_eq!(vector.item().typ().name(), "Bytes"),
+            _ => panic!("BytesVec should be a dynvec"),
+        }
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_bad_anonymous_type_name_is_used() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+array Bytes [byte; 2];
+table Foo {
+    name: vector<byte>,
+}
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let _should_panic = Parser::parse(&schema_file.into_temp_path());
+    }
+
+    #[test]
+    fn test_string_field_type() {
+        let mut schema_file = tempfile::NamedTempFile::new().unwrap();
+        schema_file
+            .write_all(
+                b"
+syntax = 2;
+string String;
+array stringy [byte; 2];
+table Foo {
+    name: string,
+    names: vector<string>,
+    other: stringy,
+}
+",
+            )
+            .unwrap();
+
+        schema_file.flush().unwrap();
+
+        let ast = Parser::parse(&schema_file.into_temp_path());
+        let names = ast.decls().iter().map(|d| d.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["StringVec", "String", "stringy", "Foo"]);
+        match ast.decls()[1].as_ref() {
+            TopDecl::FixVec(string) => assert!(string.utf8()),
+            _ => panic!("String should be a fixvec"),
+        }
+    }
+
+    #[test]
+    fn test_anonymous_types_are_deduplicated_across_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("child.mol"),
+            b"
+syntax = 2;
+array Uint32 [byte; 4];
+table Child {
+    values: vector<Uint32>,
+}
+",
+        )
+        .unwrap();
+        let root = dir.path().join("root.mol");
+        std::fs::write(
+            &root,
+            b"
+syntax = 2;
+import child;
+table Root {
+    values: vector<Uint32>,
+    child: option<Child>,
+}
+",
+        )
+        .unwrap();
+
+        let ast = Parser::parse(&root);
+        let names = ast.decls().iter().map(|d| d.name()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["Uint32Vec", "ChildOpt", "Root", "Uint32", "Child"]
+        );
+        // the anonymous type is defined in the imported file
+        let major_names = ast
+            .major_decls()
+            .iter()
+            .map(|d| d.name().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(major_names, vec!["ChildOpt", "Root"]);
+    }
+
+    #[test]
+    fn test_parse_with_import_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let vendor = dir.path().join("vendor");
+        std::fs::create_dir_all(vendor.join("common")).unwrap();
+        std::fs::write(
+            vendor.join("common").join("basic.mol"),
+            b"array Uint32 [byte; 4];\n",
+        )
+        .unwrap();
+        let root = dir.path().join("root.mol");
+        std::fs::write(&root, b"import common/basic;\ntable Root { a: Uint32, }\n").unwrap();
+
+        let ast = Parser::parse_with_import_paths(&root, &[vendor]);
+        let names = ast.decls().iter().map(|d| d.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Root", "Uint32"]);
+        assert_eq!(ast.imports()[0].decls(), &["Uint32".to_owned()]);
+    }
+
+    #[test]
+    fn test_parse_with_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root.mol");
+        let basic = dir.path().join("basic.mol");
+        std::fs::write(&root, b"import basic;\ntable Root { a: Uint32, }\n").unwrap();
+        std::fs::write(&basic, b"array Uint32 [byte; 4];\n").unwrap();
+
+        // the unsaved content of the imported file is used instead of the file on the disk
+        let unsaved = |file: &std::path::Path| {
+            (file == basic.as_path()).then(|| "array Uint64 [byte; 8];\n".to_owned())
+        };
+        let err = Parser::try_parse_with_sources(&root, &unsaved).unwrap_err();
+        assert!(err.message().contains("unable to be completed"));
+        let span = err.span().unwrap();
+        assert_eq!(span.file(), &root);
+        assert_eq!(span.range(), &(20..24));
+
+        let ast = Parser::try_parse_with_sources(&root, &|_| None).unwrap();
+        let names = ast.decls().iter().map(|d| d.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Root", "Uint32"]);
+    }
+
+    #[test]
+    fn test_parse_error_spans() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root.mol");
+        let source = "import missing;\narray Uint32 [byte; 4];\n";
+        std::fs::write(&root, source).unwrap();
+        let spans = [
+            // an imported file is not found
+            (source, "is not found", 0..15),
+            // a syntax error
+            ("array A [byte; 1];\nstruct S { a }\n", "expected", 30..30),
+            // an error of the statement
+            (
+                "syntax = 3;\narray A [byte; 1];\n",
+                "unknown syntax version",
+                0..11,
+            ),
+            // an error of the declaration
+            (
+                "array A [byte; 1];\nunion U { A: 1, A: 1, }\n",
+                "is duplicated",
+                25..26,
+            ),
+            // an error in the completion
+            (
+                "array A [byte; 1];\narray A [byte; 2];\n",
+                "more than once",
+                25..26,
+            ),
+            // a feature which is not supported
+            (
+                "syntax = 1;\narray A [byte; 1];\nunion U { A, reserved 2; }\n",
+                "requires",
+                37..38,
+            ),
+        ];
+        for (source, message, range) in spans {
+            let sources = |_: &std::path::Path| Some(source.to_owned());
+            let err = Parser::try_parse_with_sources(&root, &sources).unwrap_err();
+            assert!(err.message().contains(message), "{}", err);
+            let span = err.span().unwrap();
+            assert_eq!(span.file(), &root);
+            assert_eq!(span.range(), &range, "{}", err);
+        }
+    }
 }
@@ -0,0 +1,371 @@
+//! The protocol between the compiler and the plugins.
+//!
+//! The compiler runs `moleculec-<language> --capabilities` first, a plugin which supports the
+//! protocol outputs its [`Capabilities`] in JSON. Then the compiler runs
+//! `moleculec-<language> --protocol <version>`, writes a [`Request`] in JSON in a single line
+//! and the intermediate data to its stdin, and the plugin writes a [`Response`] in JSON to its
+//! stdout. The intermediate data is in the format version of the capabilities.
+//!
+//! The plugins which don't support the protocol fail on `--capabilities`, then the compiler
+//! falls back to the legacy protocol: the intermediate data is written to the stdin of the
+//! plugin in version 1 of the format, and the stdout of the plugin is the generated code.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, BufRead},
+};
+
+use property::Property;
+use serde::{Deserialize, Serialize};
+
+use crate::ir;
+
+/// The latest version of the protocol.
+pub const PROTOCOL_VERSION: usize = 1;
+
+/// What a plugin supports, which is the output of `moleculec-<language> --capabilities`.
+#[derive(Debug, Clone, PartialEq, Eq, Property, Deserialize, Serialize)]
+#[property(get(public))]
+pub struct Capabilities {
+    pub protocol_version: usize,
+    /// The formats of the intermediate data, the first one is preferred.
+    pub formats: Vec<ir::Format>,
+    /// The latest [version of the intermediate format](ir::Ir::FORMAT_VERSION) which the plugin
+    /// supports, the plugins which don't declare it only support version 1.
+    #[serde(default = "first_format_version")]
+    pub format_version: usize,
+    /// The options which are accepted by `--plugin-opt <name>=<value>`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<PluginOption>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Property, Deserialize, Serialize)]
+#[property(get(public))]
+pub struct PluginOption {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+}
+
+/// The request to a plugin, which is followed by the intermediate data.
+#[derive(Debug, Clone, PartialEq, Eq, Property, Deserialize, Serialize)]
+#[property(get(public))]
+pub struct Request {
+    pub protocol_version: usize,
+    /// The format of the intermediate data.
+    pub format: ir::Format,
+    /// The file name of the schema.
+    pub schema_file: String,
+    /// The directory which the files will be written into, or `None` if the output is the stdout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub options: BTreeMap<String, String>,
+}
+
+/// The response of a plugin.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Property, Deserialize, Serialize)]
+#[property(get(public))]
+pub struct Response {
+    #[serde(default)]
+    pub files: Vec<OutputFile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A generated file, the name is a relative path in the output directory.
+#[derive(Debug, Clone, PartialEq, Eq, Property, Deserialize, Serialize)]
+#[property(get(public))]
+pub struct OutputFile {
+    pub name: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Property, Deserialize, Serialize)]
+#[property(get(public))]
+pub struct Diagnostic {
+    #[property(get(type = "copy"))]
+    pub severity: Severity,
+    pub message: String,
+    /// The declaration which the diagnostic is about.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub declaration: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+const fn first_format_version() -> usize {
+    1
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+            Self::Note => write!(f, "note"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)?;
+        if let Some(ref name) = self.declaration {
+            write!(f, " (in `{}`)", name)?;
+        }
+        Ok(())
+    }
+}
+
+impl Capabilities {
+    pub fn new(formats: &[ir::Format]) -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            formats: formats.to_owned(),
+            format_version: ir::Ir::FORMAT_VERSION,
+            options: Vec::new(),
+        }
+    }
+
+    pub fn add_option(&mut self, name: &str, description: &str) -> &mut Self {
+        self.options.push(PluginOption {
+            name: name.to_owned(),
+            description: description.to_owned(),
+        });
+        self
+    }
+
+    pub fn from_json(data: &[u8]) -> Result<Self, String> {
+        serde_json::from_slice(data).map_err(|err| format!("invalid capabilities: {}", err))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+impl Request {
+    /// Writes the request and the intermediate data.
+    pub fn write<W: io::Write>(&self, writer: &mut W, data: &[u8]) -> io::Result<()> {
+        serde_json::to_writer(&mut *writer, self)?;
+        writer.write_all(b"\n")?;
+        writer.write_all(data)?;
+        writer.flush()
+    }
+
+    /// Reads the request and the intermediate data.
+    pub fn read<R: BufRead>(reader: &mut R) -> Result<(Self, Vec<u8>), String> {
+        let mut line = String::new();
+        reader
+            .read_line(&mut line)
+            .map_err(|err| format!("failed to read the request: {}", err))?;
+        let request: Self =
+            serde_json::from_str(&line).map_err(|err| format!("invalid request: {}", err))?;
+        if request.protocol_version > PROTOCOL_VERSION {
+            return Err(format!(
+                "unsupported protocol version: {} (the latest supported is {})",
+                request.protocol_version, PROTOCOL_VERSION
+            ));
+        }
+        let mut data = Vec::new();
+        reader
+            .read_to_end(&mut data)
+            .map_err(|err| format!("failed to read the intermediate data: {}", err))?;
+        Ok((request, data))
+    }
+}
+
+impl Response {
+    pub fn from_json(data: &[u8]) -> Result<Self, String> {
+        serde_json::from_slice(data).map_err(|err| format!("invalid response: {}", err))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn add_file(&mut self, name: &str, content: String) -> &mut Self {
+        self.files.push(OutputFile {
+            name: name.to_owned(),
+            content,
+        });
+        self
+    }
+
+    pub fn add_diagnostic(
+        &mut self,
+        severity: Severity,
+        message: &str,
+        declaration: Option<&str>,
+    ) -> &mut Self {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            message: message.to_owned(),
+            declaration: declaration.map(ToOwned::to_owned),
+        });
+        self
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fmt::Write as _, fs, io, process};
+
+    use super::{Capabilities, Request, Response, Severity, PROTOCOL_VERSION};
+    use crate::{ir, Compiler};
+
+    fn request(output_dir: Option<String>) -> Request {
+        Request {
+            protocol_version: PROTOCOL_VERSION,
+            format: ir::Format::JSON,
+            schema_file: "types.mol".to_owned(),
+            output_dir,
+            options: vec![("mode".to_owned(), "fast".to_owned())]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_request() {
+        let request = request(None);
+        let mut data = Vec::new();
+        request.write(&mut data, b"{\"x\": 1}\n").unwrap();
+        let (read, ir_data) = Request::read(&mut io::Cursor::new(data)).unwrap();
+        assert_eq!(read, request);
+        assert_eq!(ir_data, b"{\"x\": 1}\n");
+
+        let data = "{\"protocol_version\":99,\"format\":\"json\",\"schema_file\":\"a.mol\"}\n";
+        assert!(Request::read(&mut io::Cursor::new(data)).is_err());
+    }
+
+    #[test]
+    fn test_capabilities_format_version() {
+        let capabilities = Capabilities::new(&[ir::Format::JSON]);
+        assert_eq!(capabilities.format_version(), ir::Ir::FORMAT_VERSION);
+        let data = capabilities.to_json();
+        assert_eq!(Capabilities::from_json(data.as_bytes()), Ok(capabilities));
+
+        // the plugins which don't declare the format version only support version 1
+        let data = "{\"protocol_version\":1,\"formats\":[\"json\"]}";
+        let capabilities = Capabilities::from_json(data.as_bytes()).unwrap();
+        assert_eq!(capabilities.format_version(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_response() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let dir = tempfile::tempdir().unwrap();
+        let schema = dir.path().join("types.mol");
+        fs::write(&schema, "array Uint32 [byte; 4];\n").unwrap();
+        let mut response = Response::default();
+        response
+            .add_file("types/mod.rs", "pub mod uint32;\n".to_owned())
+            .add_file("types/uint32.rs", "// Uint32\n".to_owned())
+            .add_diagnostic(Severity::Warning, "no tests", Some("Uint32"));
+        let plugin = dir.path().join("plugin");
+        fs::write(
+            &plugin,
+            format!(
+                "#!/bin/sh\ncat > \"$0.request\"\ncat <<'EOF'\n{}\nEOF\n",
+                response.to_json()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&plugin, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let out_dir = dir.path().join("out");
+        fs::create_dir(&out_dir).unwrap();
+        let child = process::Command::new(&plugin)
+            .stdin(process::Stdio::piped())
+            .stdout(process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        Compiler::new()
+            .input_schema_file(&schema)
+            .output_plugin(child, request(Some(out_dir.display().to_string())))
+            .run()
+            .unwrap();
+        let sent = fs::read(dir.path().join("plugin.request")).unwrap();
+        let (sent, ir_data) = Request::read(&mut io::Cursor::new(sent)).unwrap();
+        assert_eq!(sent.options()["mode"], "fast");
+        assert!(ir::Format::JSON.recover(&ir_data).is_ok());
+        assert_eq!(
+            fs::read_to_string(out_dir.join("types").join("uint32.rs")).unwrap(),
+            "// Uint32\n"
+        );
+
+        // multiple files can't be written to the stdout
+        let child = process::Command::new(&plugin)
+            .stdin(process::Stdio::piped())
+            .stdout(process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let result = Compiler::new()
+            .input_schema_file(&schema)
+            .output_plugin(child, request(None))
+            .run();
+        assert!(result
+            .unwrap_err()
+            .contains("an output directory is required"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_writes_the_response_before_reading_the_request() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let dir = tempfile::tempdir().unwrap();
+        let schema = dir.path().join("types.mol");
+        // both the intermediate data and the response are larger than the pipe buffers
+        let mut schema_source = String::new();
+        for index in 0..2000 {
+            writeln!(schema_source, "array Array{} [byte; {}];", index, index + 1).unwrap();
+        }
+        fs::write(&schema, schema_source).unwrap();
+        let mut response = Response::default();
+        response.add_file("types.txt", "x".repeat(1 << 20));
+        let plugin = dir.path().join("plugin");
+        fs::write(
+            &plugin,
+            format!(
+                "#!/bin/sh\ncat <<'EOF'\n{}\nEOF\ncat > /dev/null\n",
+                response.to_json()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&plugin, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let out_dir = dir.path().join("out");
+        let child = process::Command::new(&plugin)
+            .stdin(process::Stdio::piped())
+            .stdout(process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        Compiler::new()
+            .input_schema_file(&schema)
+            .output_plugin(child, request(Some(out_dir.display().to_string())))
+            .run()
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(out_dir.join("types.txt")).unwrap().len(),
+            1 << 20
+        );
+    }
+}
@@ -0,0 +1,377 @@
+//! The configuration of a project, which is read from `molecule.toml`.
+//!
+//! ```toml
+//! # The schema files, a directory means all schema files in it.
+//! schemas = ["schemas"]
+//! # The imported schema files are searched in these directories, if they are not found
+//! # relative to the importing files.
+//! import-paths = ["vendor/schemas"]
+//!
+//! [lints]
+//! deny = ["naming"]
+//! allow = ["large-struct"]
+//! large-struct-size = 512
+//!
+//! [[targets]]
+//! language = "rust"
+//! output-dir = "src/generated"
+//! umbrella = "mod.rs"
+//! only = ["Transaction"]
+//!
+//! # The options of the generator, which are listed by `moleculec-<language> --capabilities`.
+//! [targets.options]
+//! crate-path = "ckb_types::molecule"
+//! derives = "PartialEq,Eq,Hash"
+//!
+//! [[targets]]
+//! language = "c"
+//! output-dir = "c/generated"
+//! umbrella = "schemas.h"
+//! options = { prefix = "Ckb" }
+//! ```
+//!
+//! The relative paths are relative to the directory of the configuration file.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    convert::TryFrom,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use property::Property;
+use serde::Deserialize;
+
+use crate::{
+    linter::{Lint, LintLevel, LintRule},
+    Compiler, GeneratorOptions, Language, Linter, Parser,
+};
+
+/// The file name of the configuration.
+pub const FILE_NAME: &str = "molecule.toml";
+
+#[derive(Debug, Clone, Property, Deserialize)]
+#[property(get(public))]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Project {
+    /// The directory of the configuration file.
+    #[serde(skip)]
+    root_dir: PathBuf,
+    schemas: Vec<PathBuf>,
+    #[serde(default)]
+    import_paths: Vec<PathBuf>,
+    #[serde(default)]
+    lints: Lints,
+    #[serde(default)]
+    targets: Vec<Target>,
+}
+
+/// The levels of the lint rules, the rules which are not listed are warned.
+#[derive(Debug, Clone, Default, Property, Deserialize)]
+#[property(get(public))]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Lints {
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    warn: Vec<String>,
+    #[serde(default)]
+    deny: Vec<String>,
+    #[property(get(type = "copy"))]
+    large_struct_size: Option<usize>,
+}
+
+/// The code of a language, which is generated into a directory.
+#[derive(Debug, Clone, Property, Deserialize)]
+#[property(get(public))]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Target {
+    language: String,
+    output_dir: PathBuf,
+    /// The file which ties the generated files together, e.g. `mod.rs` for Rust.
+    umbrella: Option<String>,
+    /// Only generate the code for these types and the types which they depend on.
+    #[serde(default)]
+    only: Vec<String>,
+    #[serde(default)]
+    options: BTreeMap<String, String>,
+}
+
+impl Project {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        let root_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        Self::from_toml(&source, root_dir)
+            .map_err(|err| format!("invalid {}: {}", path.display(), err))
+    }
+
+    /// Finds the configuration file in the directory or its ancestors.
+    pub fn find<P: AsRef<Path>>(dir: P) -> Option<PathBuf> {
+        dir.as_ref()
+            .ancestors()
+            .map(|dir| dir.join(FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    pub fn from_toml<P: AsRef<Path>>(source: &str, root_dir: P) -> Result<Self, String> {
+        let mut project: Self = toml::from_str(source).map_err(|err| err.to_string())?;
+        let root_dir = root_dir.as_ref();
+        for path in project
+            .schemas
+            .iter_mut()
+            .chain(project.import_paths.iter_mut())
+            .chain(
+                project
+                    .targets
+                    .iter_mut()
+                    .map(|target| &mut target.output_dir),
+            )
+        {
+            *path = root_dir.join(&path);
+        }
+        project.root_dir = root_dir.to_owned();
+        for target in &project.targets {
+            if let Some(ref umbrella) = target.umbrella {
+                if Path::new(umbrella).file_name() != Some(umbrella.as_ref()) {
+                    return Err(format!("umbrella `{}` should be a file name", umbrella));
+                }
+            }
+        }
+        project.linter()?;
+        Ok(project)
+    }
+
+    /// The schema files, the directories are replaced by the schema files in them.
+    pub fn schema_files(&self) -> Result<Vec<PathBuf>, String> {
+        collect_schema_files(&self.schemas)
+    }
+
+    pub fn linter(&self) -> Result<Linter, String> {
+        let mut linter = Linter::new();
+        for (names, level) in &[
+            (&self.lints.allow, LintLevel::Allow),
+            (&self.lints.warn, LintLevel::Warn),
+            (&self.lints.deny, LintLevel::Deny),
+        ] {
+            for name in names.iter() {
+                linter.set_level(LintRule::try_from(name.as_str())?, *level);
+            }
+        }
+        if let Some(size) = self.lints.large_struct_size {
+            linter.set_large_struct_size(size);
+        }
+        Ok(linter)
+    }
+
+    /// Lints the schema files and generates the code of all targets, which should be built-in
+    /// languages, returns the lints which are not denied.
+    ///
+    /// The targets of other languages require the plugins, which are run by `moleculec build`.
+    pub fn build(&self) -> Result<Vec<Lint>, String> {
+        let schema_files = self.schema_files()?;
+        let linter = self.linter()?;
+        let mut lints = Vec::new();
+        for schema_file in &schema_files {
+            let ast = Parser::try_parse_with_import_paths(schema_file, &self.import_paths)
+                .map_err(|err| err.to_string())?;
+            lints.extend(linter.lint(&ast));
+        }
+        if lints.iter().any(|lint| lint.level() == LintLevel::Deny) {
+            let denied = lints
+                .iter()
+                .filter(|lint| lint.level() == LintLevel::Deny)
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            return Err(denied.join("\n"));
+        }
+        for target in &self.targets {
+            let language = Language::try_from(target.language.as_str()).map_err(|_| {
+                format!(
+                    "language `{}` requires a plugin, use `moleculec build` instead",
+                    target.language
+                )
+            })?;
+            let mut options = GeneratorOptions::new();
+            for (name, value) in &target.options {
+                if !language.options().iter().any(|(option, _)| option == name) {
+                    return Err(format!(
+                        "the built-in generator of `{}` doesn't support option `{}`",
+                        target.language, name
+                    ));
+                }
+                options.set(name, value)?;
+            }
+            fs::create_dir_all(&target.output_dir).map_err(|err| {
+                format!("failed to create {}: {}", target.output_dir.display(), err)
+            })?;
+            let only_types = target.only.iter().map(String::as_str).collect::<Vec<_>>();
+            for schema_file in &schema_files {
+                Compiler::new()
+                    .generate_code(language)
+                    .generator_options(options.clone())
+                    .input_schema_file(schema_file)
+                    .import_paths(&self.import_paths)
+                    .only_types(&only_types)
+                    .output_dir(&target.output_dir)
+                    .run()?;
+            }
+            if let Some(ref umbrella) = target.umbrella {
+                write_umbrella(language, umbrella, &schema_files, &target.output_dir)?;
+            }
+        }
+        Ok(lints)
+    }
+}
+
+/// Collects the schema files, a directory is replaced by all schema files in it.
+///
+/// The schema files should have different names, since the generated files are named after them.
+pub fn collect_schema_files<P: AsRef<Path>>(paths: &[P]) -> Result<Vec<PathBuf>, String> {
+    let mut schema_files = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        if !path.is_dir() {
+            if !path.is_file() {
+                return Err(format!("schema file {} is not found", path.display()));
+            }
+            schema_files.push(path.to_owned());
+            continue;
+        }
+        let mut files = fs::read_dir(path)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        files.retain(|file| file.is_file() && file.extension() == Some("mol".as_ref()));
+        if files.is_empty() {
+            return Err(format!("{} has no schema files", path.display()));
+        }
+        files.sort();
+        schema_files.extend(files);
+    }
+    let mut names = HashSet::new();
+    for schema_file in &schema_files {
+        if !names.insert(schema_file.file_stem()) {
+            return Err(format!(
+                "more than one schema file is named {}",
+                schema_file.file_name().unwrap().to_string_lossy()
+            ));
+        }
+    }
+    Ok(schema_files)
+}
+
+/// The names of the modules, which are the names of the schema files without the extension.
+pub fn module_names(schema_files: &[PathBuf]) -> Vec<&str> {
+    schema_files
+        .iter()
+        .filter_map(|schema_file| schema_file.file_stem().and_then(|name| name.to_str()))
+        .collect()
+}
+
+/// Writes the umbrella file `file_name` into the output directory, which ties the generated
+/// files of the schema files together.
+pub fn write_umbrella(
+    language: Language,
+    file_name: &str,
+    schema_files: &[PathBuf],
+    output_dir: &Path,
+) -> Result<(), String> {
+    let modules = module_names(schema_files);
+    let name = Path::new(file_name)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let mut content = Vec::new();
+    language
+        .generate_umbrella(&mut content, name, &modules)
+        .map_err(|err| format!("failed to generate the umbrella file: {}", err))?;
+    let umbrella_file = output_dir.join(file_name);
+    fs::write(&umbrella_file, content)
+        .map_err(|err| format!("failed to write {}: {}", umbrella_file.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{Project, FILE_NAME};
+
+    #[test]
+    fn test_project() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("schemas")).unwrap();
+        fs::create_dir_all(dir.path().join("vendor")).unwrap();
+        fs::write(
+            dir.path().join("vendor").join("basic.mol"),
+            "array Uint32 [byte; 4];\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("schemas").join("types.mol"),
+            "import basic;\ntable Header { number: Uint32, }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("schemas").join("blockchain.mol"),
+            "import types;\nvector Headers <Header>;\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(FILE_NAME),
+            r#"
+schemas = ["schemas"]
+import-paths = ["vendor"]
+
+[lints]
+deny = ["naming"]
+
+[[targets]]
+language = "rust"
+output-dir = "src/generated"
+umbrella = "mod.rs"
+"#,
+        )
+        .unwrap();
+
+        let config_file = Project::find(dir.path().join("schemas")).unwrap();
+        let project = Project::load(config_file).unwrap();
+        assert_eq!(
+            project.targets()[0].output_dir(),
+            &dir.path().join("src/generated")
+        );
+        let lints = project.build().unwrap();
+        assert!(lints.is_empty());
+        let generated = dir.path().join("src").join("generated");
+        for name in &["blockchain.rs", "types.rs", "mod.rs"] {
+            assert!(generated.join(name).is_file());
+        }
+        let umbrella = fs::read_to_string(generated.join("mod.rs")).unwrap();
+        assert!(umbrella.ends_with("pub mod blockchain;\npub mod types;\n"));
+
+        fs::write(
+            dir.path().join("schemas").join("names.mol"),
+            "array bad_name [byte; 2];\n",
+        )
+        .unwrap();
+        let err = project.build().unwrap_err();
+        assert!(err.contains("error[naming]: `bad_name`"));
+
+        // the errors of the schema files are returned, instead of panics
+        fs::write(
+            dir.path().join("schemas").join("names.mol"),
+            "table Bad { a: Unknown, }\n",
+        )
+        .unwrap();
+        let err = project.build().unwrap_err();
+        assert!(err.contains("names.mol"), "{}", err);
+
+        assert!(Project::from_toml("schemas = []\nunknown = 1\n", dir.path()).is_err());
+        let source = "schemas = []\n[lints]\ndeny = [\"no-such-rule\"]\n";
+        assert!(Project::from_toml(source, dir.path()).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use std::{collections::HashMap, fs, io, path, sync::Arc};
+
+use case::CaseExt as _;
+use minijinja::{context, Environment, Error, ErrorKind, Value};
+
+use crate::{
+    ast::{self, HasName as _},
+    generator::Generator,
+    ir, VERSION,
+};
+
+/// The extensions of template files, which are removed from the names of the output files.
+const TEMPLATE_EXTENSIONS: &[&str] = &["j2", "jinja"];
+
+/// A generator which renders a [MiniJinja](https://docs.rs/minijinja) template.
+///
+/// The context of the template is the intermediate data with the layouts, so the variables are
+/// `format_version`, `syntax_version`, `namespace`, `imports`, `declarations` and
+/// `dependency_order`, and `version` is the version of molecule.
+///
+/// The filters `snake_case`, `camel_case`, `lower_camel_case`, `shouty_snake_case` and
+/// `kebab_case` convert the case of names. The function `decl(name)` looks up a declaration,
+/// and `size_of(name)` returns the fixed size of a type, or `none` if it's dynamic.
+///
+/// The extension of the output is the name of the template without `.j2` or `.jinja`, for
+/// example, `types.mol` is rendered by `columns.sql.j2` into `types.columns.sql`.
+#[derive(Clone)]
+pub struct TemplateGenerator {
+    name: String,
+    source: String,
+    extension: String,
+}
+
+impl TemplateGenerator {
+    pub fn new(name: &str, source: &str) -> Result<Self, String> {
+        // check the syntax before the template is rendered
+        let mut env = Environment::new();
+        env.add_template(name, source)
+            .map_err(|err| format!("invalid template `{}`: {:#}", name, err))?;
+        let extension = match name.rsplit_once('.') {
+            Some((stem, extension)) if TEMPLATE_EXTENSIONS.contains(&extension) => stem,
+            _ => name,
+        };
+        Ok(Self {
+            name: name.to_owned(),
+            source: source.to_owned(),
+            extension: extension.to_owned(),
+        })
+    }
+
+    pub fn from_file<P: AsRef<path::Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| format!("invalid template path: {}", path.display()))?;
+        Self::new(name, &source)
+    }
+
+    fn environment(&self, ir: &ir::Ir, ast: &ast::Ast) -> Environment {
+        let mut env = Environment::new();
+        env.set_keep_trailing_newline(true);
+        env.add_filter("snake_case", |value: &str| value.to_snake());
+        env.add_filter("camel_case", |value: &str| value.to_snake().to_camel());
+        env.add_filter("lower_camel_case", |value: &str| {
+            value.to_snake().to_camel_lowercase()
+        });
+        env.add_filter("shouty_snake_case", |value: &str| {
+            value.to_snake().to_uppercase()
+        });
+        env.add_filter("kebab_case", |value: &str| value.to_snake().to_dashed());
+
+        let decls = Arc::new(
+            ir.decls()
+                .iter()
+                .map(|decl| (decl.name().to_owned(), Value::from_serialize(decl)))
+                .collect::<HashMap<_, _>>(),
+        );
+        env.add_function("decl", move |name: &str| {
+            decls.get(name).cloned().ok_or_else(|| not_found(name))
+        });
+        let mut sizes = ast
+            .decls()
+            .iter()
+            .map(|decl| (decl.name().to_owned(), decl.total_size()))
+            .collect::<HashMap<_, _>>();
+        sizes.insert("byte".to_owned(), Some(1));
+        env.add_function("size_of", move |name: &str| {
+            sizes.get(name).copied().ok_or_else(|| not_found(name))
+        });
+        env
+    }
+}
+
+impl Generator for TemplateGenerator {
+    fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    fn generate(&self, writer: &mut dyn io::Write, ast: &ast::Ast) -> io::Result<()> {
+        let ir = ir::to_ir_with_layouts(ast);
+        let mut env = self.environment(&ir, ast);
+        let render_error = |err: Error| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("failed to render the template `{}`: {:#}", self.name, err),
+            )
+        };
+        env.add_template(&self.name, &self.source)
+            .map_err(render_error)?;
+        let context = context! {
+            version => VERSION,
+            ..Value::from_serialize(&ir)
+        };
+        let template = env.get_template(&self.name).map_err(render_error)?;
+        let output = template.render(context).map_err(render_error)?;
+        writer.write_all(output.as_bytes())
+    }
+}
+
+fn not_found(name: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidOperation,
+        format!("the type `{}` is not found", name),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use super::TemplateGenerator;
+    use crate::{Generator as _, Parser};
+
+    #[test]
+    fn test_template() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(
+            b"array Uint32 [byte; 4];
+struct BlockHeader { number: Uint32, flag: byte, }
+vector Uint32Vec <Uint32>;
+",
+        )
+        .unwrap();
+        let ast = Parser::parse(&file.path());
+        let template = "\
+{%- for decl in declarations if decl.type == \"struct\" -%}
+CREATE TABLE {{ decl.name | snake_case }} (
+{%- for field in decl.layout.fields %}
+    {{ field.name | shouty_snake_case }} BINARY({{ field.size }}) -- offset {{ field.offset }}
+{%- endfor %}
+);
+{% endfor -%}
+-- {{ decl(\"Uint32Vec\").item | lower_camel_case }}: {{ size_of(\"Uint32\") }}, {{ size_of(\"Uint32Vec\") or \"dynamic\" }}
+";
+        let generator = TemplateGenerator::new("columns.sql.j2", template).unwrap();
+        assert_eq!(generator.extension(), "columns.sql");
+        let mut output = Vec::new();
+        generator.generate(&mut output, &ast).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "CREATE TABLE block_header (
+    NUMBER BINARY(4) -- offset 0
+    FLAG BINARY(1) -- offset 4
+);
+-- uint32: 4, dynamic
+"
+        );
+
+        let generator =
+            TemplateGenerator::new("missing.txt", "{{ size_of(\"Missing\") }}").unwrap();
+        assert_eq!(generator.extension(), "missing.txt");
+        let err = generator.generate(&mut Vec::new(), &ast).unwrap_err();
+        assert!(err.to_string().contains("the type `Missing` is not found"));
+
+        assert!(TemplateGenerator::new("invalid.j2", "{% for %}").is_err());
+    }
+}
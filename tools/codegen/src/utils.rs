@@ -1,4 +1,7 @@
-use std::path::Path;
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
 use pest::error::Error as PestError;
 
@@ -10,16 +13,28 @@ pub(crate) trait PairsUtils {
     fn next_item(&mut self) -> ast::ItemDecl;
     #[allow(dead_code)]
     fn next_items(&mut self) -> Vec<ast::ItemDecl>;
-    fn next_custom_union_items(&mut self) -> Vec<ast::CustomUnionItemDecl>;
-    fn next_fields(&mut self) -> Vec<ast::FieldDecl>;
+    fn next_custom_union_items(&mut self) -> (Vec<ast::CustomUnionItemDecl>, Vec<usize>);
+    fn next_fields(&mut self, ast: &mut ast::Ast, imported_depth: usize) -> Vec<ast::FieldDecl>;
     fn next_import<P: AsRef<Path>>(
         &mut self,
         imported_base: &P,
         imported_depth: usize,
+        range: Range<usize>,
     ) -> ast::ImportStmt;
     fn next_should_be_none(self);
 }
 
 pub(crate) trait ParserUtils {
-    fn preprocess<P: AsRef<Path>>(path: &P) -> Result<ast::Ast, Box<PestError<parser::Rule>>>;
+    fn preprocess<P: AsRef<Path>>(path: &P) -> Result<ast::Ast, Box<PestError<parser::Rule>>> {
+        Self::preprocess_source(path, &|_| None, &[])
+    }
+    /// Preprocesses the schema file, the imported files are also searched in `import_paths`.
+    ///
+    /// The content of each file, including the imported files, is provided by `sources` if it
+    /// returns `Some`, otherwise it's read from the disk.
+    fn preprocess_source<P: AsRef<Path>>(
+        path: &P,
+        sources: &dyn Fn(&Path) -> Option<String>,
+        import_paths: &[PathBuf],
+    ) -> Result<ast::Ast, Box<PestError<parser::Rule>>>;
 }
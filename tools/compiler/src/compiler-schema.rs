@@ -0,0 +1,5 @@
+use molecule_codegen::{IntermediateFormat, Language};
+
+fn main() {
+    moleculec::build_commandline(Language::Schema, IntermediateFormat::Molecule).execute();
+}
@@ -1,20 +1,92 @@
-use std::{convert::TryFrom, process, str};
+use std::{convert::TryFrom, fs, panic, path, process, str};
 
-use molecule_codegen::{Compiler, IntermediateFormat};
+use molecule_codegen::{
+    ast::{Ast, HasName as _},
+    ir::Ir,
+    linter::LintLevel,
+    plugin::{Capabilities, Request, PROTOCOL_VERSION},
+    project, Bundler, Compiler, Formatter, IntermediateFormat, Linter, Parser,
+};
 
 pub(crate) mod config {
-    use std::{convert::TryFrom, path::PathBuf, process};
+    use std::{
+        collections::BTreeMap,
+        convert::TryFrom,
+        env,
+        path::{Path, PathBuf},
+        process,
+    };
 
-    use molecule_codegen::IntermediateFormat;
+    use molecule_codegen::{
+        linter::{LintLevel, LintRule},
+        project::{self, Project},
+        IntermediateFormat, Language, Linter, TemplateGenerator,
+    };
 
     pub(crate) enum OutputConfig {
         Plugin(PathBuf),
         Output(IntermediateFormat),
+        Templates(Vec<TemplateGenerator>),
+    }
+
+    pub(crate) struct ExplainConfig {
+        pub(crate) type_name: Option<String>,
+        pub(crate) json: bool,
+    }
+
+    pub(crate) enum AppConfig {
+        Compile {
+            schema_files: Vec<PathBuf>,
+            output_config: OutputConfig,
+            compile_config: CompileConfig,
+        },
+        Explain {
+            schema_file: PathBuf,
+            explain_config: ExplainConfig,
+        },
+        Format {
+            schema_files: Vec<PathBuf>,
+            check: bool,
+        },
+        Lint {
+            schema_file: PathBuf,
+            lint_config: LintConfig,
+        },
+        Bundle {
+            schema_file: PathBuf,
+            bundle_config: BundleConfig,
+        },
+        Build {
+            schema_files: Vec<PathBuf>,
+            import_paths: Vec<PathBuf>,
+            linter: Linter,
+            targets: Vec<(OutputConfig, CompileConfig)>,
+        },
+        IrSchema,
     }
 
-    pub(crate) struct AppConfig {
-        pub(crate) schema_file: PathBuf,
-        pub(crate) output_config: OutputConfig,
+    pub(crate) struct CompileConfig {
+        pub(crate) only_types: Vec<String>,
+        pub(crate) ir_layouts: bool,
+        pub(crate) plugin_options: BTreeMap<String, String>,
+        pub(crate) output_dir: Option<PathBuf>,
+        pub(crate) umbrella: Option<UmbrellaConfig>,
+        pub(crate) import_paths: Vec<PathBuf>,
+    }
+
+    pub(crate) struct UmbrellaConfig {
+        pub(crate) file_name: String,
+        pub(crate) language: Language,
+    }
+
+    pub(crate) struct LintConfig {
+        pub(crate) levels: Vec<(LintRule, LintLevel)>,
+        pub(crate) large_struct_size: Option<usize>,
+    }
+
+    pub(crate) struct BundleConfig {
+        pub(crate) roots: Vec<String>,
+        pub(crate) output: Option<PathBuf>,
     }
 
     pub(crate) fn build_commandline() -> AppConfig {
@@ -26,9 +98,142 @@ pub(crate) mod config {
             .arg(
                 clap::Arg::new("schema-file")
                     .long("schema-file")
-                    .help("Provide a schema file to compile.")
+                    .help("Provide a schema file to compile, or a directory to compile all schema files in it.\
+                    \nIt could be specified multiple times, an output directory is required when there are multiple schema files.")
                     .required(true)
-                    .action(clap::ArgAction::Set),
+                    .action(clap::ArgAction::Append),
+            )
+            .subcommand_negates_reqs(true)
+            .args_conflicts_with_subcommands(true)
+            .subcommand(
+                clap::Command::new("explain")
+                    .about("Explain the layouts of the declarations in a schema file.")
+                    .arg(
+                        clap::Arg::new("schema")
+                            .long("schema")
+                            .visible_alias("schema-file")
+                            .help("Provide a schema file to explain.")
+                            .required(true)
+                            .action(clap::ArgAction::Set),
+                    )
+                    .arg(
+                        clap::Arg::new("type")
+                            .long("type")
+                            .help("Only explain the specified type.")
+                            .action(clap::ArgAction::Set),
+                    )
+                    .arg(
+                        clap::Arg::new("format")
+                            .long("format")
+                            .help("Specify a format for the output.")
+                            .value_parser(["text", "json"])
+                            .default_value("text")
+                            .action(clap::ArgAction::Set),
+                    ),
+            )
+            .subcommand(
+                clap::Command::new("fmt")
+                    .about("Format schema files in place.")
+                    .arg(
+                        clap::Arg::new("check")
+                            .long("check")
+                            .help("Don't write the files, list the files which are not formatted and exit with 1 if any.")
+                            .action(clap::ArgAction::SetTrue),
+                    )
+                    .arg(
+                        clap::Arg::new("schema-files")
+                            .help("Provide schema files to format.")
+                            .required(true)
+                            .num_args(1..)
+                            .action(clap::ArgAction::Append),
+                    ),
+            )
+            .subcommand(
+                clap::Command::new("lint")
+                    .about("Check the declarations in a schema file by the lint rules.")
+                    .after_help(format!(
+                        "Lint rules: {}.",
+                        LintRule::ALL
+                            .iter()
+                            .map(|rule| rule.name())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                    .arg(
+                        clap::Arg::new("schema")
+                            .long("schema")
+                            .visible_alias("schema-file")
+                            .help("Provide a schema file to check.")
+                            .required(true)
+                            .action(clap::ArgAction::Set),
+                    )
+                    .arg(
+                        clap::Arg::new("allow")
+                            .long("allow")
+                            .help("Allow a lint rule.")
+                            .action(clap::ArgAction::Append),
+                    )
+                    .arg(
+                        clap::Arg::new("warn")
+                            .long("warn")
+                            .help("Warn for a lint rule, all lint rules are warned by default.")
+                            .action(clap::ArgAction::Append),
+                    )
+                    .arg(
+                        clap::Arg::new("deny")
+                            .long("deny")
+                            .help("Deny a lint rule, exit with 1 if it's violated.")
+                            .action(clap::ArgAction::Append),
+                    )
+                    .arg(
+                        clap::Arg::new("large-struct-size")
+                            .long("large-struct-size")
+                            .help("Specify the size, in bytes, of the largest struct which is not reported by \"large-struct\".")
+                            .value_parser(clap::value_parser!(usize))
+                            .action(clap::ArgAction::Set),
+                    ),
+            )
+            .subcommand(
+                clap::Command::new("bundle")
+                    .about("Bundle a schema file and all schema files which are imported by it into a single schema file, which has no imports.")
+                    .arg(
+                        clap::Arg::new("schema-file")
+                            .help("Provide a schema file to bundle.")
+                            .required(true)
+                            .action(clap::ArgAction::Set),
+                    )
+                    .arg(
+                        clap::Arg::new("output")
+                            .long("output")
+                            .short('o')
+                            .help("Write the bundled schema to the file, instead of the stdout.")
+                            .action(clap::ArgAction::Set),
+                    )
+                    .arg(
+                        clap::Arg::new("root")
+                            .long("root")
+                            .help("Only keep the types which are reachable from the root types.")
+                            .value_delimiter(',')
+                            .action(clap::ArgAction::Append),
+                    ),
+            )
+            .subcommand(
+                clap::Command::new("build")
+                    .about(format!("Lint the schema files and generate the code of all targets, which are configured in {}.", project::FILE_NAME))
+                    .arg(
+                        clap::Arg::new("config")
+                            .long("config")
+                            .help("Provide the configuration file, which is searched in the current directory and its ancestors by default.\
+                            \nThe relative paths in it are relative to its directory.")
+                            .action(clap::ArgAction::Set),
+                    )
+                    .arg(
+                        clap::Arg::new("target")
+                            .long("target")
+                            .help("Only generate the code of the targets of the languages.")
+                            .value_delimiter(',')
+                            .action(clap::ArgAction::Append),
+                    ),
             )
             .arg(
                 clap::Arg::new("language")
@@ -36,35 +241,304 @@ pub(crate) mod config {
                     .help("Specify a language, then generate source code for the specified language and output the generated code to the stdout.\
                     \nThis parameter actually specifies a plugin to use. It should be a simple word, and the compiler will search for a plugin called \"moleculec-<language>\" in `$PATH`.\
                     \nIf \"<language>\" is \"-\", the compiler will dump the intermediate data of schema to standard output.")
-                    .required(true)
+                    .required_unless_present("template")
                     .action(clap::ArgAction::Set),
             )
+            .arg(
+                clap::Arg::new("template")
+                    .long("template")
+                    .help("Render a template instead of running a plugin, the output file is named after the schema file and the template without \".j2\" or \".jinja\".\
+                    \nThe template is rendered by MiniJinja with the intermediate data, which includes the layouts.")
+                    .conflicts_with_all(["language", "format", "plugin-opt"])
+                    .action(clap::ArgAction::Append),
+            )
             .arg(
                 clap::Arg::new("format")
                     .long("format")
                     .help(r#"If "<language>" is "-", this parameter is used to specify a format for the intermediate data."#)
-                    .value_parser(["json", "yaml"])
+                    .value_parser(["json", "yaml", "molecule"])
                     .action(clap::ArgAction::Set),
             )
+            .arg(
+                clap::Arg::new("only")
+                    .long("only")
+                    .help("Only generate the code for the specified types and the types which they depend on, the imported types which are required are generated with them.")
+                    .value_delimiter(',')
+                    .action(clap::ArgAction::Append),
+            )
+            .arg(
+                clap::Arg::new("plugin-opt")
+                    .long("plugin-opt")
+                    .help("Pass an option to the plugin, in the format \"<name>=<value>\". The options which are supported are listed by `moleculec-<language> --capabilities`.")
+                    .action(clap::ArgAction::Append),
+            )
+            .arg(
+                clap::Arg::new("output-dir")
+                    .long("output-dir")
+                    .help("Write the generated files into the directory, instead of the stdout.")
+                    .action(clap::ArgAction::Set),
+            )
+            .arg(
+                clap::Arg::new("import-path")
+                    .long("import-path")
+                    .help("Search the imported schema files in the directory, if they are not found relative to the importing files.")
+                    .action(clap::ArgAction::Append),
+            )
+            .arg(
+                clap::Arg::new("umbrella")
+                    .long("umbrella")
+                    .help("Also write an umbrella file into the output directory, which ties the generated files together, e.g. \"mod.rs\" for Rust or a header for C.")
+                    .requires("output-dir")
+                    .conflicts_with("template")
+                    .action(clap::ArgAction::Set),
+            )
+            .arg(
+                clap::Arg::new("ir-schema")
+                    .long("ir-schema")
+                    .help("Output the JSON Schema of the intermediate data, which the plugins receive.")
+                    .exclusive(true)
+                    .action(clap::ArgAction::SetTrue),
+            )
+            .arg(
+                clap::Arg::new("ir-layouts")
+                    .long("ir-layouts")
+                    .help("Include the layout of each declaration and the dependency order in the intermediate data.")
+                    .action(clap::ArgAction::SetTrue),
+            )
             .get_matches();
         AppConfig::from(matches)
     }
 
+    fn schema_file_from(matches: &clap::ArgMatches, id: &str) -> PathBuf {
+        let path = matches.get_one::<String>(id).unwrap();
+        checked_schema_file(id, path)
+    }
+
+    fn checked_schema_file(id: &str, path: &str) -> PathBuf {
+        let schema_file = PathBuf::from(path);
+        if !schema_file.as_path().is_file() {
+            eprintln!(
+                "Error: {} [{}] should be a file",
+                id,
+                schema_file.to_str().unwrap()
+            );
+            process::exit(1);
+        }
+        schema_file
+    }
+
+    /// Collects the schema files, a directory is replaced by all schema files in it.
+    fn schema_files_from(matches: &clap::ArgMatches, id: &str) -> Vec<PathBuf> {
+        let paths = matches
+            .get_many::<String>(id)
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        project::collect_schema_files(&paths).unwrap_or_else(|error| {
+            eprintln!("Error: {}", error);
+            process::exit(1);
+        })
+    }
+
+    fn plugin_config(language: &str) -> OutputConfig {
+        let plugin_name = format!("moleculec-{}", language);
+        if let Ok(plugin) = which::which(&plugin_name) {
+            OutputConfig::Plugin(plugin)
+        } else {
+            eprintln!("Error: no such plugin (should be '{}')", plugin_name);
+            process::exit(1);
+        }
+    }
+
+    fn umbrella_config(
+        file_name: &str,
+        language: &str,
+        schema_files: &[PathBuf],
+    ) -> UmbrellaConfig {
+        let language = Language::try_from(language).unwrap_or_else(|_| {
+            eprintln!(
+                "Error: an umbrella file is not supported for language [{}]",
+                language
+            );
+            process::exit(1);
+        });
+        let is_output = schema_files.iter().any(|schema_file| {
+            schema_file.with_extension(language.extension()).file_name() == Some(file_name.as_ref())
+        });
+        if is_output || Path::new(file_name).file_name() != Some(file_name.as_ref()) {
+            eprintln!("Error: umbrella [{}] should be a new file name", file_name);
+            process::exit(1);
+        }
+        UmbrellaConfig {
+            file_name: file_name.to_owned(),
+            language,
+        }
+    }
+
+    fn build_config_from(matches: &clap::ArgMatches) -> AppConfig {
+        let config_file = matches
+            .get_one::<String>("config")
+            .map(PathBuf::from)
+            .or_else(|| Project::find(env::current_dir().ok()?))
+            .unwrap_or_else(|| {
+                eprintln!("Error: {} is not found", project::FILE_NAME);
+                process::exit(1);
+            });
+        let project = Project::load(&config_file).unwrap_or_else(|error| {
+            eprintln!("Error: {}", error);
+            process::exit(1);
+        });
+        let schema_files = project.schema_files().unwrap_or_else(|error| {
+            eprintln!("Error: {}", error);
+            process::exit(1);
+        });
+        let linter = project.linter().unwrap_or_else(|error| {
+            eprintln!("Error: {}", error);
+            process::exit(1);
+        });
+        let languages = matches
+            .get_many::<String>("target")
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        for language in &languages {
+            if !project
+                .targets()
+                .iter()
+                .any(|target| target.language() == language.as_str())
+            {
+                eprintln!("Error: no such target [{}] in the project", language);
+                process::exit(1);
+            }
+        }
+        let targets = project
+            .targets()
+            .iter()
+            .filter(|target| {
+                languages.is_empty()
+                    || languages
+                        .iter()
+                        .any(|lang| lang.as_str() == target.language())
+            })
+            .map(|target| {
+                let output_config = plugin_config(target.language());
+                let umbrella = target
+                    .umbrella()
+                    .map(|file_name| umbrella_config(file_name, target.language(), &schema_files));
+                let compile_config = CompileConfig {
+                    only_types: target.only().to_owned(),
+                    ir_layouts: false,
+                    plugin_options: target.options().clone(),
+                    output_dir: Some(target.output_dir().to_owned()),
+                    umbrella,
+                    import_paths: project.import_paths().to_owned(),
+                };
+                (output_config, compile_config)
+            })
+            .collect();
+        AppConfig::Build {
+            schema_files,
+            import_paths: project.import_paths().to_owned(),
+            linter,
+            targets,
+        }
+    }
+
     impl From<clap::ArgMatches> for AppConfig {
         fn from(matches: clap::ArgMatches) -> Self {
-            let schema_file = {
-                let path = matches.get_one::<String>("schema-file").unwrap();
-                PathBuf::from(path)
-            };
-            let language = matches.get_one::<String>("language").unwrap();
-            if !schema_file.as_path().is_file() {
-                eprintln!(
-                    "Error: schema-file [{}] should be a file",
-                    schema_file.to_str().unwrap()
-                );
-                process::exit(1);
+            match matches.subcommand() {
+                Some(("explain", matches)) => {
+                    let schema_file = schema_file_from(matches, "schema");
+                    let type_name = matches.get_one::<String>("type").cloned();
+                    let json = matches.get_one::<String>("format").unwrap() == "json";
+                    let explain_config = ExplainConfig { type_name, json };
+                    return Self::Explain {
+                        schema_file,
+                        explain_config,
+                    };
+                }
+                Some(("fmt", matches)) => {
+                    let schema_files = matches
+                        .get_many::<String>("schema-files")
+                        .unwrap()
+                        .map(|path| checked_schema_file("schema-file", path))
+                        .collect();
+                    let check = matches.get_flag("check");
+                    return Self::Format {
+                        schema_files,
+                        check,
+                    };
+                }
+                Some(("lint", matches)) => {
+                    let schema_file = schema_file_from(matches, "schema");
+                    let mut levels = Vec::new();
+                    for (id, level) in &[
+                        ("allow", LintLevel::Allow),
+                        ("warn", LintLevel::Warn),
+                        ("deny", LintLevel::Deny),
+                    ] {
+                        for name in matches.get_many::<String>(id).into_iter().flatten() {
+                            match LintRule::try_from(name.as_str()) {
+                                Ok(rule) => levels.push((rule, *level)),
+                                Err(error) => {
+                                    eprintln!("Error: {}", error);
+                                    process::exit(1);
+                                }
+                            }
+                        }
+                    }
+                    let large_struct_size = matches.get_one::<usize>("large-struct-size").copied();
+                    let lint_config = LintConfig {
+                        levels,
+                        large_struct_size,
+                    };
+                    return Self::Lint {
+                        schema_file,
+                        lint_config,
+                    };
+                }
+                Some(("bundle", matches)) => {
+                    let schema_file = schema_file_from(matches, "schema-file");
+                    let roots = matches
+                        .get_many::<String>("root")
+                        .into_iter()
+                        .flatten()
+                        .cloned()
+                        .collect();
+                    let output = matches.get_one::<String>("output").map(PathBuf::from);
+                    let bundle_config = BundleConfig { roots, output };
+                    return Self::Bundle {
+                        schema_file,
+                        bundle_config,
+                    };
+                }
+                Some(("build", matches)) => {
+                    return build_config_from(matches);
+                }
+                _ => {}
+            }
+            if matches.get_flag("ir-schema") {
+                return Self::IrSchema;
             }
-            let output_config = if language == "-" {
+            let schema_files = schema_files_from(&matches, "schema-file");
+            let templates = matches
+                .get_many::<String>("template")
+                .into_iter()
+                .flatten()
+                .map(|path| {
+                    TemplateGenerator::from_file(path).unwrap_or_else(|error| {
+                        eprintln!("Error: {}", error);
+                        process::exit(1);
+                    })
+                })
+                .collect::<Vec<_>>();
+            let language = matches
+                .get_one::<String>("language")
+                .map(String::as_str)
+                .unwrap_or_default();
+            let output_config = if !templates.is_empty() {
+                OutputConfig::Templates(templates)
+            } else if language == "-" {
                 let format = matches.get_one::<String>("format").unwrap_or_else(|| {
                     eprintln!("Error: since language is \"-\", a format is required");
                     process::exit(1);
@@ -81,58 +555,479 @@ pub(crate) mod config {
                     eprintln!("Error: since language is not \"-\", don't specify format");
                     process::exit(1);
                 }
-                let plugin_name = format!("moleculec-{}", language);
-                if let Ok(plugin) = which::which(&plugin_name) {
-                    OutputConfig::Plugin(plugin)
+                plugin_config(language)
+            };
+            let only_types = matches
+                .get_many::<String>("only")
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect();
+            let ir_layouts = matches.get_flag("ir-layouts");
+            let mut plugin_options = BTreeMap::new();
+            for option in matches
+                .get_many::<String>("plugin-opt")
+                .into_iter()
+                .flatten()
+            {
+                if let Some((name, value)) = option.split_once('=') {
+                    plugin_options.insert(name.to_owned(), value.to_owned());
                 } else {
-                    eprintln!("Error: no such plugin (should be '{}')", plugin_name);
+                    eprintln!(
+                        "Error: plugin option [{}] should be in the format \"<name>=<value>\"",
+                        option
+                    );
+                    process::exit(1);
+                }
+            }
+            if !plugin_options.is_empty() && language == "-" {
+                eprintln!("Error: since language is \"-\", don't specify plugin options");
+                process::exit(1);
+            }
+            let output_dir = matches.get_one::<String>("output-dir").map(PathBuf::from);
+            if let Some(ref output_dir) = output_dir {
+                if output_dir.exists() && !output_dir.is_dir() {
+                    eprintln!(
+                        "Error: output-dir [{}] should be a directory",
+                        output_dir.display()
+                    );
                     process::exit(1);
                 }
+            } else if schema_files.len() > 1 {
+                eprintln!(
+                    "Error: since there are multiple schema files, an output-dir is required"
+                );
+                process::exit(1);
+            }
+            let umbrella = matches
+                .get_one::<String>("umbrella")
+                .map(|file_name| umbrella_config(file_name, language, &schema_files));
+            let import_paths = matches
+                .get_many::<String>("import-path")
+                .into_iter()
+                .flatten()
+                .map(PathBuf::from)
+                .collect();
+            let compile_config = CompileConfig {
+                only_types,
+                ir_layouts,
+                plugin_options,
+                output_dir,
+                umbrella,
+                import_paths,
             };
-            Self {
-                schema_file,
+            Self::Compile {
+                schema_files,
                 output_config,
+                compile_config,
             }
         }
     }
 }
 
+/// Parses the schema file, the errors in it are reported and the process exits.
+fn parse_schema_file(schema_file: &path::Path) -> Ast {
+    catch_panic(|| Ok(Parser::parse(&schema_file))).unwrap_or_else(|err| {
+        eprintln!("Error: [{}] {}", schema_file.display(), err);
+        process::exit(1);
+    })
+}
+
+fn explain(schema_file: &path::Path, config: &config::ExplainConfig) {
+    let ast = parse_schema_file(schema_file);
+    let layouts = if let Some(ref type_name) = config.type_name {
+        if let Some(decl) = ast.decls().iter().find(|decl| decl.name() == type_name) {
+            vec![decl.layout()]
+        } else {
+            eprintln!("Error: no such type [{}] in the schema file", type_name);
+            process::exit(1);
+        }
+    } else {
+        ast.layouts()
+    };
+    if config.json {
+        let json = serde_json::to_string_pretty(&layouts).unwrap_or_else(|err| {
+            eprintln!("Error: failed to serialize the layouts: {}", err);
+            process::exit(1);
+        });
+        println!("{}", json);
+    } else {
+        for (index, layout) in layouts.iter().enumerate() {
+            if index > 0 {
+                println!();
+            }
+            print!("{}", layout);
+        }
+    }
+}
+
+fn format(schema_files: &[path::PathBuf], check: bool) {
+    let mut unformatted = false;
+    for schema_file in schema_files {
+        let source = fs::read_to_string(schema_file).unwrap_or_else(|err| {
+            eprintln!(
+                "Error: failed to read [{}]: {}",
+                schema_file.to_str().unwrap(),
+                err
+            );
+            process::exit(1);
+        });
+        let formatted = Formatter::format(&source).unwrap_or_else(|err| {
+            eprintln!(
+                "Error: failed to parse [{}]:\n{}",
+                schema_file.to_str().unwrap(),
+                err
+            );
+            process::exit(1);
+        });
+        if formatted == source {
+            continue;
+        }
+        if check {
+            println!("{}", schema_file.to_str().unwrap());
+            unformatted = true;
+        } else if let Err(err) = fs::write(schema_file, formatted) {
+            eprintln!(
+                "Error: failed to write [{}]: {}",
+                schema_file.to_str().unwrap(),
+                err
+            );
+            process::exit(1);
+        }
+    }
+    if unformatted {
+        process::exit(1);
+    }
+}
+
+fn lint(schema_file: &path::Path, config: &config::LintConfig) {
+    let ast = parse_schema_file(schema_file);
+    let mut linter = Linter::new();
+    for (rule, level) in &config.levels {
+        linter.set_level(*rule, *level);
+    }
+    if let Some(size) = config.large_struct_size {
+        linter.set_large_struct_size(size);
+    }
+    let lints = linter.lint(&ast);
+    for lint in &lints {
+        println!("{}", lint);
+    }
+    if lints.iter().any(|lint| lint.level() == LintLevel::Deny) {
+        process::exit(1);
+    }
+}
+
+fn bundle(schema_file: &path::Path, config: &config::BundleConfig) {
+    let ast = parse_schema_file(schema_file);
+    let mut bundler = Bundler::new();
+    for root in &config.roots {
+        bundler.add_root(root);
+    }
+    let bundled = bundler.bundle(&ast).unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    });
+    if let Some(ref output) = config.output {
+        if let Err(err) = fs::write(output, bundled) {
+            eprintln!(
+                "Error: failed to write [{}]: {}",
+                output.to_str().unwrap(),
+                err
+            );
+            process::exit(1);
+        }
+    } else {
+        print!("{}", bundled);
+    }
+}
+
 fn main() {
-    let config = config::build_commandline();
-    let default_format = IntermediateFormat::JSON;
+    match config::build_commandline() {
+        config::AppConfig::Compile {
+            schema_files,
+            output_config,
+            compile_config,
+        } => {
+            check_schema_files(&schema_files, &compile_config.import_paths, None);
+            generate(&schema_files, &output_config, &compile_config);
+        }
+        config::AppConfig::Explain {
+            schema_file,
+            explain_config,
+        } => {
+            explain(schema_file.as_path(), &explain_config);
+        }
+        config::AppConfig::Format {
+            schema_files,
+            check,
+        } => {
+            format(&schema_files, check);
+        }
+        config::AppConfig::Lint {
+            schema_file,
+            lint_config,
+        } => {
+            lint(schema_file.as_path(), &lint_config);
+        }
+        config::AppConfig::Bundle {
+            schema_file,
+            bundle_config,
+        } => {
+            bundle(schema_file.as_path(), &bundle_config);
+        }
+        config::AppConfig::Build {
+            schema_files,
+            import_paths,
+            linter,
+            targets,
+        } => {
+            check_schema_files(&schema_files, &import_paths, Some(&linter));
+            for (output_config, compile_config) in &targets {
+                generate(&schema_files, output_config, compile_config);
+            }
+        }
+        config::AppConfig::IrSchema => {
+            print!("{}", Ir::json_schema());
+        }
+    }
+}
+
+/// Checks all schema files, and lints them if the linter is provided, before anything is
+/// generated.
+fn check_schema_files(
+    schema_files: &[path::PathBuf],
+    import_paths: &[path::PathBuf],
+    linter: Option<&Linter>,
+) {
+    let mut failed = false;
+    for schema_file in schema_files {
+        let result = catch_panic(|| {
+            let ast = Parser::parse_with_import_paths(schema_file, import_paths);
+            Ok(linter.map(|linter| linter.lint(&ast)).unwrap_or_default())
+        });
+        match result {
+            Ok(lints) => {
+                for lint in &lints {
+                    println!("{}", lint);
+                }
+                if lints.iter().any(|lint| lint.level() == LintLevel::Deny) {
+                    failed = true;
+                }
+            }
+            Err(err) => {
+                eprintln!("Error: [{}] {}", schema_file.display(), err);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// Generates the code for all schema files, and the umbrella file if it's required.
+fn generate(
+    schema_files: &[path::PathBuf],
+    output_config: &config::OutputConfig,
+    compile_config: &config::CompileConfig,
+) {
+    if let Some(ref output_dir) = compile_config.output_dir {
+        if let Err(err) = fs::create_dir_all(output_dir) {
+            eprintln!(
+                "Error: failed to create [{}]: {}",
+                output_dir.display(),
+                err
+            );
+            process::exit(1);
+        }
+    }
+    for schema_file in schema_files {
+        if let Err(err) = catch_panic(|| compile(schema_file, output_config, compile_config)) {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+    }
+    if let (Some(umbrella), Some(output_dir)) =
+        (&compile_config.umbrella, &compile_config.output_dir)
+    {
+        let result = project::write_umbrella(
+            umbrella.language,
+            &umbrella.file_name,
+            schema_files,
+            output_dir,
+        );
+        if let Err(err) = result {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Runs the function, and turns a panic into an error.
+///
+/// The panics, e.g. the errors in the schema files, are reported as errors, so the panic hook
+/// is silenced while the function runs, and restored after it.
+fn catch_panic<T, F: FnOnce() -> Result<T, String>>(f: F) -> Result<T, String> {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(f));
+    panic::set_hook(hook);
+    result.unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| (*message).to_owned())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown error".to_owned());
+        Err(message)
+    })
+}
+
+fn compile(
+    schema_file: &path::Path,
+    output_config: &config::OutputConfig,
+    compile_config: &config::CompileConfig,
+) -> Result<(), String> {
+    let only_types = compile_config
+        .only_types
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
     let mut compiler = Compiler::new();
-    match config.output_config {
+    match *output_config {
         config::OutputConfig::Plugin(ref plugin_file) => {
-            let output = process::Command::new(plugin_file.as_path())
-                .arg("--format")
-                .output()
-                .expect("Error: failed to execute plugin process");
-            let format = if output.status.success() {
-                str::from_utf8(&output.stdout[..])
-                    .ok()
-                    .and_then(|s| IntermediateFormat::try_from(s).ok())
-                    .unwrap_or(default_format)
-            } else {
-                default_format
-            };
-            if let Ok(child) = process::Command::new(plugin_file.as_path())
-                .stdin(process::Stdio::piped())
-                .spawn()
-            {
+            if let Some(capabilities) = plugin_capabilities(plugin_file) {
+                let request = plugin_request(schema_file, &capabilities, compile_config)?;
+                let child = process::Command::new(plugin_file.as_path())
+                    .arg("--protocol")
+                    .arg(request.protocol_version().to_string())
+                    .stdin(process::Stdio::piped())
+                    .stdout(process::Stdio::piped())
+                    .spawn()
+                    .map_err(|err| format!("failed to spawn plugin process: {}", err))?;
                 compiler
-                    .generate_intermediate(format)
-                    .output_plugin_process(child);
+                    .intermediate_format_version(
+                        capabilities.format_version().min(Ir::FORMAT_VERSION),
+                    )
+                    .output_plugin(child, request);
             } else {
-                eprintln!("Error: failed to spawn plugin process");
-                process::exit(1);
+                if !compile_config.plugin_options.is_empty() {
+                    return Err("the plugin doesn't support options".to_owned());
+                }
+                if compile_config.output_dir.is_some() {
+                    return Err("the plugin doesn't support the output directory".to_owned());
+                }
+                spawn_legacy_plugin(&mut compiler, plugin_file)?;
             }
         }
         config::OutputConfig::Output(format) => {
             compiler.generate_intermediate(format);
+            if let Some(ref output_dir) = compile_config.output_dir {
+                compiler.output_dir(output_dir);
+            }
+        }
+        config::OutputConfig::Templates(ref templates) => {
+            for template in templates {
+                let mut compiler = Compiler::new();
+                compiler.generate_with(Box::new(template.clone()));
+                if let Some(ref output_dir) = compile_config.output_dir {
+                    compiler.output_dir(output_dir);
+                }
+                compiler
+                    .input_schema_file(schema_file)
+                    .import_paths(&compile_config.import_paths)
+                    .only_types(&only_types)
+                    .run()?;
+            }
+            return Ok(());
         }
     };
     compiler
-        .input_schema_file(config.schema_file.as_path())
+        .input_schema_file(schema_file)
+        .import_paths(&compile_config.import_paths)
+        .only_types(&only_types)
+        .intermediate_layouts(compile_config.ir_layouts)
         .run()
-        .unwrap()
+}
+
+/// Gets the capabilities of the plugin, or `None` if the plugin only supports the legacy
+/// protocol.
+fn plugin_capabilities(plugin_file: &path::Path) -> Option<Capabilities> {
+    let output = process::Command::new(plugin_file)
+        .arg("--capabilities")
+        .stderr(process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Capabilities::from_json(&output.stdout).ok()
+}
+
+fn plugin_request(
+    schema_file: &path::Path,
+    capabilities: &Capabilities,
+    config: &config::CompileConfig,
+) -> Result<Request, String> {
+    let format = capabilities
+        .formats()
+        .first()
+        .copied()
+        .ok_or("the plugin doesn't support any format of the intermediate data")?;
+    for name in config.plugin_options.keys() {
+        if !capabilities
+            .options()
+            .iter()
+            .any(|option| option.name() == name)
+        {
+            let supported = capabilities
+                .options()
+                .iter()
+                .map(|option| option.name())
+                .collect::<Vec<_>>();
+            return Err(format!(
+                "the plugin doesn't support option [{}] (supported: [{}])",
+                name,
+                supported.join(", ")
+            ));
+        }
+    }
+    Ok(Request {
+        protocol_version: capabilities.protocol_version().min(PROTOCOL_VERSION),
+        format,
+        schema_file: schema_file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap()
+            .to_owned(),
+        output_dir: config
+            .output_dir
+            .as_ref()
+            .map(|dir| dir.to_string_lossy().into_owned()),
+        options: config.plugin_options.clone(),
+    })
+}
+
+fn spawn_legacy_plugin(compiler: &mut Compiler, plugin_file: &path::Path) -> Result<(), String> {
+    let default_format = IntermediateFormat::JSON;
+    let output = process::Command::new(plugin_file)
+        .arg("--format")
+        .output()
+        .map_err(|err| format!("failed to execute plugin process: {}", err))?;
+    let format = if output.status.success() {
+        str::from_utf8(&output.stdout[..])
+            .ok()
+            .and_then(|s| IntermediateFormat::try_from(s.trim()).ok())
+            .unwrap_or(default_format)
+    } else {
+        default_format
+    };
+    let child = process::Command::new(plugin_file)
+        .stdin(process::Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("failed to spawn plugin process: {}", err))?;
+    compiler
+        .generate_intermediate(format)
+        .output_plugin_process(child);
+    Ok(())
 }
@@ -1,13 +1,18 @@
 use std::{
     io::{self, Read},
-    process,
+    path, process,
 };
 
-use molecule_codegen::{Compiler, IntermediateFormat, Language};
+use molecule_codegen::{
+    plugin::{Capabilities, Request, Response, Severity},
+    Compiler, GeneratorOptions, IntermediateFormat, Language,
+};
 
 pub(crate) enum AppAction {
     DisplayFormat,
+    DisplayCapabilities,
     ProcessIntermediate(Vec<u8>),
+    ProcessRequest(Request, Vec<u8>),
 }
 
 pub struct AppConfig {
@@ -29,6 +34,19 @@ pub fn build_commandline(lang: Language, format: IntermediateFormat) -> AppConfi
                 .help("Output the supported format for the intermediate data.")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            clap::Arg::new("capabilities")
+                .long("capabilities")
+                .help("Output the capabilities of the plugin for the plugin protocol.")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            clap::Arg::new("protocol")
+                .long("protocol")
+                .help("Read a request of the plugin protocol from stdin, instead of the intermediate data.")
+                .value_parser(clap::value_parser!(usize))
+                .action(clap::ArgAction::Set),
+        )
         .get_matches();
     AppConfig::from((lang, format, matches))
 }
@@ -38,6 +56,16 @@ impl From<RawAppConfig> for AppConfig {
         let (lang, format, matches) = input;
         let action = if matches.get_flag("format") {
             AppAction::DisplayFormat
+        } else if matches.get_flag("capabilities") {
+            AppAction::DisplayCapabilities
+        } else if matches.get_one::<usize>("protocol").is_some() {
+            match Request::read(&mut io::stdin().lock()) {
+                Ok((request, data)) => AppAction::ProcessRequest(request, data),
+                Err(error) => {
+                    eprintln!("Error: {}", error);
+                    process::exit(1);
+                }
+            }
         } else {
             let mut input = Vec::new();
             if io::stdin().read_to_end(&mut input).is_err() {
@@ -60,13 +88,77 @@ impl AppConfig {
             AppAction::DisplayFormat => {
                 println!("{}", self.format);
             }
+            AppAction::DisplayCapabilities => {
+                let mut formats = vec![self.format];
+                for format in &[
+                    IntermediateFormat::JSON,
+                    IntermediateFormat::YAML,
+                    IntermediateFormat::Molecule,
+                ] {
+                    if !formats.contains(format) {
+                        formats.push(*format);
+                    }
+                }
+                let mut capabilities = Capabilities::new(&formats);
+                for (name, description) in self.lang.options() {
+                    capabilities.add_option(name, description);
+                }
+                println!("{}", capabilities.to_json());
+            }
+            AppAction::ProcessRequest(ref request, ref data) => {
+                println!("{}", process_request(self.lang, request, data).to_json());
+            }
             AppAction::ProcessIntermediate(ref input) => {
-                Compiler::new()
+                if let Err(error) = Compiler::new()
                     .generate_code(self.lang)
                     .input_intermediate(self.format, input.to_owned())
                     .run()
-                    .unwrap();
+                {
+                    eprintln!("Error: {}", error);
+                    process::exit(1);
+                }
             }
         }
     }
 }
+
+fn process_request(lang: Language, request: &Request, data: &[u8]) -> Response {
+    let mut response = Response::default();
+    let mut options = GeneratorOptions::new();
+    for (name, value) in request.options() {
+        let result = if lang.options().iter().any(|(option, _)| option == name) {
+            options.set(name, value).map(|_| ())
+        } else {
+            Err(format!("unknown option `{}`", name))
+        };
+        if let Err(message) = result {
+            response.add_diagnostic(Severity::Error, &message, None);
+        }
+    }
+    if response.has_errors() {
+        return response;
+    }
+    let ast = match request.format().recover(data) {
+        Ok(ast) => ast,
+        Err(error) => {
+            response.add_diagnostic(Severity::Error, &error, None);
+            return response;
+        }
+    };
+    let name = path::Path::new(request.schema_file())
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or("generated");
+    match lang.generate_files(name, &ast, &options) {
+        Ok(files) => {
+            for (file_name, content) in files {
+                response.add_file(&file_name, String::from_utf8(content).unwrap());
+            }
+        }
+        Err(error) => {
+            let message = format!("failed to generate the code: {}", error);
+            response.add_diagnostic(Severity::Error, &message, None);
+        }
+    }
+    response
+}
@@ -0,0 +1,371 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    ops::Range,
+    panic,
+    path::{Component, Path, PathBuf},
+};
+
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, Hover, HoverContents,
+    Location, MarkupContent, MarkupKind, NumberOrString, Position, TextEdit, Url, WorkspaceEdit,
+};
+use molecule_codegen::{
+    ast::{Ast, HasName as _},
+    linter::{Lint, LintLevel},
+    outline::Import,
+    Linter, Outline, ParseError, Parser,
+};
+
+use crate::line_index::LineIndex;
+
+const SOURCE: &str = "moleculec";
+
+/// The schema files which are opened in the editor.
+///
+/// The files which are not opened are read from the disk. The scope of the references is the
+/// opened files and all files which are imported by them.
+#[derive(Default)]
+pub(crate) struct Analysis {
+    documents: HashMap<PathBuf, String>,
+}
+
+impl Analysis {
+    pub(crate) fn open(&mut self, path: PathBuf, text: String) {
+        self.documents.insert(normalize(&path), text);
+    }
+
+    pub(crate) fn close(&mut self, path: &Path) {
+        self.documents.remove(&normalize(path));
+    }
+
+    pub(crate) fn documents(&self) -> impl Iterator<Item = &PathBuf> {
+        self.documents.keys()
+    }
+
+    fn source(&self, path: &Path) -> Option<String> {
+        self.documents
+            .get(path)
+            .cloned()
+            .or_else(|| fs::read_to_string(path).ok())
+    }
+
+    fn outline(&self, path: &Path) -> Option<Outline> {
+        self.source(path)
+            .and_then(|source| Outline::parse(&source).ok())
+    }
+
+    /// Returns the file itself and all files which are imported by it, directly or indirectly.
+    fn import_closure(&self, path: &Path) -> Vec<PathBuf> {
+        let mut files = vec![normalize(path)];
+        let mut index = 0;
+        while index < files.len() {
+            if let Some(outline) = self.outline(&files[index]) {
+                for import in outline.imports() {
+                    let file = normalize(&import.file_path(&files[index]));
+                    if !files.contains(&file) {
+                        files.push(file);
+                    }
+                }
+            }
+            index += 1;
+        }
+        files
+    }
+
+    /// Finds the file which declares the type, in the files which are visible from `path`.
+    fn find_decl(&self, path: &Path, name: &str) -> Option<(PathBuf, Range<usize>)> {
+        self.import_closure(path).into_iter().find_map(|file| {
+            self.outline(&file)
+                .and_then(|outline| outline.decl(name).map(|decl| decl.name_range().clone()))
+                .map(|range| (file, range))
+        })
+    }
+
+    fn location(&self, path: &Path, range: &Range<usize>) -> Option<Location> {
+        let source = self.source(path)?;
+        let uri = Url::from_file_path(path).ok()?;
+        Some(Location::new(uri, LineIndex::new(&source).range(range)))
+    }
+
+    fn symbol_at(&self, path: &Path, position: Position) -> Option<String> {
+        let source = self.source(path)?;
+        let offset = LineIndex::new(&source).offset(position);
+        Outline::parse(&source)
+            .ok()?
+            .symbol_at(offset)
+            .map(ToOwned::to_owned)
+    }
+
+    pub(crate) fn diagnostics(&self, path: &Path) -> Vec<Diagnostic> {
+        let source = if let Some(source) = self.source(path) {
+            source
+        } else {
+            return Vec::new();
+        };
+        let index = LineIndex::new(&source);
+        let outline = match Outline::parse(&source) {
+            Ok(outline) => outline,
+            Err(err) => {
+                return vec![diagnostic(
+                    index.range(err.range()),
+                    DiagnosticSeverity::ERROR,
+                    None,
+                    format!("syntax error: {}", err.message()),
+                )];
+            }
+        };
+        match self.verify(path) {
+            Ok(ast) => Linter::new()
+                .lint(&ast)
+                .iter()
+                .map(|lint| {
+                    let severity = if lint.level() == LintLevel::Deny {
+                        DiagnosticSeverity::ERROR
+                    } else {
+                        DiagnosticSeverity::WARNING
+                    };
+                    diagnostic(
+                        index.range(&lint_range(&outline, lint)),
+                        severity,
+                        Some(lint.rule().name()),
+                        format!("`{}`: {}", lint.decl(), lint.message()),
+                    )
+                })
+                .collect(),
+            Err(err) => vec![diagnostic(
+                index.range(&error_range(path, &outline, &err)),
+                DiagnosticSeverity::ERROR,
+                None,
+                err.message().to_owned(),
+            )],
+        }
+    }
+
+    /// Verifies the schema by the compiler, the opened files are used instead of the files on
+    /// the disk.
+    fn verify(&self, path: &Path) -> Result<Ast, ParseError> {
+        let sources = |file: &Path| self.documents.get(&normalize(file)).cloned();
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let result = Parser::try_parse_with_sources(&path, &sources);
+        panic::set_hook(hook);
+        result
+    }
+
+    pub(crate) fn definition(&self, path: &Path, position: Position) -> Option<Location> {
+        let name = self.symbol_at(path, position)?;
+        let (file, range) = self.find_decl(path, &name)?;
+        self.location(&file, &range)
+    }
+
+    /// Returns the ranges of all uses of the type at the position, grouped by files.
+    fn occurrences(
+        &self,
+        path: &Path,
+        position: Position,
+        include_declaration: bool,
+    ) -> Option<Vec<(PathBuf, Range<usize>)>> {
+        let name = self.symbol_at(path, position)?;
+        let (decl_file, decl_range) = self.find_decl(path, &name)?;
+        let mut files = Vec::new();
+        for document in self
+            .documents
+            .keys()
+            .map(PathBuf::as_path)
+            .chain(Some(path))
+        {
+            for file in self.import_closure(document) {
+                if !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+        files.sort();
+        let mut ranges = Vec::new();
+        if include_declaration {
+            ranges.push((decl_file.clone(), decl_range));
+        }
+        for file in files {
+            let outline = if let Some(outline) = self.outline(&file) {
+                outline
+            } else {
+                continue;
+            };
+            let uses = outline
+                .references()
+                .iter()
+                .filter(|reference| reference.name() == name)
+                .map(|reference| reference.range().clone())
+                .collect::<Vec<_>>();
+            if uses.is_empty()
+                || self.find_decl(&file, &name).map(|(file, _)| file).as_ref() != Some(&decl_file)
+            {
+                continue;
+            }
+            ranges.extend(uses.into_iter().map(|range| (file.clone(), range)));
+        }
+        Some(ranges)
+    }
+
+    pub(crate) fn references(
+        &self,
+        path: &Path,
+        position: Position,
+        include_declaration: bool,
+    ) -> Vec<Location> {
+        self.occurrences(path, position, include_declaration)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|(file, range)| self.location(file, range))
+            .collect()
+    }
+
+    pub(crate) fn hover(&self, path: &Path, position: Position) -> Option<Hover> {
+        let name = self.symbol_at(path, position)?;
+        let ast = self.verify(path).ok()?;
+        let decl = ast.decls().iter().find(|decl| decl.name() == name)?;
+        let contents = MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: format!("```text\n{}```", decl.layout()),
+        };
+        Some(Hover {
+            contents: HoverContents::Markup(contents),
+            range: None,
+        })
+    }
+
+    pub(crate) fn completion(&self, path: &Path) -> Vec<CompletionItem> {
+        let mut names = HashSet::new();
+        let mut items = vec![CompletionItem {
+            label: "byte".to_owned(),
+            kind: Some(CompletionItemKind::KEYWORD),
+            ..Default::default()
+        }];
+        for file in self.import_closure(path) {
+            let outline = if let Some(outline) = self.outline(&file) {
+                outline
+            } else {
+                continue;
+            };
+            for decl in outline.decls() {
+                if names.insert(decl.name().to_owned()) {
+                    items.push(CompletionItem {
+                        label: decl.name().to_owned(),
+                        kind: Some(CompletionItemKind::CLASS),
+                        detail: Some(decl.keyword().to_owned()),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+        items
+    }
+
+    pub(crate) fn rename(
+        &self,
+        path: &Path,
+        position: Position,
+        new_name: &str,
+    ) -> Result<Option<WorkspaceEdit>, String> {
+        if !is_identifier(new_name) {
+            return Err(format!("`{}` is not a valid type name", new_name));
+        }
+        let occurrences = if let Some(occurrences) = self.occurrences(path, position, true) {
+            occurrences
+        } else {
+            return Ok(None);
+        };
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for (file, range) in &occurrences {
+            if self.find_decl(file, new_name).is_some() {
+                return Err(format!("the name `{}` is used more than once", new_name));
+            }
+            if let Some(location) = self.location(file, range) {
+                changes
+                    .entry(location.uri)
+                    .or_default()
+                    .push(TextEdit::new(location.range, new_name.to_owned()));
+            }
+        }
+        Ok(Some(WorkspaceEdit::new(changes)))
+    }
+}
+
+fn diagnostic(
+    range: lsp_types::Range,
+    severity: DiagnosticSeverity,
+    code: Option<&str>,
+    message: String,
+) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(severity),
+        code: code.map(|code| NumberOrString::String(code.to_owned())),
+        source: Some(SOURCE.to_owned()),
+        message,
+        ..Default::default()
+    }
+}
+
+fn import_path(import: &Import) -> String {
+    let mut path = "../".repeat(import.path_supers());
+    for p in import.paths() {
+        path.push_str(p);
+        path.push('/');
+    }
+    path.push_str(import.name());
+    path
+}
+
+fn lint_range(outline: &Outline, lint: &Lint) -> Range<usize> {
+    if let Some(decl) = outline.decl(lint.decl()) {
+        return decl.name_range().clone();
+    }
+    outline
+        .imports()
+        .iter()
+        .find(|import| import_path(import) == lint.decl())
+        .or_else(|| outline.imports().first())
+        .map(|import| import.range().clone())
+        .unwrap_or(0..0)
+}
+
+/// The range of the error if it's found in the file, otherwise the range of the first import,
+/// since the error is in the imported files.
+fn error_range(path: &Path, outline: &Outline, err: &ParseError) -> Range<usize> {
+    if let Some(span) = err.span() {
+        if normalize(span.file()) == normalize(path) {
+            return span.range().clone();
+        }
+    }
+    outline
+        .imports()
+        .first()
+        .map(|import| import.range().clone())
+        .unwrap_or(0..0)
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|ch| ch.is_ascii_alphabetic())
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        && name != "byte"
+}
+
+/// Removes the `.` and `..` in the path without touching the file system.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
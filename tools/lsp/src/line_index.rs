@@ -0,0 +1,58 @@
+use std::ops::Range;
+
+use lsp_types::Position;
+
+/// Converts between the byte offsets and the LSP positions, whose characters are counted in
+/// UTF-16 code units.
+pub(crate) struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(pos, _)| pos + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    pub(crate) fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        let character = self.source[start..offset]
+            .chars()
+            .map(char::len_utf16)
+            .sum::<usize>();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub(crate) fn range(&self, range: &Range<usize>) -> lsp_types::Range {
+        lsp_types::Range::new(self.position(range.start), self.position(range.end))
+    }
+
+    pub(crate) fn offset(&self, position: Position) -> usize {
+        let line = position.line as usize;
+        if line >= self.line_starts.len() {
+            return self.source.len();
+        }
+        let start = self.line_starts[line];
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .copied()
+            .unwrap_or(self.source.len());
+        let mut units = 0;
+        for (pos, ch) in self.source[start..end].char_indices() {
+            if units >= position.character as usize || ch == '\n' {
+                return start + pos;
+            }
+            units += ch.len_utf16();
+        }
+        end
+    }
+}
@@ -0,0 +1,210 @@
+use std::{error::Error, path::PathBuf};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+        PublishDiagnostics,
+    },
+    request::{Completion, GotoDefinition, HoverRequest, References, Rename, Request as _},
+    CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, GotoDefinitionParams,
+    GotoDefinitionResponse, HoverParams, HoverProviderCapability, OneOf, PublishDiagnosticsParams,
+    ReferenceParams, RenameParams, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Url,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+mod analysis;
+mod line_index;
+
+use analysis::{normalize, Analysis};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+struct Server {
+    connection: Connection,
+    analysis: Analysis,
+}
+
+fn main() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        rename_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+    let mut server = Server {
+        connection,
+        analysis: Analysis::default(),
+    };
+    server.run()?;
+    drop(server);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn file_path(uri: &Url) -> std::result::Result<PathBuf, String> {
+    uri.to_file_path()
+        .map(|path| normalize(&path))
+        .map_err(|_| format!("`{}` is not a file", uri))
+}
+
+impl Server {
+    fn run(&mut self) -> Result<()> {
+        while let Ok(message) = self.connection.receiver.recv() {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    self.handle_request(request)?;
+                }
+                Message::Notification(notification) => self.handle_notification(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Result<()> {
+        let analysis = &self.analysis;
+        let response = match request.method.as_str() {
+            GotoDefinition::METHOD => respond(request, |params: GotoDefinitionParams| {
+                let position = params.text_document_position_params;
+                let path = file_path(&position.text_document.uri)?;
+                Ok(analysis
+                    .definition(&path, position.position)
+                    .map(GotoDefinitionResponse::Scalar))
+            }),
+            References::METHOD => respond(request, |params: ReferenceParams| {
+                let position = params.text_document_position;
+                let path = file_path(&position.text_document.uri)?;
+                Ok(analysis.references(
+                    &path,
+                    position.position,
+                    params.context.include_declaration,
+                ))
+            }),
+            HoverRequest::METHOD => respond(request, |params: HoverParams| {
+                let position = params.text_document_position_params;
+                let path = file_path(&position.text_document.uri)?;
+                Ok(analysis.hover(&path, position.position))
+            }),
+            Completion::METHOD => respond(request, |params: CompletionParams| {
+                let path = file_path(&params.text_document_position.text_document.uri)?;
+                Ok(CompletionResponse::Array(analysis.completion(&path)))
+            }),
+            Rename::METHOD => respond(request, |params: RenameParams| {
+                let position = params.text_document_position;
+                let path = file_path(&position.text_document.uri)?;
+                analysis.rename(&path, position.position, &params.new_name)
+            }),
+            _ => Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unsupported request `{}`", request.method),
+            ),
+        };
+        self.connection.sender.send(response.into())?;
+        Ok(())
+    }
+
+    /// Handles a notification, an invalid one is skipped since it has no response.
+    fn handle_notification(&mut self, notification: Notification) -> Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                if let Some((params, path)) =
+                    document(notification, |params: &DidOpenTextDocumentParams| {
+                        &params.text_document.uri
+                    })
+                {
+                    self.analysis.open(path, params.text_document.text);
+                    self.publish_diagnostics()?;
+                }
+            }
+            DidChangeTextDocument::METHOD => {
+                if let Some((mut params, path)) =
+                    document(notification, |params: &DidChangeTextDocumentParams| {
+                        &params.text_document.uri
+                    })
+                {
+                    if let Some(change) = params.content_changes.pop() {
+                        self.analysis.open(path, change.text);
+                        self.publish_diagnostics()?;
+                    }
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                if let Some((params, path)) =
+                    document(notification, |params: &DidCloseTextDocumentParams| {
+                        &params.text_document.uri
+                    })
+                {
+                    self.analysis.close(&path);
+                    self.send_diagnostics(params.text_document.uri, Vec::new())?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Publishes the diagnostics of all opened files, since a file could be imported by others.
+    fn publish_diagnostics(&self) -> Result<()> {
+        for path in self.analysis.documents() {
+            if let Ok(uri) = Url::from_file_path(path) {
+                self.send_diagnostics(uri, self.analysis.diagnostics(path))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn send_diagnostics(&self, uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) -> Result<()> {
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_owned(), params);
+        self.connection.sender.send(notification.into())?;
+        Ok(())
+    }
+}
+
+/// Parses the params of a notification about a document.
+///
+/// Returns `None` if the params are invalid, or if the document isn't a file, e.g. an
+/// `untitled:` buffer.
+fn document<P, F>(notification: Notification, uri: F) -> Option<(P, PathBuf)>
+where
+    P: DeserializeOwned,
+    F: FnOnce(&P) -> &Url,
+{
+    let params = match serde_json::from_value(notification.params) {
+        Ok(params) => params,
+        Err(err) => {
+            eprintln!("invalid params of `{}`: {}", notification.method, err);
+            return None;
+        }
+    };
+    let path = file_path(uri(&params)).ok()?;
+    Some((params, path))
+}
+
+fn respond<P, R, F>(request: Request, handler: F) -> Response
+where
+    P: DeserializeOwned,
+    R: Serialize,
+    F: FnOnce(P) -> std::result::Result<R, String>,
+{
+    let id: RequestId = request.id;
+    match serde_json::from_value(request.params) {
+        Ok(params) => match handler(params) {
+            Ok(result) => Response::new_ok(id, result),
+            Err(message) => Response::new_err(id, ErrorCode::InvalidParams as i32, message),
+        },
+        Err(err) => Response::new_err(id, ErrorCode::InvalidParams as i32, err.to_string()),
+    }
+}
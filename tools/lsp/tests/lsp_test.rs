@@ -0,0 +1,311 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use serde_json::{json, Value};
+
+/// A scripted LSP client, which talks to the server over stdio.
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_moleculec-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut client = Self {
+            child,
+            stdin,
+            stdout,
+            next_id: 0,
+        };
+        let result = client.request("initialize", json!({ "capabilities": {} }));
+        assert_eq!(result["capabilities"]["renameProvider"], json!(true));
+        client.notify("initialized", json!({}));
+        client
+    }
+
+    fn send(&mut self, message: Value) {
+        let content = message.to_string();
+        write!(
+            self.stdin,
+            "Content-Length: {}\r\n\r\n{}",
+            content.len(),
+            content
+        )
+        .unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                length = value.parse().unwrap();
+            }
+        }
+        let mut content = vec![0; length];
+        self.stdout.read_exact(&mut content).unwrap();
+        serde_json::from_slice(&content).unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn try_request(&mut self, method: &str, params: Value) -> Result<Value, Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let mut message = self.receive();
+            if message["id"] == json!(id) {
+                if let Some(error) = message.get("error") {
+                    return Err(error.clone());
+                }
+                return Ok(message["result"].take());
+            }
+        }
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.try_request(method, params).unwrap()
+    }
+
+    /// Waits for the diagnostics of the file.
+    fn diagnostics(&mut self, uri: &str) -> Vec<Value> {
+        loop {
+            let mut message = self.receive();
+            if message["method"] == "textDocument/publishDiagnostics"
+                && message["params"]["uri"] == uri
+            {
+                return message["params"]["diagnostics"]
+                    .take()
+                    .as_array()
+                    .unwrap()
+                    .clone();
+            }
+        }
+    }
+
+    fn open(&mut self, uri: &str, text: &str) {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": "molecule", "version": 1, "text": text }
+            }),
+        );
+    }
+
+    fn change(&mut self, uri: &str, version: u32, text: &str) {
+        self.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": version },
+                "contentChanges": [{ "text": text }]
+            }),
+        );
+    }
+
+    fn shutdown(mut self) {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+fn position(uri: &str, line: u32, character: u32) -> Value {
+    json!({
+        "textDocument": { "uri": uri },
+        "position": { "line": line, "character": character }
+    })
+}
+
+fn uri_of(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
+
+const TYPES: &str = "array Uint32 [byte; 4];
+struct Point {
+    x: Uint32,
+    y: Uint32,
+}
+";
+
+const SHAPES: &str = "import types;
+
+vector Points <Point>;
+table Shape {
+    points: Points,
+    center: Point,
+}
+";
+
+#[test]
+fn test_language_server() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("lsp-test");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("types.mol"), TYPES).unwrap();
+    fs::write(dir.join("shapes.mol"), SHAPES).unwrap();
+    let types = uri_of(&dir.join("types.mol"));
+    let shapes = uri_of(&dir.join("shapes.mol"));
+
+    let mut client = Client::start();
+    client.open(&shapes, SHAPES);
+    assert!(client.diagnostics(&shapes).is_empty());
+
+    // Diagnostics as you type.
+    client.change(&shapes, 2, "vector Points <Point>");
+    let diagnostics = client.diagnostics(&shapes);
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("syntax error"));
+    client.change(
+        &shapes,
+        3,
+        &SHAPES.replace("center: Point", "center: Circle"),
+    );
+    let diagnostics = client.diagnostics(&shapes);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0]["range"]["start"],
+        json!({ "line": 3, "character": 6 })
+    );
+    client.change(
+        &shapes,
+        4,
+        &SHAPES
+            .replace("<Point>", "<Uint32>")
+            .replace("center: Point", "center: Points"),
+    );
+    let diagnostics = client.diagnostics(&shapes);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["code"], "unused-declaration");
+    assert_eq!(diagnostics[0]["severity"], 2);
+    client.change(&shapes, 5, SHAPES);
+    assert!(client.diagnostics(&shapes).is_empty());
+
+    // The unsaved content of the imported file is verified.
+    client.open(&types, &TYPES.replace("struct Point", "struct Vec2"));
+    let diagnostics = client.diagnostics(&shapes);
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("unable to be completed"));
+    assert_eq!(
+        diagnostics[0]["range"],
+        json!({ "start": { "line": 2, "character": 7 }, "end": { "line": 2, "character": 13 } })
+    );
+    client.change(&types, 2, TYPES);
+    assert!(client.diagnostics(&shapes).is_empty());
+
+    // Go to the definition in the imported file.
+    let location = client.request("textDocument/definition", position(&shapes, 5, 14));
+    assert_eq!(location["uri"], types);
+    assert_eq!(
+        location["range"],
+        json!({ "start": { "line": 1, "character": 7 }, "end": { "line": 1, "character": 12 } })
+    );
+
+    // Find the references in all files.
+    let mut params = position(&shapes, 5, 14);
+    params["context"] = json!({ "includeDeclaration": true });
+    let locations = client.request("textDocument/references", params);
+    let mut locations = locations
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|location| {
+            (
+                location["uri"] == types,
+                location["range"]["start"]["line"].as_u64().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    locations.sort();
+    assert_eq!(locations, vec![(false, 2), (false, 5), (true, 1)]);
+
+    // Hover shows the encoding and the size.
+    let hover = client.request("textDocument/hover", position(&shapes, 5, 14));
+    let value = hover["contents"]["value"].as_str().unwrap();
+    assert!(value.contains("struct Point"));
+    assert!(value.contains("size: 8 bytes"));
+    let hover = client.request("textDocument/hover", position(&shapes, 3, 7));
+    assert!(hover["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .contains("table Shape"));
+
+    // Complete the type names.
+    let items = client.request("textDocument/completion", position(&shapes, 5, 12));
+    let mut labels = items
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap().to_owned())
+        .collect::<Vec<_>>();
+    labels.sort();
+    assert_eq!(labels, vec!["Point", "Points", "Shape", "Uint32", "byte"]);
+
+    // Rename across files.
+    let mut params = position(&shapes, 2, 16);
+    params["newName"] = json!("Vec2");
+    let edit = client.request("textDocument/rename", params);
+    assert_eq!(edit["changes"][&shapes].as_array().unwrap().len(), 2);
+    assert_eq!(edit["changes"][&types].as_array().unwrap().len(), 1);
+    let mut params = position(&shapes, 2, 16);
+    params["newName"] = json!("Shape");
+    assert!(client.try_request("textDocument/rename", params).is_err());
+    let mut params = position(&shapes, 2, 16);
+    params["newName"] = json!("2d");
+    assert!(client.try_request("textDocument/rename", params).is_err());
+
+    client.shutdown();
+}
+
+#[test]
+fn test_invalid_notifications() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("lsp-test-invalid");
+    fs::create_dir_all(&dir).unwrap();
+    let types = uri_of(&dir.join("types.mol"));
+    let untitled = "untitled:Untitled-1";
+
+    let mut client = Client::start();
+    // Neither a document which isn't a file nor invalid params stop the server.
+    client.open(untitled, TYPES);
+    client.change(untitled, 2, TYPES);
+    client.notify("textDocument/didOpen", json!({ "textDocument": {} }));
+    assert!(client
+        .try_request("textDocument/hover", position(untitled, 1, 8))
+        .is_err());
+
+    client.open(&types, TYPES);
+    assert!(client.diagnostics(&types).is_empty());
+    let hover = client.request("textDocument/hover", position(&types, 1, 8));
+    assert!(hover["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .contains("struct Point"));
+
+    client.shutdown();
+}